@@ -0,0 +1,242 @@
+//! Diagnostic Aggregation
+//!
+//! Keeps a noisy pattern from flooding the editor with thousands of
+//! identical diagnostics. Above a threshold, all occurrences of a pattern
+//! collapse into one summary diagnostic on the first occurrence, and each
+//! document's diagnostics can be capped, keeping the most severe.
+
+use crate::pattern_engine::{line_range, Detection};
+use crate::payload::DetectionPayload;
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString, Range,
+    Url,
+};
+
+/// Maximum number of other locations, and of occurrence lines in the
+/// payload, listed on a summary diagnostic
+pub const MAX_RELATED_LOCATIONS: usize = 100;
+
+/// A detection to publish, with the other occurrences it summarizes
+#[derive(Debug)]
+pub struct Published<'a> {
+    pub detection: &'a Detection,
+
+    /// Later occurrences of the same pattern (empty unless aggregated)
+    pub others: Vec<&'a Detection>,
+}
+
+impl Published<'_> {
+    /// Whether this stands for several occurrences
+    pub fn is_summary(&self) -> bool {
+        !self.others.is_empty()
+    }
+
+    /// Number of occurrences, including the first
+    pub fn count(&self) -> usize {
+        self.others.len() + 1
+    }
+
+    /// Turn the diagnostic of the first occurrence into a summary listing
+    /// the count and the other locations
+    pub fn summarize(&self, uri: &Url, diagnostic: &mut Diagnostic) {
+        if !self.is_summary() {
+            return;
+        }
+
+        diagnostic.message = format!("{} ({} occurrences)", diagnostic.message, self.count());
+
+        let related = diagnostic.related_information.get_or_insert_with(Vec::new);
+        related.extend(self.others.iter().take(MAX_RELATED_LOCATIONS).map(|other| {
+            DiagnosticRelatedInformation {
+                location: Location {
+                    uri: uri.clone(),
+                    range: line_range(other.line_number, other.column_range),
+                },
+                message: format!("Occurrence on line {}", other.line_number + 1),
+            }
+        }));
+        if self.others.len() > MAX_RELATED_LOCATIONS {
+            related.push(DiagnosticRelatedInformation {
+                location: Location {
+                    uri: uri.clone(),
+                    range: line_range(self.detection.line_number, self.detection.column_range),
+                },
+                message: format!("... and {} more", self.others.len() - MAX_RELATED_LOCATIONS),
+            });
+        }
+
+        if let Some(mut payload) = DetectionPayload::from_data(diagnostic.data.as_ref()) {
+            payload.aggregated = true;
+            payload.occurrences = Some(self.count());
+            payload.occurrence_lines = std::iter::once(self.detection)
+                .chain(self.others.iter().copied())
+                .take(MAX_RELATED_LOCATIONS)
+                .map(|d| d.line_number)
+                .collect();
+            diagnostic.data = Some(payload.to_value());
+        }
+    }
+}
+
+/// Collapse patterns with more than `threshold` occurrences into one entry
+/// on their first occurrence; a threshold of 0 disables aggregation
+///
+/// Entries keep the order of the detections.
+pub fn aggregate(detections: &[Detection], threshold: usize) -> Vec<Published<'_>> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for detection in detections {
+        *counts.entry(detection.pattern.id.as_str()).or_default() += 1;
+    }
+
+    let mut published: Vec<Published> = Vec::new();
+    let mut summaries: HashMap<&str, usize> = HashMap::new();
+    for detection in detections {
+        let id = detection.pattern.id.as_str();
+        if threshold == 0 || counts[id] <= threshold {
+            published.push(Published {
+                detection,
+                others: Vec::new(),
+            });
+            continue;
+        }
+
+        match summaries.get(id) {
+            Some(&index) => published[index].others.push(detection),
+            None => {
+                summaries.insert(id, published.len());
+                published.push(Published {
+                    detection,
+                    others: Vec::new(),
+                });
+            }
+        }
+    }
+
+    published
+}
+
+/// Keep at most `max` diagnostics, preferring the most severe, and add a
+/// notice saying how many were left out; a maximum of 0 keeps everything
+pub fn cap_diagnostics(diagnostics: &mut Vec<Diagnostic>, max: usize) {
+    let total = diagnostics.len();
+    if max == 0 || total <= max {
+        return;
+    }
+
+    // Lower values are more severe; diagnostics without severity come last
+    let mut order: Vec<usize> = (0..total).collect();
+    order.sort_by_key(|&i| {
+        diagnostics[i]
+            .severity
+            .map_or(i32::MAX, |severity| match severity {
+                DiagnosticSeverity::ERROR => 1,
+                DiagnosticSeverity::WARNING => 2,
+                DiagnosticSeverity::INFORMATION => 3,
+                _ => 4,
+            })
+    });
+    let mut keep = vec![false; total];
+    for &index in order.iter().take(max) {
+        keep[index] = true;
+    }
+    let mut index = 0;
+    diagnostics.retain(|_| {
+        index += 1;
+        keep[index - 1]
+    });
+
+    diagnostics.push(Diagnostic {
+        range: Range::default(),
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        code: Some(NumberOrString::String("diagnostic-cap".to_string())),
+        source: Some("log-scout".to_string()),
+        message: format!(
+            "Showing the {} most severe of {} diagnostics (max_diagnostics_per_document)",
+            max, total
+        ),
+        ..Default::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern, Severity};
+
+    #[test]
+    fn test_aggregate_above_threshold() {
+        let detections = detect_lines(
+            vec![
+                Pattern::test_with("noisy", "retrying", Severity::Warning, "network"),
+                Pattern::test_with("rare", "crashed", Severity::Warning, "crash"),
+            ],
+            "retrying\nretrying\ncrashed\nretrying",
+        );
+        let published = aggregate(&detections, 2);
+
+        assert_eq!(published.len(), 2);
+        assert_eq!(published[0].count(), 3);
+        assert_eq!(published[1].detection.pattern.id, "rare");
+        assert!(!published[1].is_summary());
+
+        let uri = Url::parse("file:///logs/app.log").unwrap();
+        let mut diagnostic = Diagnostic {
+            message: "noisy".to_string(),
+            data: Some(DetectionPayload::new(published[0].detection).to_value()),
+            ..Default::default()
+        };
+        published[0].summarize(&uri, &mut diagnostic);
+        assert_eq!(diagnostic.message, "noisy (3 occurrences)");
+        assert_eq!(diagnostic.related_information.unwrap().len(), 2);
+        assert_eq!(
+            diagnostic.data.unwrap()["occurrenceLines"],
+            serde_json::json!([0, 1, 3])
+        );
+
+        // 0 disables aggregation
+        assert_eq!(aggregate(&detections, 0).len(), 4);
+    }
+
+    #[test]
+    fn test_summary_caps_occurrence_lines() {
+        let detections = detect_lines(
+            vec![Pattern::test("noisy", "retrying")],
+            &"retrying\n".repeat(MAX_RELATED_LOCATIONS + 50),
+        );
+        let published = aggregate(&detections, 2);
+
+        let uri = Url::parse("file:///logs/app.log").unwrap();
+        let mut diagnostic = Diagnostic {
+            data: Some(DetectionPayload::new(published[0].detection).to_value()),
+            ..Default::default()
+        };
+        published[0].summarize(&uri, &mut diagnostic);
+
+        // Only the first lines are listed; the count covers all of them
+        let payload = DetectionPayload::from_data(diagnostic.data.as_ref()).unwrap();
+        assert_eq!(payload.occurrences, Some(MAX_RELATED_LOCATIONS + 50));
+        assert_eq!(payload.occurrence_lines.len(), MAX_RELATED_LOCATIONS);
+    }
+
+    #[test]
+    fn test_cap_keeps_most_severe() {
+        let diagnostic = |line: u32, severity| Diagnostic {
+            range: line_range(line as usize, (0, 1)),
+            severity: Some(severity),
+            ..Default::default()
+        };
+        let mut diagnostics = vec![
+            diagnostic(0, DiagnosticSeverity::HINT),
+            diagnostic(1, DiagnosticSeverity::ERROR),
+            diagnostic(2, DiagnosticSeverity::WARNING),
+        ];
+        cap_diagnostics(&mut diagnostics, 2);
+
+        let lines: Vec<u32> = diagnostics.iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![1, 2, 0]);
+        assert!(diagnostics[2]
+            .message
+            .starts_with("Showing the 2 most severe of 3"));
+    }
+}
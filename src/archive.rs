@@ -0,0 +1,473 @@
+//! Compressed Logs and Problem Report Bundles
+//!
+//! Opens zip archives (e.g. Jabber PRT bundles) and gzip-compressed logs in
+//! place. Each log inside is exposed as a read-only virtual document under
+//! the `logscout-archive:` scheme and analyzed without extracting anything.
+
+use crate::pattern_engine::{Detection, Severity};
+use crate::virtual_document::percent_decode;
+use flate2::read::MultiGzDecoder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, Position, Range, Url,
+};
+
+/// Custom request that opens an archive and analyzes its members
+pub const OPEN_ARCHIVE_METHOD: &str = "logScout/openArchive";
+
+/// URI scheme for logs inside archives
+pub const ARCHIVE_SCHEME: &str = "logscout-archive";
+
+/// Separates the archive path from the member name in archive URIs
+const MEMBER_SEPARATOR: &str = "!/";
+
+/// Bytes inspected to tell text logs from binary members
+const BINARY_PROBE_LEN: usize = 8192;
+
+/// Errors that can occur while reading archives
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Not a zip or gzip file: {0}")]
+    Unsupported(String),
+
+    #[error("Member not found: {0}")]
+    MemberNotFound(String),
+
+    #[error("{member} is larger than {limit_mb} MB uncompressed")]
+    TooLarge { member: String, limit_mb: u64 },
+}
+
+impl ArchiveError {
+    fn too_large(member: &str, limit: u64) -> Self {
+        Self::TooLarge {
+            member: member.to_string(),
+            limit_mb: limit / (1024 * 1024),
+        }
+    }
+}
+
+/// Parameters of the `logScout/openArchive` request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenArchiveParams {
+    /// `file:` URI of a `.zip` or `.gz` file
+    pub uri: Url,
+}
+
+/// Result of the `logScout/openArchive` request
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveListing {
+    pub uri: Url,
+    pub members: Vec<ArchiveMember>,
+}
+
+/// A log inside an archive
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveMember {
+    /// Path of the member inside the archive
+    pub name: String,
+
+    /// Virtual document URI of the member
+    pub uri: Url,
+
+    /// Uncompressed size in bytes
+    pub size: u64,
+
+    /// Number of detections in the member
+    pub detections: usize,
+}
+
+/// Kinds of archives that can be opened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Gzip,
+}
+
+fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_str()?.to_lowercase();
+    if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".gz") {
+        Some(ArchiveKind::Gzip)
+    } else {
+        None
+    }
+}
+
+/// Check whether a file can be opened as an archive
+pub fn is_archive(path: &Path) -> bool {
+    archive_kind(path).is_some()
+}
+
+/// URI of a member inside an archive, e.g.
+/// `logscout-archive:/tmp/prt.zip!/logs/jabber.log.1.gz`
+pub fn member_uri(archive: &Path, member: &str) -> Url {
+    let mut uri = Url::parse(&format!("{}:/", ARCHIVE_SCHEME)).unwrap();
+    let archive_path = archive.to_string_lossy().replace('\\', "/");
+    uri.set_path(&format!(
+        "/{}{}{}",
+        archive_path.trim_start_matches('/'),
+        MEMBER_SEPARATOR,
+        member
+    ));
+    uri
+}
+
+/// Archive path and member name referenced by a `logscout-archive:` URI
+pub fn parse_member_uri(uri: &Url) -> Option<(PathBuf, String)> {
+    if uri.scheme() != ARCHIVE_SCHEME {
+        return None;
+    }
+
+    let path = percent_decode(uri.path())?;
+    let (archive, member) = path.split_once(MEMBER_SEPARATOR)?;
+
+    // Windows paths come out as `/C:/...`; keep the leading slash elsewhere
+    let archive = match archive.as_bytes() {
+        [b'/', _, b':', ..] => &archive[1..],
+        _ => archive,
+    };
+    Some((PathBuf::from(archive), member.to_string()))
+}
+
+/// Read every log in an archive as `(member name, text)` pairs
+///
+/// Zip members that are themselves gzip-compressed are decompressed too.
+/// Directories, binary members, zip members larger than `limit` bytes
+/// once decompressed and members that fail to decompress are skipped.
+pub fn read_members(path: &Path, limit: u64) -> Result<Vec<(String, String)>, ArchiveError> {
+    let mut members = Vec::new();
+
+    match archive_kind(path) {
+        Some(ArchiveKind::Zip) => {
+            let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index)?;
+                if entry.is_dir() {
+                    continue;
+                }
+
+                let name = entry.name().to_string();
+                // One unreadable member does not hide the rest of the bundle
+                let decoded = match read_limited(&mut entry, limit) {
+                    Ok(Some(bytes)) => decode_member(&name, bytes, limit),
+                    Ok(None) => Err(ArchiveError::too_large(&name, limit)),
+                    Err(e) => Err(e.into()),
+                };
+                match decoded {
+                    Ok(Some(text)) => members.push((name, text)),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Skipping {} in {}: {}", name, path.display(), e),
+                }
+            }
+        }
+        Some(ArchiveKind::Gzip) => {
+            let name = gzip_member_name(path);
+            let bytes = gunzip(File::open(path)?, limit)?
+                .ok_or_else(|| ArchiveError::too_large(&name, limit))?;
+            if let Some(text) = decode_member(&name, bytes, limit)? {
+                members.push((name, text));
+            }
+        }
+        None => return Err(ArchiveError::Unsupported(path.display().to_string())),
+    }
+
+    members.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(members)
+}
+
+/// Read a single log from an archive, up to `limit` bytes decompressed
+pub fn read_member(path: &Path, member: &str, limit: u64) -> Result<String, ArchiveError> {
+    let bytes = match archive_kind(path) {
+        Some(ArchiveKind::Zip) => {
+            let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+            let mut entry = archive
+                .by_name(member)
+                .map_err(|_| ArchiveError::MemberNotFound(member.to_string()))?;
+            read_limited(&mut entry, limit)?
+                .ok_or_else(|| ArchiveError::too_large(member, limit))?
+        }
+        Some(ArchiveKind::Gzip) if gzip_member_name(path) == member => {
+            let bytes = gunzip(File::open(path)?, limit)?
+                .ok_or_else(|| ArchiveError::too_large(member, limit))?;
+            return Ok(decode_text(&bytes));
+        }
+        Some(ArchiveKind::Gzip) => return Err(ArchiveError::MemberNotFound(member.to_string())),
+        None => return Err(ArchiveError::Unsupported(path.display().to_string())),
+    };
+
+    decode_member(member, bytes, limit)?
+        .ok_or_else(|| ArchiveError::MemberNotFound(member.to_string()))
+}
+
+/// Diagnostics summarizing detections across all members of a bundle
+///
+/// One diagnostic per pattern, placed on the first line of the archive,
+/// with related information pointing at the first occurrence in each member.
+/// Its severity is the most severe evaluated severity of the pattern.
+pub fn bundle_diagnostics(members: &[(Url, &[Detection])]) -> Vec<Diagnostic> {
+    // Pattern ID -> per-member (uri, first detection, count)
+    let mut order: Vec<&str> = Vec::new();
+    let mut groups: HashMap<&str, Vec<(&Url, &Detection, usize)>> = HashMap::new();
+    let mut severities: HashMap<&str, Severity> = HashMap::new();
+
+    for (uri, detections) in members {
+        for detection in detections.iter() {
+            let id = detection.pattern.id.as_str();
+            let group = groups.entry(id).or_insert_with(|| {
+                order.push(id);
+                Vec::new()
+            });
+            let severity = severities.entry(id).or_insert(detection.final_severity);
+            if detection.final_severity.rank() > severity.rank() {
+                *severity = detection.final_severity;
+            }
+            match group.iter_mut().find(|(member, _, _)| *member == uri) {
+                Some((_, _, count)) => *count += 1,
+                None => group.push((uri, detection, 1)),
+            }
+        }
+    }
+
+    order
+        .into_iter()
+        .map(|id| {
+            let group = &groups[id];
+            let pattern = &group[0].1.pattern;
+            let total: usize = group.iter().map(|(_, _, count)| count).sum();
+
+            let related = group
+                .iter()
+                .map(|(uri, first, count)| {
+                    let line = first.line_number as u32;
+                    DiagnosticRelatedInformation {
+                        location: Location {
+                            uri: (*uri).clone(),
+                            range: Range {
+                                start: Position {
+                                    line,
+                                    character: first.column_range.0 as u32,
+                                },
+                                end: Position {
+                                    line,
+                                    character: first.column_range.1 as u32,
+                                },
+                            },
+                        },
+                        message: format!("{} occurrence(s) in {}", count, member_label(uri)),
+                    }
+                })
+                .collect();
+
+            Diagnostic {
+                range: Range::default(),
+                severity: Some(DiagnosticSeverity::from(severities[id])),
+                code: Some(tower_lsp::lsp_types::NumberOrString::String(
+                    pattern.id.clone(),
+                )),
+                source: Some("log-scout".to_string()),
+                message: format!(
+                    "{}: {} occurrence(s) in {} file(s)",
+                    pattern.name,
+                    total,
+                    group.len()
+                ),
+                related_information: Some(related),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn member_label(uri: &Url) -> String {
+    parse_member_uri(uri)
+        .map(|(_, member)| member)
+        .unwrap_or_else(|| uri.to_string())
+}
+
+/// Name of the single member of a `.gz` file (the file name without `.gz`)
+fn gzip_member_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name[..name.len() - 3].to_string())
+        .unwrap_or_default()
+}
+
+/// Read at most `limit` bytes, or `None` when there is more
+fn read_limited(reader: impl Read, limit: u64) -> io::Result<Option<Vec<u8>>> {
+    let mut bytes = Vec::new();
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut bytes)?;
+    Ok((bytes.len() as u64 <= limit).then_some(bytes))
+}
+
+fn gunzip(reader: impl Read, limit: u64) -> io::Result<Option<Vec<u8>>> {
+    read_limited(MultiGzDecoder::new(reader), limit)
+}
+
+/// Decompress nested `.gz` members and reject binary content
+fn decode_member(name: &str, bytes: Vec<u8>, limit: u64) -> Result<Option<String>, ArchiveError> {
+    let bytes = if name.to_lowercase().ends_with(".gz") {
+        gunzip(bytes.as_slice(), limit)?.ok_or_else(|| ArchiveError::too_large(name, limit))?
+    } else {
+        bytes
+    };
+
+    let probe = &bytes[..bytes.len().min(BINARY_PROBE_LEN)];
+    if probe.contains(&0) {
+        return Ok(None);
+    }
+
+    Ok(Some(decode_text(&bytes)))
+}
+
+fn decode_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_member_uri_round_trip() {
+        let uri = member_uri(Path::new("/tmp/my prt.zip"), "logs/jabber.log.1.gz");
+        assert_eq!(uri.scheme(), ARCHIVE_SCHEME);
+        assert_eq!(
+            parse_member_uri(&uri),
+            Some((
+                PathBuf::from("/tmp/my prt.zip"),
+                "logs/jabber.log.1.gz".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_member_uri(&Url::parse("file:///tmp/prt.zip").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_zip_bundle() {
+        let path = std::env::temp_dir().join(format!("logscout-prt-{}.zip", std::process::id()));
+        {
+            let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            writer.add_directory("logs/", options).unwrap();
+            writer.start_file("logs/jabber.log", options).unwrap();
+            writer.write_all(b"current\n").unwrap();
+            writer.start_file("logs/jabber.log.1.gz", options).unwrap();
+            writer.write_all(&gzip("rotated\n")).unwrap();
+            writer.start_file("screenshot.png", options).unwrap();
+            writer.write_all(&[0x89, b'P', b'N', b'G', 0, 0]).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let members = read_members(&path, 1024).unwrap();
+        let member = read_member(&path, "logs/jabber.log.1.gz", 1024).unwrap();
+        // Both logs are 8 bytes once decompressed
+        let limited = read_members(&path, 7).unwrap();
+        let too_large = read_member(&path, "logs/jabber.log.1.gz", 7);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            members,
+            vec![
+                ("logs/jabber.log".to_string(), "current\n".to_string()),
+                ("logs/jabber.log.1.gz".to_string(), "rotated\n".to_string()),
+            ]
+        );
+        assert_eq!(member, "rotated\n");
+        assert!(limited.is_empty());
+        assert!(matches!(too_large, Err(ArchiveError::TooLarge { .. })));
+    }
+
+    #[test]
+    fn test_read_zip_bundle_skips_corrupt_member() {
+        let path =
+            std::env::temp_dir().join(format!("logscout-corrupt-{}.zip", std::process::id()));
+        {
+            let mut writer = zip::ZipWriter::new(File::create(&path).unwrap());
+            let options = zip::write::SimpleFileOptions::default();
+            writer.start_file("jabber.log", options).unwrap();
+            writer.write_all(b"current\n").unwrap();
+            let rotated = gzip("rotated\n");
+            writer.start_file("jabber.log.1.gz", options).unwrap();
+            writer.write_all(&rotated[..rotated.len() / 2]).unwrap();
+            writer.finish().unwrap();
+        }
+
+        let members = read_members(&path, 1024).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            members,
+            vec![("jabber.log".to_string(), "current\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_bundle_diagnostics() {
+        use crate::pattern_engine::{detect_lines, Pattern};
+
+        let pattern = Pattern {
+            name: "Registration failed".to_string(),
+            category: "sip".to_string(),
+            ..Pattern::test("reg-fail", "REGISTER failed")
+        };
+        let detect = |text: &str| detect_lines(vec![pattern.clone()], text);
+
+        let archive = Path::new("/tmp/prt.zip");
+        let jabber = detect("REGISTER failed\nok\nREGISTER failed");
+        let rotated = detect("ok\nREGISTER failed");
+        let members = [
+            (member_uri(archive, "jabber.log"), jabber.as_slice()),
+            (member_uri(archive, "jabber.log.1"), rotated.as_slice()),
+        ];
+
+        let diagnostics = bundle_diagnostics(&members);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Registration failed: 3 occurrence(s) in 2 file(s)"
+        );
+
+        let related = diagnostics[0].related_information.as_ref().unwrap();
+        assert_eq!(related[0].message, "2 occurrence(s) in jabber.log");
+        assert_eq!(related[1].location.range.start.line, 1);
+    }
+
+    #[test]
+    fn test_read_gzip_log() {
+        let path = std::env::temp_dir().join(format!("logscout-{}.log.gz", std::process::id()));
+        std::fs::write(&path, gzip("line one\n")).unwrap();
+
+        let members = read_members(&path, 1024).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let expected_name = format!("logscout-{}.log", std::process::id());
+        assert_eq!(members, vec![(expected_name, "line one\n".to_string())]);
+    }
+}
@@ -0,0 +1,653 @@
+//! Batch Analysis
+//!
+//! Headless mode of the binary (`log-scout-lsp-server analyze <files...>`)
+//! for CI jobs and scripts. Loads patterns, stages, settings and ignore
+//! rules the same way the server does, runs the analysis pipeline over
+//! files or stdin and prints the detections as JSON, SARIF or a text table.
+
+use crate::config::{self, ClientSettings};
+use crate::pattern_engine::{self, Detection, PatternEngine, Severity};
+use crate::payload::DetectionPayload;
+use crate::pipeline::{AnalysisContext, AnalysisPipeline};
+use crate::position::PositionEncoding;
+use crate::process::ProcessEngine;
+use crate::scenario::ScenarioEngine;
+use crate::signature::SignatureEngine;
+use crate::suppression::Suppressions;
+use crate::tagscout::{SyncMode, SyncService};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tower_lsp::lsp_types::Url;
+
+/// Input name that reads from stdin
+pub const STDIN_INPUT: &str = "-";
+
+/// SARIF base of relative input paths, the directory the analysis ran in
+const SRCROOT: &str = "%SRCROOT%";
+
+/// Errors that can occur during batch analysis
+#[derive(Error, Debug)]
+pub enum BatchError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to read {path}: {source}")]
+    Input {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("No patterns available (no TagScout cache and no YAML pattern packs found)")]
+    NoPatterns,
+
+    #[error("Pattern error: {0}")]
+    Pattern(#[from] pattern_engine::PatternError),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Report formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Sarif,
+    Text,
+}
+
+impl OutputFormat {
+    /// Parse output format from string
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Some(OutputFormat::Json),
+            "sarif" => Some(OutputFormat::Sarif),
+            "text" | "table" => Some(OutputFormat::Text),
+            _ => None,
+        }
+    }
+}
+
+/// Options for a batch run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchOptions {
+    /// Files to analyze (`-` for stdin); stdin when empty
+    pub inputs: Vec<String>,
+
+    /// Report format
+    pub format: OutputFormat,
+
+    /// Exit non-zero when findings at or above this severity exist
+    pub fail_on: Option<Severity>,
+
+    /// Extra YAML pattern files or directories
+    pub pattern_paths: Vec<PathBuf>,
+
+    /// TagScout cache directory (defaults to the server's)
+    pub cache_dir: Option<PathBuf>,
+
+    /// Allow fetching patterns from TagScout when the cache is stale
+    pub online: bool,
+
+    /// JSON settings file, in the shape of the server's `initializationOptions`
+    pub settings_path: Option<PathBuf>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            inputs: Vec::new(),
+            format: OutputFormat::Text,
+            fail_on: Some(Severity::Error),
+            pattern_paths: Vec::new(),
+            cache_dir: None,
+            online: false,
+            settings_path: None,
+        }
+    }
+}
+
+/// Detections found in one input
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub name: String,
+    pub detections: Vec<Detection>,
+}
+
+/// One detection as printed in JSON reports
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based start column, in UTF-16 code units as in SARIF
+    pub column: usize,
+    /// 1-based end column (exclusive), in UTF-16 code units
+    pub end_column: usize,
+    pub category: String,
+    pub message: String,
+    pub matched_text: String,
+
+    /// Same payload as the detection's diagnostic `data`
+    #[serde(flatten)]
+    pub payload: DetectionPayload,
+}
+
+impl Finding {
+    fn new(file: &str, detection: &Detection) -> Self {
+        let (start, end) = detection.column_range;
+        Self {
+            file: file.to_string(),
+            line: detection.line_number + 1,
+            column: utf16_column(detection, start),
+            end_column: utf16_column(detection, end),
+            category: detection.pattern.category.clone(),
+            message: message(detection),
+            matched_text: detection.matched_text.clone(),
+            payload: DetectionPayload::new(detection),
+        }
+    }
+}
+
+/// Run a batch analysis and write the report to `out`
+///
+/// Returns whether findings at or above `fail_on` were found.
+pub async fn run(options: &BatchOptions, out: &mut impl Write) -> Result<bool, BatchError> {
+    let analyzer = Analyzer::load(options).await?;
+
+    let inputs = if options.inputs.is_empty() {
+        vec![STDIN_INPUT.to_string()]
+    } else {
+        options.inputs.clone()
+    };
+
+    let mut reports = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let text = read_input(&input)?;
+        let path = (input != STDIN_INPUT)
+            .then(|| std::fs::canonicalize(&input).ok())
+            .flatten();
+        reports.push(FileReport {
+            detections: analyzer.analyze(&text, path.as_deref()),
+            name: input,
+        });
+    }
+
+    let report = match options.format {
+        OutputFormat::Json => render_json(&reports)?,
+        OutputFormat::Sarif => render_sarif(&reports, &std::env::current_dir()?)?,
+        OutputFormat::Text => render_text(&reports),
+    };
+    out.write_all(report.as_bytes())?;
+    out.flush()?;
+
+    Ok(options
+        .fail_on
+        .is_some_and(|threshold| has_findings_at(&reports, threshold)))
+}
+
+/// Patterns, stages, settings and ignore rules used to analyze the inputs
+///
+/// Built like the server's, so findings match the editor's diagnostics
+/// for the same settings.
+pub struct Analyzer {
+    engine: PatternEngine,
+    pipeline: AnalysisPipeline,
+    settings: ClientSettings,
+    suppressions: Suppressions,
+}
+
+impl Analyzer {
+    /// Analyzer with the standard stages, default settings and no ignore rules
+    pub fn new(engine: PatternEngine) -> Self {
+        Self {
+            engine,
+            pipeline: AnalysisPipeline::standard(
+                SignatureEngine::default(),
+                ProcessEngine::default(),
+                ScenarioEngine::default(),
+            ),
+            settings: ClientSettings::default(),
+            suppressions: Suppressions::default(),
+        }
+    }
+
+    /// Load local pattern packs and the TagScout cache, the settings file
+    /// and the ignore file of the current directory
+    ///
+    /// Local patterns win when both define the same ID, as in the server.
+    pub async fn load(options: &BatchOptions) -> Result<Self, BatchError> {
+        let settings = match &options.settings_path {
+            Some(path) => {
+                let content =
+                    std::fs::read_to_string(path).map_err(|source| BatchError::Input {
+                        path: path.display().to_string(),
+                        source,
+                    })?;
+                ClientSettings::from_json(&serde_json::from_str(&content)?)?
+            }
+            None => ClientSettings::default(),
+        };
+
+        let cwd = std::env::current_dir()?;
+        let mut pack_dirs = config::pattern_pack_dirs(std::slice::from_ref(&cwd));
+        let mut local = Vec::new();
+        for path in &options.pattern_paths {
+            if path.is_dir() {
+                pack_dirs.push(path.clone());
+            } else {
+                local.extend(config::load_pattern_pack(path)?);
+            }
+        }
+        let local = config::merge_patterns(vec![local, config::load_pattern_packs(&pack_dirs)]);
+
+        let mut sync_config = settings.global.sync_service_config();
+        sync_config.sync_mode = if options.online {
+            SyncMode::CacheFirst
+        } else {
+            SyncMode::OfflineOnly
+        };
+        sync_config.auto_refresh_interval = None;
+        if let Some(cache_dir) = &options.cache_dir {
+            sync_config.cache_dir = cache_dir.clone();
+        }
+
+        let tagscout = match SyncService::new(sync_config).await {
+            Ok(mut service) => match service.initialize().await {
+                Ok(_) => service.get_patterns().await.unwrap_or_default(),
+                Err(e) => {
+                    tracing::warn!("TagScout patterns unavailable: {}", e);
+                    Vec::new()
+                }
+            },
+            Err(e) => {
+                tracing::warn!("TagScout patterns unavailable: {}", e);
+                Vec::new()
+            }
+        };
+
+        let patterns = config::merge_patterns(vec![local, tagscout]);
+        if patterns.is_empty() {
+            return Err(BatchError::NoPatterns);
+        }
+
+        Ok(Self {
+            engine: PatternEngine::new(
+                patterns,
+                settings.global.detection_threshold,
+                settings.global.multiline_context_window,
+            )?,
+            pipeline: AnalysisPipeline::standard(
+                SignatureEngine::new(config::load_signature_packs(&pack_dirs)),
+                ProcessEngine::new(config::load_process_packs(&pack_dirs)),
+                ScenarioEngine::new(config::load_scenario_packs(&pack_dirs)),
+            ),
+            suppressions: Suppressions::load(&[cwd]),
+            settings,
+        })
+    }
+
+    /// Run the pipeline over a whole text
+    ///
+    /// `path` selects the folder settings and ignore rules of a file.
+    pub fn analyze(&self, text: &str, path: Option<&Path>) -> Vec<Detection> {
+        let settings = path.map_or(&self.settings.global, |path| self.settings.for_path(path));
+        let mut context = AnalysisContext::new(text, &self.engine)
+            .with_settings(settings)
+            .with_suppressions(&self.suppressions, path);
+        self.pipeline.run(&mut context);
+        context.detections
+    }
+}
+
+/// Check whether any detection is at or above `threshold`
+pub fn has_findings_at(reports: &[FileReport], threshold: Severity) -> bool {
+    reports
+        .iter()
+        .flat_map(|report| &report.detections)
+        .any(|d| d.final_severity.rank() >= threshold.rank())
+}
+
+/// JSON report: an array of findings
+pub fn render_json(reports: &[FileReport]) -> Result<String, BatchError> {
+    let findings: Vec<Finding> = reports
+        .iter()
+        .flat_map(|report| {
+            report
+                .detections
+                .iter()
+                .map(|detection| Finding::new(&report.name, detection))
+        })
+        .collect();
+
+    Ok(serde_json::to_string_pretty(&findings)? + "\n")
+}
+
+/// SARIF 2.1.0 report with one rule per matched pattern
+///
+/// Relative input paths are resolved against `root`.
+pub fn render_sarif(reports: &[FileReport], root: &Path) -> Result<String, BatchError> {
+    let mut rules = BTreeMap::new();
+    let mut results = Vec::new();
+
+    for report in reports {
+        for detection in &report.detections {
+            let pattern = &detection.pattern;
+            rules.entry(pattern.id.clone()).or_insert_with(|| {
+                serde_json::json!({
+                    "id": pattern.id,
+                    "name": pattern.name,
+                    "shortDescription": { "text": pattern.name },
+                    "properties": { "category": pattern.category },
+                })
+            });
+
+            let finding = Finding::new(&report.name, detection);
+            results.push(serde_json::json!({
+                "ruleId": finding.payload.pattern_id,
+                "level": sarif_level(finding.payload.severity),
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": artifact_location(&finding.file, root),
+                        "region": {
+                            "startLine": finding.line,
+                            "startColumn": finding.column,
+                            "endColumn": finding.end_column,
+                        },
+                    },
+                }],
+                "properties": finding.payload,
+            }));
+        }
+    }
+
+    let sarif = serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "log-scout",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.into_values().collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+            "columnKind": "utf16CodeUnits",
+            "originalUriBaseIds": {
+                SRCROOT: { "uri": Url::from_directory_path(root).ok() },
+            },
+        }],
+    });
+
+    Ok(serde_json::to_string_pretty(&sarif)? + "\n")
+}
+
+/// Text table with one line per finding and a summary line
+pub fn render_text(reports: &[FileReport]) -> String {
+    let findings: Vec<Finding> = reports
+        .iter()
+        .flat_map(|report| {
+            report
+                .detections
+                .iter()
+                .map(|detection| Finding::new(&report.name, detection))
+        })
+        .collect();
+
+    let locations: Vec<String> = findings
+        .iter()
+        .map(|f| format!("{}:{}:{}", f.file, f.line, f.column))
+        .collect();
+    let location_width = locations.iter().map(|l| l.len()).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for (finding, location) in findings.iter().zip(&locations) {
+        out.push_str(&format!(
+            "{:<width$}  {:<7}  {}: {}\n",
+            location,
+            finding.payload.severity.as_str(),
+            finding.payload.pattern_name,
+            finding.message,
+            width = location_width
+        ));
+    }
+
+    let count = |severity: Severity| {
+        findings
+            .iter()
+            .filter(|f| f.payload.severity == severity)
+            .count()
+    };
+    let files = findings
+        .iter()
+        .map(|f| f.file.as_str())
+        .collect::<BTreeSet<_>>()
+        .len();
+    out.push_str(&format!(
+        "{} findings ({} errors, {} warnings) in {} of {} inputs\n",
+        findings.len(),
+        count(Severity::Error),
+        count(Severity::Warning),
+        files,
+        reports.len()
+    ));
+
+    out
+}
+
+fn message(detection: &Detection) -> String {
+    if detection.pattern.annotation.is_empty() {
+        detection.pattern.name.clone()
+    } else {
+        pattern_engine::substitute_template(&detection.pattern.annotation, &detection.field_values)
+    }
+}
+
+/// 1-based UTF-16 column of a byte offset in a detection's line, the
+/// first of its context
+fn utf16_column(detection: &Detection, byte: usize) -> usize {
+    let column = match detection.context.first() {
+        Some(line) => PositionEncoding::Utf16.column(line, byte) as usize,
+        None => byte,
+    };
+    column + 1
+}
+
+/// SARIF location of an input
+///
+/// Relative paths are URI references against `%SRCROOT%` and absolute
+/// ones `file:` URLs; standard input has no URI.
+fn artifact_location(input: &str, root: &Path) -> serde_json::Value {
+    let url = (input != STDIN_INPUT)
+        .then(|| Url::from_file_path(root.join(input)).ok())
+        .flatten();
+    let Some(url) = url else {
+        let text = if input == STDIN_INPUT {
+            "standard input"
+        } else {
+            input
+        };
+        return serde_json::json!({ "description": { "text": text } });
+    };
+
+    let relative = Path::new(input)
+        .is_relative()
+        .then(|| Url::from_directory_path(root).ok()?.make_relative(&url))
+        .flatten();
+    match relative {
+        Some(relative) => serde_json::json!({ "uri": relative, "uriBaseId": SRCROOT }),
+        None => serde_json::json!({ "uri": url }),
+    }
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info | Severity::Hint => "note",
+    }
+}
+
+fn read_input(input: &str) -> Result<String, BatchError> {
+    let bytes = if input == STDIN_INPUT {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        bytes
+    } else {
+        std::fs::read(input).map_err(|source| BatchError::Input {
+            path: input.to_string(),
+            source,
+        })?
+    };
+
+    // Logs are not always valid UTF-8
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{ParameterExtractor, Pattern};
+
+    fn analyzer() -> Analyzer {
+        let engine = PatternEngine::new(
+            vec![Pattern {
+                name: "Registration failed".to_string(),
+                annotation: "Registration failed with {{ CODE }}".to_string(),
+                parameter_extractors: vec![ParameterExtractor {
+                    name: "CODE".to_string(),
+                    regex: r"failed (\d+)".to_string(),
+                    enum_name: None,
+                }],
+                ..Pattern::test_with(
+                    "sip-register",
+                    r"REGISTER failed \d+",
+                    Severity::Warning,
+                    "sip",
+                )
+            }],
+            0.7,
+            10,
+        )
+        .unwrap();
+
+        Analyzer::new(engine)
+    }
+
+    fn reports() -> Vec<FileReport> {
+        let analyzer = analyzer();
+        vec![
+            FileReport {
+                name: "jabber.log".to_string(),
+                detections: analyzer.analyze("ok\nREGISTER failed 403\n", None),
+            },
+            FileReport {
+                name: "webex.log".to_string(),
+                detections: analyzer.analyze("all good\n", None),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_thresholds() {
+        let reports = reports();
+        assert!(has_findings_at(&reports, Severity::Warning));
+        assert!(has_findings_at(&reports, Severity::Info));
+        assert!(!has_findings_at(&reports, Severity::Error));
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let reports = reports();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_json(&reports).unwrap()).unwrap();
+        assert_eq!(json[0]["file"], "jabber.log");
+        assert_eq!(json[0]["line"], 2);
+        assert_eq!(json[0]["column"], 1);
+        assert_eq!(json[0]["message"], "Registration failed with 403");
+        assert_eq!(json[0]["parameters"]["CODE"], "403");
+        assert_eq!(json[0]["version"], crate::payload::SCHEMA_VERSION);
+
+        let sarif: serde_json::Value =
+            serde_json::from_str(&render_sarif(&reports, Path::new("/work")).unwrap()).unwrap();
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "sip-register");
+        assert_eq!(run["results"][0]["level"], "warning");
+        assert_eq!(run["results"][0]["properties"]["parameters"]["CODE"], "403");
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"]["region"]["startLine"],
+            2
+        );
+        assert_eq!(
+            run["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"],
+            serde_json::json!({ "uri": "jabber.log", "uriBaseId": "%SRCROOT%" })
+        );
+        assert_eq!(
+            run["originalUriBaseIds"]["%SRCROOT%"]["uri"],
+            "file:///work/"
+        );
+
+        let text = render_text(&reports);
+        assert!(text.starts_with(
+            "jabber.log:2:1  warning  Registration failed: Registration failed with 403\n"
+        ));
+        assert!(text.ends_with("1 findings (0 errors, 1 warnings) in 1 of 2 inputs\n"));
+    }
+
+    #[test]
+    fn test_sarif_artifact_locations() {
+        let root = Path::new("/work");
+        assert_eq!(
+            artifact_location("logs/jabber client.log", root),
+            serde_json::json!({ "uri": "logs/jabber%20client.log", "uriBaseId": "%SRCROOT%" })
+        );
+        assert_eq!(
+            artifact_location("/var/log/webex.log", root),
+            serde_json::json!({ "uri": "file:///var/log/webex.log" })
+        );
+        assert_eq!(
+            artifact_location(STDIN_INPUT, root),
+            serde_json::json!({ "description": { "text": "standard input" } })
+        );
+    }
+
+    #[test]
+    fn test_analyzer_applies_pipeline() {
+        let mut analyzer = analyzer();
+        assert_eq!(analyzer.analyze("REGISTER failed 403\n", None).len(), 1);
+
+        analyzer.suppressions = Suppressions::new(vec![crate::suppression::SuppressionRule::mute(
+            "sip-register",
+            None,
+        )]);
+        assert!(analyzer.analyze("REGISTER failed 403\n", None).is_empty());
+    }
+
+    #[test]
+    fn test_columns_are_utf16() {
+        let reports = vec![FileReport {
+            name: "jabber.log".to_string(),
+            detections: analyzer().analyze("user=José 𝄞 REGISTER failed 403\n", None),
+        }];
+        let sarif: serde_json::Value =
+            serde_json::from_str(&render_sarif(&reports, Path::new("/work")).unwrap()).unwrap();
+        let run = &sarif["runs"][0];
+        assert_eq!(run["columnKind"], "utf16CodeUnits");
+        let region = &run["results"][0]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startColumn"], 14);
+        assert_eq!(region["endColumn"], 33);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_json(&reports).unwrap()).unwrap();
+        assert_eq!(json[0]["column"], 14);
+        assert_eq!(json[0]["endColumn"], 33);
+        assert!(render_text(&reports).starts_with("jabber.log:1:14 "));
+    }
+}
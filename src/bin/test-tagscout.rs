@@ -127,9 +127,11 @@ async fn main() {
     println!("─────────────────────────────────");
     let sync_start = Instant::now();
 
-    let mut config = SyncServiceConfig::default();
-    config.sync_mode = SyncMode::OnlineFirst;
-    config.cache_dir = std::env::temp_dir().join(".tagscout_test_cache");
+    let config = SyncServiceConfig {
+        sync_mode: SyncMode::OnlineFirst,
+        cache_dir: std::env::temp_dir().join(".tagscout_test_cache"),
+        ..Default::default()
+    };
 
     match SyncService::new(config).await {
         Ok(mut service) => {
@@ -198,9 +200,11 @@ async fn main() {
     println!("─────────────────────────────────");
     let offline_start = Instant::now();
 
-    let mut offline_config = SyncServiceConfig::default();
-    offline_config.sync_mode = SyncMode::OfflineOnly;
-    offline_config.cache_dir = std::env::temp_dir().join(".tagscout_test_cache");
+    let offline_config = SyncServiceConfig {
+        sync_mode: SyncMode::OfflineOnly,
+        cache_dir: std::env::temp_dir().join(".tagscout_test_cache"),
+        ..Default::default()
+    };
 
     match SyncService::new(offline_config).await {
         Ok(mut service) => {
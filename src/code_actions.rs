@@ -0,0 +1,295 @@
+//! Code Actions
+//!
+//! Actions offered for the detections under the cursor: open the pattern's
+//! remediation documentation, show every occurrence of the pattern, mute
+//! it, copy the detection as ticket-ready Markdown, and suggest a new pattern
+//! from the current line or selection. Each action runs a server command.
+
+use crate::pattern_details::PatternDetailsParams;
+use crate::pattern_engine::Detection;
+use crate::payload::DiagnosticPayload;
+use crate::suppression::MUTE_COMMAND;
+use std::fmt::Write;
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Command, Diagnostic, Range, Url,
+};
+
+/// Command opening a pattern's documentation
+pub const OPEN_DOCUMENTATION_COMMAND: &str = "logScout.openDocumentation";
+
+/// Command returning the locations of a pattern in all analyzed logs
+pub const SHOW_OCCURRENCES_COMMAND: &str = "logScout.showOccurrences";
+
+/// Command returning a detection as ticket-ready Markdown
+pub const TICKET_MARKDOWN_COMMAND: &str = "logScout.copyTicketMarkdown";
+
+/// Command proposing a pattern for a log line (or selection) and adding it
+/// to the local pattern pack
+pub const SUGGEST_PATTERN_COMMAND: &str = "logScout.suggestPattern";
+
+/// First `http(s)://` link in a piece of documentation
+pub fn documentation_url(documentation: &str) -> Option<Url> {
+    documentation
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '<' | '>' | '"'))
+        .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+        .find_map(|word| Url::parse(word.trim_end_matches(['.', ',', ';'])).ok())
+}
+
+/// Render a detection as Markdown to paste into a ticket
+///
+/// `message` is the pattern's template with the extracted values filled in.
+pub fn ticket_markdown(uri: &Url, detection: &Detection, message: &str) -> String {
+    let pattern = &detection.pattern;
+    let file = uri
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or(uri.as_str());
+
+    let mut out = String::new();
+    let _ = writeln!(out, "### {}\n", pattern.name);
+    let _ = writeln!(out, "| Field | Value |");
+    let _ = writeln!(out, "|---|---|");
+    let _ = writeln!(out, "| File | `{}` |", file);
+    let _ = writeln!(out, "| Line | {} |", detection.line_number + 1);
+    if let Some(timestamp) = &detection.timestamp {
+        let _ = writeln!(out, "| Timestamp | {} |", timestamp);
+    }
+    let _ = writeln!(out, "| Severity | {} |", detection.final_severity.as_str());
+    let _ = writeln!(out, "| Pattern | `{}` ({}) |", pattern.id, pattern.category);
+    if let Some(service) = &pattern.service {
+        let _ = writeln!(out, "| Product | {} |", service);
+    }
+
+    if !message.is_empty() {
+        let _ = writeln!(out, "\n**Message:** {}", message);
+    }
+
+    let log_line = detection
+        .context
+        .first()
+        .map(String::as_str)
+        .unwrap_or(&detection.matched_text);
+    let _ = writeln!(out, "\n```log\n{}\n```", log_line);
+
+    if !detection.field_values.is_empty() {
+        let mut fields: Vec<_> = detection.field_values.iter().collect();
+        fields.sort();
+        let _ = writeln!(out, "\n**Parameters:**\n");
+        for (name, value) in fields {
+            let _ = writeln!(out, "- `{}`: {}", name, value);
+        }
+    }
+
+    if let Some(action) = &pattern.action {
+        let _ = writeln!(out, "\n**Suggested action:** {}", action);
+    }
+
+    out
+}
+
+fn command_action(
+    title: String,
+    kind: CodeActionKind,
+    diagnostic: Option<&Diagnostic>,
+    command: &str,
+    arguments: Vec<serde_json::Value>,
+) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title: title.clone(),
+        kind: Some(kind),
+        diagnostics: diagnostic.map(|diagnostic| vec![diagnostic.clone()]),
+        edit: None,
+        command: Some(Command {
+            title,
+            command: command.to_string(),
+            arguments: Some(arguments),
+        }),
+        is_preferred: Some(false),
+        disabled: None,
+        data: None,
+    })
+}
+
+/// Actions for a pattern diagnostic
+///
+/// Diagnostics of signatures, scenarios and suppressed detections get none,
+/// and a log-scout diagnostic whose data is not a payload is logged. Each
+/// action's `data` holds the parameters of `logScout/patternDetails`, so
+/// resolving it returns the details of the pattern and detection.
+pub fn detection_actions(uri: &Url, diagnostic: &Diagnostic) -> Vec<CodeActionOrCommand> {
+    let payload = match DiagnosticPayload::from_data(diagnostic.data.as_ref()) {
+        Some(Ok(DiagnosticPayload::Detection(payload))) => payload,
+        Some(Err(e)) if diagnostic.source.as_deref() == Some("log-scout") => {
+            tracing::warn!(
+                "No actions for '{}': unreadable diagnostic data: {}",
+                diagnostic.message,
+                e
+            );
+            return Vec::new();
+        }
+        _ => return Vec::new(),
+    };
+    if payload.suppressed_by.is_some() {
+        return Vec::new();
+    }
+    let pattern_name = &payload.pattern_name;
+
+    let uri_arg = serde_json::to_value(uri).unwrap();
+    let id_arg = serde_json::Value::String(payload.pattern_id.clone());
+    let line_arg = serde_json::Value::from(diagnostic.range.start.line);
+    let details = serde_json::to_value(PatternDetailsParams {
+        pattern_id: payload.pattern_id.clone(),
+        uri: Some(uri.clone()),
+        line: Some(diagnostic.range.start.line),
+    })
+    .unwrap();
+
+    let mut actions = vec![
+        command_action(
+            format!("Open remediation documentation for '{}'", pattern_name),
+            CodeActionKind::EMPTY,
+            Some(diagnostic),
+            OPEN_DOCUMENTATION_COMMAND,
+            vec![id_arg.clone()],
+        ),
+        command_action(
+            format!("Show all occurrences of '{}'", pattern_name),
+            CodeActionKind::EMPTY,
+            Some(diagnostic),
+            SHOW_OCCURRENCES_COMMAND,
+            vec![uri_arg.clone(), id_arg.clone()],
+        ),
+        command_action(
+            format!("Mute '{}' in this file", pattern_name),
+            CodeActionKind::QUICKFIX,
+            Some(diagnostic),
+            MUTE_COMMAND,
+            vec![uri_arg.clone(), id_arg.clone(), "file".into()],
+        ),
+        command_action(
+            format!("Mute '{}' everywhere", pattern_name),
+            CodeActionKind::QUICKFIX,
+            Some(diagnostic),
+            MUTE_COMMAND,
+            vec![uri_arg.clone(), id_arg.clone(), "everywhere".into()],
+        ),
+        command_action(
+            format!("Copy '{}' as ticket Markdown", pattern_name),
+            CodeActionKind::EMPTY,
+            Some(diagnostic),
+            TICKET_MARKDOWN_COMMAND,
+            vec![uri_arg, line_arg, id_arg],
+        ),
+    ];
+    for action in &mut actions {
+        if let CodeActionOrCommand::CodeAction(action) = action {
+            action.data = Some(details.clone());
+        }
+    }
+    actions
+}
+
+/// Action suggesting a pattern for the selected text, or for the whole line
+/// when nothing (or more than one line) is selected
+pub fn suggest_pattern_action(uri: &Url, range: Range) -> CodeActionOrCommand {
+    let uri_arg = serde_json::to_value(uri).unwrap();
+    if range.start.line == range.end.line && range.start.character < range.end.character {
+        command_action(
+            "Suggest pattern from selection".to_string(),
+            CodeActionKind::REFACTOR,
+            None,
+            SUGGEST_PATTERN_COMMAND,
+            vec![uri_arg, serde_json::to_value(range).unwrap()],
+        )
+    } else {
+        command_action(
+            "Suggest pattern from this line".to_string(),
+            CodeActionKind::REFACTOR,
+            None,
+            SUGGEST_PATTERN_COMMAND,
+            vec![uri_arg, range.start.line.into()],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+    use crate::payload::DetectionPayload;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    #[test]
+    fn test_documentation_url() {
+        assert_eq!(
+            documentation_url("See the runbook (https://wiki.example.com/sip-401).")
+                .unwrap()
+                .as_str(),
+            "https://wiki.example.com/sip-401"
+        );
+        assert!(documentation_url("Restart the client").is_none());
+    }
+
+    #[test]
+    fn test_ticket_markdown() {
+        // The failure is on line 42
+        let text = "\n".repeat(41) + "2024-01-15 10:00:00 SIP/2.0 401 user=alice";
+        let detection = detect_lines(
+            vec![Pattern {
+                name: "SIP unauthorized".to_string(),
+                category: "registration".to_string(),
+                service: Some("Jabber".to_string()),
+                action: Some("Check the credentials".to_string()),
+                ..Pattern::test("sip-401", r"SIP/2\.0 401 user=(?P<user>\w+)")
+            }],
+            &text,
+        )
+        .remove(0);
+        let uri = Url::parse("file:///logs/jabber.log").unwrap();
+
+        let markdown = ticket_markdown(&uri, &detection, "Registration rejected for alice");
+        assert!(markdown.starts_with("### SIP unauthorized\n"));
+        assert!(markdown.contains("| File | `jabber.log` |\n| Line | 42 |"));
+        assert!(markdown.contains("**Message:** Registration rejected for alice"));
+        assert!(markdown.contains("- `user`: alice"));
+        assert!(markdown.contains("**Suggested action:** Check the credentials"));
+
+        let diagnostic = Diagnostic {
+            range: Range::new(Position::new(41, 20), Position::new(41, 42)),
+            data: Some(DetectionPayload::new(&detection).to_value()),
+            ..Default::default()
+        };
+        let titles: Vec<String> = detection_actions(&uri, &diagnostic)
+            .into_iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => action.title,
+                CodeActionOrCommand::Command(command) => command.title,
+            })
+            .collect();
+        assert_eq!(titles.len(), 5);
+        assert_eq!(titles[4], "Copy 'SIP unauthorized' as ticket Markdown");
+
+        // Unreadable data gets no actions
+        let diagnostic = Diagnostic {
+            source: Some("log-scout".to_string()),
+            data: Some(serde_json::json!({ "patternId": "sip-401" })),
+            ..diagnostic
+        };
+        assert!(detection_actions(&uri, &diagnostic).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_pattern_action() {
+        let uri = Url::parse("file:///logs/jabber.log").unwrap();
+        let arguments = |range| match suggest_pattern_action(&uri, range) {
+            CodeActionOrCommand::CodeAction(action) => action.command.unwrap().arguments.unwrap(),
+            CodeActionOrCommand::Command(command) => command.arguments.unwrap(),
+        };
+
+        // A selection within the line is passed on, anything else means the line
+        let selection = Range::new(Position::new(3, 20), Position::new(3, 42));
+        assert_eq!(arguments(selection)[1]["start"]["character"], 20);
+        let cursor = Range::new(Position::new(3, 20), Position::new(3, 20));
+        assert_eq!(arguments(cursor)[1], 3);
+    }
+}
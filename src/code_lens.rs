@@ -0,0 +1,193 @@
+//! Code Lens Provider
+//!
+//! Summarizes pattern hits at the top of a log: a summary lens on the first
+//! line and a lens on the first occurrence of each matched pattern.
+
+use crate::pattern_engine::{line_range, Detection, Severity};
+use crate::timestamp;
+use std::collections::{HashMap, HashSet};
+use tower_lsp::lsp_types::{CodeLens, Command, Url};
+
+/// Command run when a lens is clicked; jumps to the next occurrence
+pub const NEXT_OCCURRENCE_COMMAND: &str = "logScout.nextOccurrence";
+
+/// Build all code lenses for a document from its detections
+pub fn build_code_lenses(uri: &Url, detections: &[Detection]) -> Vec<CodeLens> {
+    if detections.is_empty() {
+        return Vec::new();
+    }
+
+    let mut lenses = vec![summary_lens(uri, detections)];
+
+    // Group occurrences by pattern, keeping the order of first appearance
+    let mut order: Vec<&str> = Vec::new();
+    let mut occurrences: HashMap<&str, Vec<&Detection>> = HashMap::new();
+    for detection in detections {
+        let id = detection.pattern.id.as_str();
+        occurrences
+            .entry(id)
+            .or_insert_with(|| {
+                order.push(id);
+                Vec::new()
+            })
+            .push(detection);
+    }
+
+    for id in order {
+        let group = &occurrences[id];
+        let first = group[0];
+        let title = occurrence_title(group);
+
+        lenses.push(CodeLens {
+            range: line_range(first.line_number, (0, 0)),
+            command: Some(Command {
+                title,
+                command: NEXT_OCCURRENCE_COMMAND.to_string(),
+                arguments: Some(vec![
+                    serde_json::to_value(uri).unwrap(),
+                    serde_json::Value::String(id.to_string()),
+                ]),
+            }),
+            data: None,
+        });
+    }
+
+    lenses
+}
+
+/// Pick the occurrence to jump to on the `cursor`-th click, wrapping around
+///
+/// With `pattern_id` set only that pattern's occurrences are visited,
+/// otherwise every detection in the document is.
+pub fn occurrence_at<'a>(
+    detections: &'a [Detection],
+    pattern_id: Option<&str>,
+    cursor: usize,
+) -> Option<&'a Detection> {
+    let matching: Vec<&Detection> = detections
+        .iter()
+        .filter(|d| pattern_id.is_none_or(|id| d.pattern.id == id))
+        .collect();
+
+    if matching.is_empty() {
+        None
+    } else {
+        Some(matching[cursor % matching.len()])
+    }
+}
+
+/// Summary lens: "12 errors · 40 warnings · 5 categories"
+fn summary_lens(uri: &Url, detections: &[Detection]) -> CodeLens {
+    let errors = detections
+        .iter()
+        .filter(|d| d.final_severity == Severity::Error)
+        .count();
+    let warnings = detections
+        .iter()
+        .filter(|d| d.final_severity == Severity::Warning)
+        .count();
+    let categories = detections
+        .iter()
+        .map(|d| d.pattern.category.as_str())
+        .filter(|c| !c.is_empty())
+        .collect::<HashSet<_>>()
+        .len();
+
+    CodeLens {
+        range: line_range(0, (0, 0)),
+        command: Some(Command {
+            title: format!(
+                "{} · {} · {}",
+                plural(errors, "error", "errors"),
+                plural(warnings, "warning", "warnings"),
+                plural(categories, "category", "categories")
+            ),
+            command: NEXT_OCCURRENCE_COMMAND.to_string(),
+            arguments: Some(vec![serde_json::to_value(uri).unwrap()]),
+        }),
+        data: None,
+    }
+}
+
+/// Per-pattern title: "N occurrences, first at 10:02:11, last at 10:44:03"
+fn occurrence_title(group: &[&Detection]) -> String {
+    let times: Vec<String> = group
+        .iter()
+        .filter_map(|d| d.timestamp.as_deref())
+        .filter_map(timestamp::extract_timestamp)
+        .map(|ts| ts.time_of_day())
+        .collect();
+
+    let count = plural(group.len(), "occurrence", "occurrences");
+    match (times.first(), times.last()) {
+        (Some(first), Some(last)) if group.len() > 1 => {
+            format!("{}, first at {}, last at {}", count, first, last)
+        }
+        (Some(first), _) => format!("{} at {}", count, first),
+        _ => count,
+    }
+}
+
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+
+    #[test]
+    fn test_summary_and_pattern_lenses() {
+        let uri = Url::parse("file:///jabber.log").unwrap();
+        let detections = detect_lines(
+            vec![
+                Pattern::test_with("login-failed", "login failed", Severity::Error, "auth"),
+                Pattern::test_with("retry", "retrying", Severity::Warning, "network"),
+            ],
+            "2024-01-15 10:02:11 login failed\n\
+             2024-01-15 10:05:00 retrying\n\
+             2024-01-15 10:44:03 login failed",
+        );
+
+        let lenses = build_code_lenses(&uri, &detections);
+        assert_eq!(lenses.len(), 3);
+
+        let summary = lenses[0].command.as_ref().unwrap();
+        assert_eq!(summary.title, "2 errors · 1 warning · 2 categories");
+
+        let login = lenses[1].command.as_ref().unwrap();
+        assert_eq!(lenses[1].range.start.line, 0);
+        assert_eq!(
+            login.title,
+            "2 occurrences, first at 10:02:11, last at 10:44:03"
+        );
+
+        let retry = lenses[2].command.as_ref().unwrap();
+        assert_eq!(lenses[2].range.start.line, 1);
+        assert_eq!(retry.title, "1 occurrence at 10:05:00");
+    }
+
+    #[test]
+    fn test_occurrence_cycling() {
+        let detections = detect_lines(
+            vec![
+                Pattern::test("login-failed", "login failed"),
+                Pattern::test("retry", "retrying"),
+            ],
+            "login failed\nretrying\nlogin failed",
+        );
+
+        let lines: Vec<usize> = (0..3)
+            .map(|cursor| {
+                occurrence_at(&detections, Some("login-failed"), cursor)
+                    .unwrap()
+                    .line_number
+            })
+            .collect();
+        assert_eq!(lines, vec![0, 2, 0]);
+
+        assert_eq!(occurrence_at(&detections, None, 1).unwrap().line_number, 1);
+        assert!(occurrence_at(&detections, Some("missing"), 0).is_none());
+    }
+}
@@ -5,9 +5,20 @@
 
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::Url;
 
-use crate::pattern_engine::{Pattern, PatternError};
+use crate::dedup::{Precedence, DEFAULT_PRECEDENCE};
+use crate::pattern_engine::{ParameterExtractor, Pattern, PatternError, PatternSource, Severity};
+use crate::process::ProcessDefinition;
+use crate::scenario::ScenarioDefinition;
+use crate::severity_policy::SeverityRule;
+use crate::signature::SignatureDefinition;
+use crate::suppression::SuppressionMode;
+use crate::tagscout::{SyncMode, SyncServiceConfig};
+
+/// Directory (relative to a workspace root) holding local pattern packs
+pub const WORKSPACE_PATTERN_DIR: &str = ".logscout/patterns";
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,6 +103,10 @@ pub struct Settings {
     #[serde(default = "default_true")]
     pub correlation_enabled: bool,
 
+    /// Maximum time between correlated detections (in milliseconds)
+    #[serde(default = "default_correlation_window")]
+    pub correlation_window_ms: i64,
+
     /// Maximum file size to process (in MB)
     #[serde(default = "default_max_file_size")]
     pub max_file_size_mb: usize,
@@ -103,6 +118,48 @@ pub struct Settings {
     /// Enable background processing
     #[serde(default = "default_true")]
     pub background_processing: bool,
+
+    /// Names of analysis pipeline stages to skip
+    #[serde(default)]
+    pub disabled_stages: Vec<String>,
+
+    /// Rules remapping the evaluated severity of detections
+    #[serde(default)]
+    pub severity_policy: Vec<SeverityRule>,
+
+    /// Whether detections muted by `.logscout-ignore` are hidden or dimmed
+    #[serde(default)]
+    pub suppression_mode: SuppressionMode,
+
+    /// Occurrences of a pattern above which they collapse into one
+    /// summary diagnostic (0 disables)
+    #[serde(default = "default_aggregation_threshold")]
+    pub aggregation_threshold: usize,
+
+    /// Maximum diagnostics published per document (0 means no limit)
+    #[serde(default = "default_max_diagnostics")]
+    pub max_diagnostics_per_document: usize,
+
+    /// Product of the logs (e.g. "jabber"), preferred when patterns for
+    /// several products match the same text
+    #[serde(default)]
+    pub product: Option<String>,
+
+    /// Order of the criteria deciding between overlapping detections
+    #[serde(default = "default_dedup_precedence")]
+    pub dedup_precedence: Vec<Precedence>,
+
+    /// How TagScout patterns are synchronized
+    #[serde(default = "default_sync_mode")]
+    pub sync_mode: SyncMode,
+
+    /// Time before cached TagScout patterns are considered stale (in seconds)
+    #[serde(default = "default_cache_ttl")]
+    pub cache_ttl_seconds: u64,
+
+    /// Interval between background TagScout refreshes (in seconds, 0 disables)
+    #[serde(default = "default_refresh_interval")]
+    pub refresh_interval_seconds: u64,
 }
 
 impl Default for Settings {
@@ -113,13 +170,180 @@ impl Default for Settings {
             multiline_context_window: default_context_window(),
             baseline_learning: true,
             correlation_enabled: true,
+            correlation_window_ms: default_correlation_window(),
             max_file_size_mb: default_max_file_size(),
             streaming_chunk_size_kb: default_chunk_size(),
             background_processing: true,
+            disabled_stages: Vec::new(),
+            severity_policy: Vec::new(),
+            suppression_mode: SuppressionMode::default(),
+            aggregation_threshold: default_aggregation_threshold(),
+            max_diagnostics_per_document: default_max_diagnostics(),
+            product: None,
+            dedup_precedence: default_dedup_precedence(),
+            sync_mode: default_sync_mode(),
+            cache_ttl_seconds: default_cache_ttl(),
+            refresh_interval_seconds: default_refresh_interval(),
+        }
+    }
+}
+
+impl Settings {
+    /// TagScout sync service configuration for these settings
+    pub fn sync_service_config(&self) -> SyncServiceConfig {
+        SyncServiceConfig {
+            sync_mode: self.sync_mode,
+            cache_ttl_seconds: self.cache_ttl_seconds,
+            auto_refresh_interval: (self.refresh_interval_seconds > 0)
+                .then_some(self.refresh_interval_seconds),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the pattern engine must be rebuilt to apply `other`
+    pub fn engine_changed(&self, other: &Settings) -> bool {
+        self.detection_threshold != other.detection_threshold
+            || self.multiline_context_window != other.multiline_context_window
+    }
+
+    /// Whether the TagScout sync service must be recreated to apply `other`
+    pub fn sync_changed(&self, other: &Settings) -> bool {
+        self.sync_mode != other.sync_mode
+            || self.cache_ttl_seconds != other.cache_ttl_seconds
+            || self.refresh_interval_seconds != other.refresh_interval_seconds
+    }
+}
+
+/// Settings that configure the shared pattern engine and TagScout sync
+/// service, so they cannot be overridden per workspace folder
+const SERVER_WIDE_KEYS: [&str; 5] = [
+    "detection_threshold",
+    "multiline_context_window",
+    "sync_mode",
+    "cache_ttl_seconds",
+    "refresh_interval_seconds",
+];
+
+/// Section of the client configuration holding the settings
+pub const SETTINGS_SECTION: &str = "logScout";
+
+/// Settings sent by the client, with per-workspace-folder overrides
+///
+/// Read from `initializationOptions` and `workspace/didChangeConfiguration`.
+/// The payload is either the settings object itself or wraps it in a
+/// `logScout` section. A `folders` object maps workspace folder paths (or
+/// `file://` URIs) to partial settings that override the global ones for
+/// documents inside that folder.
+#[derive(Debug, Clone, Default)]
+pub struct ClientSettings {
+    pub global: Settings,
+
+    /// Folder and its effective settings, most specific folder first
+    pub folders: Vec<(PathBuf, Settings)>,
+}
+
+impl ClientSettings {
+    /// The settings in a `didChangeConfiguration` payload, if it has any
+    ///
+    /// That is its `logScout` section, or the payload itself when it has
+    /// settings keys. Pull-model clients send `null` (or only their other
+    /// sections) to signal a change, which gives `None`.
+    pub fn section(value: &serde_json::Value) -> Option<&serde_json::Value> {
+        if let Some(section) = value.get(SETTINGS_SECTION) {
+            return (!section.is_null()).then_some(section);
+        }
+        let known = serde_json::to_value(Settings::default()).ok()?;
+        value
+            .as_object()?
+            .keys()
+            .any(|key| key == "folders" || known.get(key).is_some())
+            .then_some(value)
+    }
+
+    /// Parse the settings payload sent by the client
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, PatternError> {
+        let value = value.get(SETTINGS_SECTION).unwrap_or(value);
+        let invalid =
+            |e: serde_json::Error| PatternError::ConfigError(format!("Invalid settings: {}", e));
+
+        let mut base = match value {
+            serde_json::Value::Object(map) => map.clone(),
+            serde_json::Value::Null => serde_json::Map::new(),
+            _ => {
+                return Err(PatternError::ConfigError(
+                    "Settings must be an object".to_string(),
+                ))
+            }
+        };
+        let overrides = base.remove("folders");
+        let global: Settings =
+            serde_json::from_value(serde_json::Value::Object(base.clone())).map_err(invalid)?;
+
+        let mut partials: Vec<(PathBuf, serde_json::Map<String, serde_json::Value>)> = Vec::new();
+        if let Some(serde_json::Value::Object(overrides)) = overrides {
+            for (folder, partial) in overrides {
+                let serde_json::Value::Object(partial) = partial else {
+                    continue;
+                };
+                if let Some(key) = SERVER_WIDE_KEYS
+                    .iter()
+                    .find(|key| partial.contains_key(**key))
+                {
+                    return Err(PatternError::ConfigError(format!(
+                        "`{}` applies to the whole server and cannot be set for folder {}",
+                        key, folder
+                    )));
+                }
+                partials.push((folder_path(&folder), partial));
+            }
+        }
+        partials.sort_by_key(|(path, _)| path.components().count());
+
+        // Nested folders apply the overrides of their parents first
+        let mut folders = Vec::new();
+        for (folder, _) in &partials {
+            let mut merged = base.clone();
+            for (parent, partial) in &partials {
+                if folder.starts_with(parent) {
+                    merged.extend(partial.clone());
+                }
+            }
+            let settings: Settings =
+                serde_json::from_value(serde_json::Value::Object(merged)).map_err(invalid)?;
+            folders.push((folder.clone(), settings));
+        }
+        folders.reverse();
+
+        Ok(Self { global, folders })
+    }
+
+    /// Effective settings for a file: the override of the innermost
+    /// folder containing it, or the global settings
+    pub fn for_path(&self, path: &Path) -> &Settings {
+        self.folders
+            .iter()
+            .find(|(folder, _)| path.starts_with(folder))
+            .map_or(&self.global, |(_, settings)| settings)
+    }
+
+    /// Effective settings for a document; documents that are not files
+    /// get the global settings
+    pub fn for_uri(&self, uri: &Url) -> &Settings {
+        match uri.to_file_path() {
+            Ok(path) => self.for_path(&path),
+            Err(_) => &self.global,
         }
     }
 }
 
+/// Path of a folder given as a path or a `file://` URI
+fn folder_path(folder: &str) -> PathBuf {
+    Url::parse(folder)
+        .ok()
+        .and_then(|uri| uri.to_file_path().ok())
+        .unwrap_or_else(|| PathBuf::from(folder))
+}
+
 fn default_threshold() -> f32 {
     0.85
 }
@@ -128,6 +352,34 @@ fn default_context_window() -> usize {
     10
 }
 
+fn default_correlation_window() -> i64 {
+    5_000
+}
+
+fn default_sync_mode() -> SyncMode {
+    SyncMode::CacheFirst
+}
+
+fn default_cache_ttl() -> u64 {
+    3600
+}
+
+fn default_refresh_interval() -> u64 {
+    300
+}
+
+fn default_aggregation_threshold() -> usize {
+    100
+}
+
+fn default_max_diagnostics() -> usize {
+    2000
+}
+
+fn default_dedup_precedence() -> Vec<Precedence> {
+    DEFAULT_PRECEDENCE.to_vec()
+}
+
 fn default_max_file_size() -> usize {
     100
 }
@@ -169,6 +421,275 @@ pub fn parse_patterns(yaml: &str) -> Result<Vec<Pattern>, PatternError> {
     Ok(pattern_file.patterns)
 }
 
+/// Load signature definitions from a pattern pack's YAML
+///
+/// Packs without a `signatures:` list define no signatures.
+pub fn parse_signatures(yaml: &str) -> Result<Vec<SignatureDefinition>, PatternError> {
+    #[derive(Deserialize)]
+    struct SignatureFile {
+        #[serde(default)]
+        signatures: Vec<SignatureDefinition>,
+    }
+
+    let signature_file: SignatureFile = serde_yaml::from_str(yaml)
+        .map_err(|e| PatternError::ConfigError(format!("Failed to parse signatures: {}", e)))?;
+
+    Ok(signature_file.signatures)
+}
+
+/// Load process definitions from a pattern pack's YAML
+///
+/// Packs without a `processes:` list define no processes.
+pub fn parse_processes(yaml: &str) -> Result<Vec<ProcessDefinition>, PatternError> {
+    #[derive(Deserialize)]
+    struct ProcessFile {
+        #[serde(default)]
+        processes: Vec<ProcessDefinition>,
+    }
+
+    let process_file: ProcessFile = serde_yaml::from_str(yaml)
+        .map_err(|e| PatternError::ConfigError(format!("Failed to parse processes: {}", e)))?;
+
+    Ok(process_file.processes)
+}
+
+/// Load scenario definitions from a pattern pack's YAML
+///
+/// Packs without a `scenarios:` list define no scenarios.
+pub fn parse_scenarios(yaml: &str) -> Result<Vec<ScenarioDefinition>, PatternError> {
+    #[derive(Deserialize)]
+    struct ScenarioFile {
+        #[serde(default)]
+        scenarios: Vec<ScenarioDefinition>,
+    }
+
+    let scenario_file: ScenarioFile = serde_yaml::from_str(yaml)
+        .map_err(|e| PatternError::ConfigError(format!("Failed to parse scenarios: {}", e)))?;
+
+    Ok(scenario_file.scenarios)
+}
+
+/// Load a local pattern pack, recording where each pattern is defined
+pub fn load_pattern_pack<P: AsRef<Path>>(path: P) -> Result<Vec<Pattern>, PatternError> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).map_err(|e| {
+        PatternError::ConfigError(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+
+    let mut patterns = parse_patterns(&content)?;
+    for pattern in &mut patterns {
+        pattern.source = Some(PatternSource {
+            path: path.to_path_buf(),
+            line: find_pattern_line(&content, &pattern.id).unwrap_or(0),
+        });
+    }
+
+    Ok(patterns)
+}
+
+/// Append a pattern to a pattern pack, creating the pack if needed
+///
+/// The entry is inserted as text at the end of the `patterns:` list, before
+/// any key that follows it, so existing comments survive. Returns the
+/// 0-based line of the new entry's `id`.
+pub fn append_pattern(path: &Path, pattern: &Pattern) -> Result<usize, PatternError> {
+    #[derive(Serialize)]
+    struct PackEntry<'a> {
+        id: &'a str,
+        name: &'a str,
+        description: &'a str,
+        pattern: &'a str,
+        severity: Severity,
+        category: &'a str,
+        #[serde(skip_serializing_if = "<[_]>::is_empty")]
+        parameter_extractors: &'a [ParameterExtractor],
+    }
+
+    let entry = serde_yaml::to_string(&PackEntry {
+        id: &pattern.id,
+        name: &pattern.name,
+        description: &pattern.annotation,
+        pattern: &pattern.pattern,
+        severity: pattern.severity,
+        category: &pattern.category,
+        parameter_extractors: &pattern.parameter_extractors,
+    })
+    .map_err(|e| PatternError::ConfigError(format!("Failed to write pattern: {}", e)))?;
+
+    let content = fs::read_to_string(path).unwrap_or_default();
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    let key = lines.iter().position(|line| {
+        line.strip_prefix("patterns:").is_some_and(|rest| {
+            let rest = rest.split('#').next().unwrap_or_default().trim();
+            rest.is_empty() || rest == "[]"
+        })
+    });
+    let key = match key {
+        Some(key) => {
+            if lines[key].contains("[]") {
+                lines[key] = "patterns:".to_string();
+            }
+            key
+        }
+        None => {
+            lines.push("patterns:".to_string());
+            lines.len() - 1
+        }
+    };
+
+    // The list ends at its last item line before the next top-level key;
+    // blank lines and comments after it stay with that key
+    let in_list = |line: &String| line.is_empty() || line.starts_with([' ', '\t', '-', '#']);
+    let is_item = |line: &String| !line.trim().is_empty() && line.starts_with([' ', '\t', '-']);
+    let list = &lines[key + 1..];
+    let list_len = list.iter().take_while(|line| in_list(line)).count();
+    let line = key
+        + 1
+        + list[..list_len]
+            .iter()
+            .rposition(is_item)
+            .map_or(0, |i| i + 1);
+
+    let indent = lines[key + 1..line]
+        .iter()
+        .find_map(|item| {
+            let trimmed = item.trim_start();
+            trimmed
+                .starts_with('-')
+                .then(|| item[..item.len() - trimmed.len()].to_string())
+        })
+        .unwrap_or_else(|| "  ".to_string());
+    let entry_lines = entry.lines().enumerate().map(|(index, entry_line)| {
+        let marker = if index == 0 { "- " } else { "  " };
+        format!("{}{}{}", indent, marker, entry_line)
+    });
+    lines.splice(line..line, entry_lines);
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| PatternError::ConfigError(e.to_string()))?;
+    }
+    fs::write(path, content).map_err(|e| {
+        PatternError::ConfigError(format!("Failed to write {}: {}", path.display(), e))
+    })?;
+    Ok(line)
+}
+
+/// Load every `.yaml`/`.yml` pattern pack found in the given directories
+///
+/// Packs that fail to parse are skipped with a warning so one bad file
+/// does not disable the others.
+pub fn load_pattern_packs(dirs: &[PathBuf]) -> Vec<Pattern> {
+    let mut pattern_sets = Vec::new();
+
+    for file in pattern_pack_files(dirs) {
+        match load_pattern_pack(&file) {
+            Ok(patterns) => {
+                tracing::info!("Loaded {} patterns from {}", patterns.len(), file.display());
+                pattern_sets.push(patterns);
+            }
+            Err(e) => tracing::warn!("Skipping pattern pack {}: {}", file.display(), e),
+        }
+    }
+
+    merge_patterns(pattern_sets)
+}
+
+/// Load the signature definitions of every pattern pack in the given directories
+pub fn load_signature_packs(dirs: &[PathBuf]) -> Vec<SignatureDefinition> {
+    load_pack_definitions(dirs, parse_signatures, |signature| &signature.id)
+}
+
+/// Load the process definitions of every pattern pack in the given directories
+pub fn load_process_packs(dirs: &[PathBuf]) -> Vec<ProcessDefinition> {
+    load_pack_definitions(dirs, parse_processes, |process| &process.id)
+}
+
+/// Load the scenario definitions of every pattern pack in the given directories
+pub fn load_scenario_packs(dirs: &[PathBuf]) -> Vec<ScenarioDefinition> {
+    load_pack_definitions(dirs, parse_scenarios, |scenario| &scenario.id)
+}
+
+/// Load one kind of definition from every pattern pack
+///
+/// The first definition of an ID wins, as for patterns. Packs that fail to
+/// parse are skipped with a warning.
+fn load_pack_definitions<T>(
+    dirs: &[PathBuf],
+    parse: fn(&str) -> Result<Vec<T>, PatternError>,
+    id: fn(&T) -> &String,
+) -> Vec<T> {
+    let mut definitions = Vec::new();
+    let mut seen_ids = std::collections::HashSet::new();
+
+    for file in pattern_pack_files(dirs) {
+        let parsed = fs::read_to_string(&file)
+            .map_err(|e| PatternError::ConfigError(e.to_string()))
+            .and_then(|content| parse(&content));
+        match parsed {
+            Ok(defined) => definitions.extend(
+                defined
+                    .into_iter()
+                    .filter(|definition| seen_ids.insert(id(definition).clone())),
+            ),
+            Err(e) => tracing::warn!("Skipping definitions in {}: {}", file.display(), e),
+        }
+    }
+
+    definitions
+}
+
+/// `.yaml`/`.yml` files in the given directories, sorted per directory
+fn pattern_pack_files(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut pack_files = Vec::new();
+
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == "yaml" || ext == "yml")
+            })
+            .collect();
+        files.sort();
+        pack_files.extend(files);
+    }
+
+    pack_files
+}
+
+/// Pattern pack directories for the given workspace roots, plus the user's own
+pub fn pattern_pack_dirs(workspace_roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = workspace_roots
+        .iter()
+        .map(|root| root.join(WORKSPACE_PATTERN_DIR))
+        .collect();
+
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".log-scout-analyzer").join("patterns"));
+    }
+
+    dirs
+}
+
+/// Find the line of a pattern's `id:` entry in a pattern pack
+fn find_pattern_line(yaml: &str, id: &str) -> Option<usize> {
+    yaml.lines().position(|line| {
+        let entry = line.trim_start().trim_start_matches('-').trim_start();
+        entry
+            .strip_prefix("id:")
+            .map(|value| value.trim().trim_matches(|c| c == '"' || c == '\''))
+            == Some(id)
+    })
+}
+
 /// Merge multiple pattern files into one configuration
 pub fn merge_patterns(pattern_sets: Vec<Vec<Pattern>>) -> Vec<Pattern> {
     let mut merged = Vec::new();
@@ -262,6 +783,79 @@ patterns:
         assert_eq!(patterns[1].id, "warning-1");
     }
 
+    #[test]
+    fn test_parse_signatures() {
+        let yaml = r#"
+patterns:
+  - id: "vpn-reconnect"
+    name: "VPN reconnect"
+    pattern: "VPN reconnect"
+    category: "registration"
+signatures:
+  - id: "vpn-registration-loop"
+    name: "VPN-induced registration loop"
+    category: "registration"
+    patterns: ["vpn-reconnect", "sip-401"]
+    window_ms: 10000
+"#;
+
+        let signatures = parse_signatures(yaml).unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0].patterns.len(), 2);
+        assert_eq!(signatures[0].window_ms, 10_000);
+        assert_eq!(signatures[0].window_lines, 200);
+        assert!(signatures[0].severity.is_none());
+
+        assert!(parse_signatures("patterns: []").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_processes() {
+        let yaml = r#"
+patterns: []
+processes:
+  - id: "call-setup"
+    name: "Call setup"
+    key: "callId"
+    steps:
+      - name: "Invite"
+        pattern: "sip-invite"
+      - pattern: "sip-200"
+    failure_patterns: ["sip-486"]
+"#;
+
+        let processes = parse_processes(yaml).unwrap();
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].steps.len(), 2);
+        assert_eq!(processes[0].steps[1].name, "");
+        assert_eq!(processes[0].timeout_ms, 30_000);
+    }
+
+    #[test]
+    fn test_parse_scenarios() {
+        let yaml = r#"
+patterns: []
+scenarios:
+  - id: "network-sso-calls"
+    name: "Network change breaks SSO and calls"
+    steps:
+      - signature: "network-change"
+      - label: "SSO"
+        process: "sso-login"
+        status: failed
+        weight: 2
+"#;
+
+        let scenarios = parse_scenarios(yaml).unwrap();
+        assert_eq!(scenarios.len(), 1);
+        assert!(scenarios[0].ordered);
+        assert_eq!(scenarios[0].steps[0].weight, 1.0);
+        assert_eq!(
+            scenarios[0].steps[1].status,
+            Some(crate::process::ProcessStatus::Failed)
+        );
+    }
+
     #[test]
     fn test_default_settings() {
         let settings = Settings::default();
@@ -271,6 +865,73 @@ patterns:
         assert!(settings.baseline_learning);
     }
 
+    #[test]
+    fn test_client_settings_with_folder_overrides() {
+        let payload = serde_json::json!({
+            "logScout": {
+                "sync_mode": "offline_only",
+                "refresh_interval_seconds": 0,
+                "folders": {
+                    "/work/webex": { "correlation_enabled": false },
+                    "file:///work/webex/legacy": { "disabled_stages": ["scenarios"] }
+                }
+            }
+        });
+
+        let settings = ClientSettings::from_json(&payload).unwrap();
+        assert_eq!(settings.global.sync_mode, SyncMode::OfflineOnly);
+        assert_eq!(
+            settings.global.sync_service_config().auto_refresh_interval,
+            None
+        );
+        assert_eq!(settings.global.multiline_context_window, 10);
+
+        let webex = settings.for_path(Path::new("/work/webex/app.log"));
+        assert!(!webex.correlation_enabled);
+        assert_eq!(webex.sync_mode, SyncMode::OfflineOnly);
+        let uri = Url::parse("file:///work/webex/app.log").unwrap();
+        assert!(!settings.for_uri(&uri).correlation_enabled);
+
+        // Nested overrides stack
+        let legacy = settings.for_path(Path::new("/work/webex/legacy/app.log"));
+        assert!(!legacy.correlation_enabled);
+        assert_eq!(legacy.disabled_stages, vec!["scenarios"]);
+
+        assert!(
+            settings
+                .for_path(Path::new("/other/app.log"))
+                .correlation_enabled
+        );
+        assert!(ClientSettings::from_json(&serde_json::json!({"cache_ttl_seconds": "x"})).is_err());
+    }
+
+    #[test]
+    fn test_folder_overrides_reject_server_wide_settings() {
+        let result = ClientSettings::from_json(&serde_json::json!({
+            "folders": { "/work/webex-logs": { "detection_threshold": 0.5 } }
+        }));
+        assert!(matches!(result, Err(PatternError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_settings_section() {
+        let section = |value| ClientSettings::section(&value).cloned();
+        assert_eq!(section(serde_json::Value::Null), None);
+        assert_eq!(section(serde_json::json!({ "logScout": null })), None);
+        assert_eq!(
+            section(serde_json::json!({ "editor": { "tabSize": 4 } })),
+            None
+        );
+        assert_eq!(
+            section(serde_json::json!({ "logScout": { "product": "jabber" } })),
+            Some(serde_json::json!({ "product": "jabber" }))
+        );
+        assert_eq!(
+            section(serde_json::json!({ "product": "jabber" })),
+            Some(serde_json::json!({ "product": "jabber" }))
+        );
+    }
+
     #[test]
     fn test_validate_config() {
         let config = Config {
@@ -305,6 +966,84 @@ patterns:
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_load_pattern_pack_records_source() {
+        let dir = std::env::temp_dir().join("logscout_pattern_pack_test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pack.yaml");
+        fs::write(
+            &path,
+            r#"patterns:
+  - id: "first"
+    name: "First"
+    description: "First pattern"
+    pattern: "ONE"
+    severity: error
+  - name: "Second"
+    id: second
+    description: "Second pattern"
+    pattern: "TWO"
+    severity: warning
+"#,
+        )
+        .unwrap();
+
+        let patterns = load_pattern_packs(std::slice::from_ref(&dir));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(patterns.len(), 2);
+        let first = patterns[0].source.as_ref().unwrap();
+        assert_eq!(first.path, path);
+        assert_eq!(first.line, 1);
+        assert_eq!(patterns[1].source.as_ref().unwrap().line, 7);
+    }
+
+    #[test]
+    fn test_append_pattern() {
+        let dir = std::env::temp_dir().join(format!("logscout-append-{}", std::process::id()));
+        let path = dir.join("drafts.yaml");
+        let draft = crate::pattern_draft::draft_from_line("ERROR disk C:\\ full", |_| false);
+
+        assert_eq!(append_pattern(&path, &draft).unwrap(), 1);
+        let second = Pattern {
+            id: "second".to_string(),
+            ..draft.clone()
+        };
+        let line = append_pattern(&path, &second).unwrap();
+
+        let patterns = load_pattern_pack(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(patterns[0].pattern, draft.pattern);
+        assert_eq!(patterns[0].annotation, "ERROR disk C:\\ full");
+        assert_eq!(patterns[1].source.as_ref().unwrap().line, line);
+    }
+
+    #[test]
+    fn test_append_pattern_before_next_key() {
+        let dir = std::env::temp_dir().join(format!("logscout-append-key-{}", std::process::id()));
+        let path = dir.join("pack.yaml");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &path,
+            "patterns:\n- id: first\n  name: First\n  description: First\n  pattern: FIRST\n  \
+             severity: error\n  category: test\n\n# Pack metadata\nversion: 3\n",
+        )
+        .unwrap();
+
+        let line = append_pattern(&path, &Pattern::test("second", "SECOND")).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        let patterns = load_pattern_pack(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(line, 7);
+        assert_eq!(content.lines().nth(line), Some("- id: second"));
+        assert!(content.ends_with("\n# Pack metadata\nversion: 3\n"));
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(patterns[1].source.as_ref().unwrap().line, line);
+    }
+
     #[test]
     fn test_merge_patterns() {
         let set1 = vec![Pattern {
@@ -324,7 +1063,9 @@ patterns:
             condition_triggers: Vec::new(),
             capture_fields: Vec::new(),
             parameter_extractors: Vec::new(),
+            priority: 0,
             tagscout_metadata: None,
+            source: None,
         }];
 
         let set2 = vec![Pattern {
@@ -344,7 +1085,9 @@ patterns:
             condition_triggers: Vec::new(),
             capture_fields: Vec::new(),
             parameter_extractors: Vec::new(),
+            priority: 0,
             tagscout_metadata: None,
+            source: None,
         }];
 
         let merged = merge_patterns(vec![set1, set2]);
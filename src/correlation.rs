@@ -0,0 +1,502 @@
+//! Cross-Log Correlation
+//!
+//! Links detections in different documents that share an extracted
+//! parameter value (request ID, call ID, device ID) and happened close
+//! together in time, e.g. a server-side 500 and the client-side
+//! "login failed" for the same request.
+//!
+//! Each document's correlation keys and timestamps are indexed once per
+//! analysis, and a changed document is only re-linked against the indexes
+//! of its peers.
+
+use crate::pattern_engine::Detection;
+use crate::timestamp::{self, LogTimestamp};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tower_lsp::lsp_types::{DiagnosticRelatedInformation, Location, Position, Range, Url};
+
+/// Values shared by more detections than this are too common to correlate on
+const MAX_GROUP_SIZE: usize = 200;
+
+/// Minimum length of a parameter value used as a correlation key
+const MIN_KEY_LEN: usize = 4;
+
+/// Identifies a detection within a document: line, column range and pattern ID
+type DetectionKey = (usize, (usize, usize), String);
+
+/// A link from one detection to a detection in another document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationLink {
+    pub uri: Url,
+    pub line_number: usize,
+    pub column_range: (usize, usize),
+    pub pattern_name: String,
+
+    /// Parameter name (in the linked detection) and shared value
+    pub parameter: String,
+    pub value: String,
+
+    /// Time from this detection to the linked one, when both have timestamps
+    pub delta_ms: Option<i64>,
+}
+
+/// Correlation keys and parsed timestamps of one document's detections
+#[derive(Debug, Default)]
+pub struct CorrelationIndex {
+    detections: Arc<Vec<Detection>>,
+    timestamps: Vec<Option<LogTimestamp>>,
+
+    /// Parameter value -> (detection index, parameter name)
+    keys: HashMap<String, Vec<(usize, String)>>,
+}
+
+impl CorrelationIndex {
+    pub fn new(detections: Arc<Vec<Detection>>) -> Self {
+        let mut keys: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+        for (index, detection) in detections.iter().enumerate() {
+            for (name, value) in &detection.field_values {
+                if is_correlation_key(value) {
+                    keys.entry(value.clone())
+                        .or_default()
+                        .push((index, name.clone()));
+                }
+            }
+        }
+
+        let timestamps = detections
+            .iter()
+            .map(|d| {
+                d.timestamp
+                    .as_deref()
+                    .and_then(timestamp::extract_timestamp)
+            })
+            .collect();
+
+        Self {
+            detections,
+            timestamps,
+            keys,
+        }
+    }
+
+    /// Number of detections extracting `value`
+    fn group_len(&self, value: &str) -> usize {
+        self.keys.get(value).map_or(0, Vec::len)
+    }
+}
+
+/// Links between detections across documents
+#[derive(Debug, Default)]
+pub struct Correlations {
+    links: HashMap<Url, HashMap<DetectionKey, Vec<CorrelationLink>>>,
+}
+
+impl Correlations {
+    /// Links of a detection in a document
+    pub fn links_for(&self, uri: &Url, detection: &Detection) -> &[CorrelationLink] {
+        self.links
+            .get(uri)
+            .and_then(|links| links.get(&key(detection)))
+            .map_or(&[], |links| links.as_slice())
+    }
+
+    /// Links of a detection as diagnostic related information
+    pub fn related_information(
+        &self,
+        uri: &Url,
+        detection: &Detection,
+    ) -> Vec<DiagnosticRelatedInformation> {
+        self.links_for(uri, detection)
+            .iter()
+            .map(|link| {
+                let line = link.line_number as u32;
+                let delta = link
+                    .delta_ms
+                    .map(|ms| format!(" ({})", timestamp::format_delta(ms)))
+                    .unwrap_or_default();
+                DiagnosticRelatedInformation {
+                    location: Location {
+                        uri: link.uri.clone(),
+                        range: Range {
+                            start: Position {
+                                line,
+                                character: link.column_range.0 as u32,
+                            },
+                            end: Position {
+                                line,
+                                character: link.column_range.1 as u32,
+                            },
+                        },
+                    },
+                    message: format!(
+                        "Same {}={} in {}: {}{}",
+                        link.parameter,
+                        link.value,
+                        file_name(&link.uri),
+                        link.pattern_name,
+                        delta
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    /// Documents with detections linked to detections in `uri`
+    pub fn linked_documents(&self, uri: &Url) -> HashSet<Url> {
+        self.links
+            .get(uri)
+            .into_iter()
+            .flat_map(|links| links.values().flatten())
+            .map(|link| link.uri.clone())
+            .collect()
+    }
+
+    /// Whether any links were found
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Forget the links from and to a document
+    ///
+    /// Returns the documents it was linked with.
+    pub fn remove(&mut self, uri: &Url) -> HashSet<Url> {
+        let mut linked: HashSet<Url> = self
+            .links
+            .remove(uri)
+            .into_iter()
+            .flat_map(|links| links.into_values().flatten())
+            .map(|link| link.uri)
+            .collect();
+        for (peer_uri, links) in self.links.iter_mut() {
+            for detection_links in links.values_mut() {
+                let before = detection_links.len();
+                detection_links.retain(|link| &link.uri != uri);
+                if detection_links.len() != before {
+                    linked.insert(peer_uri.clone());
+                }
+            }
+            links.retain(|_, detection_links| !detection_links.is_empty());
+        }
+        self.links.retain(|_, links| !links.is_empty());
+        linked
+    }
+
+    /// Re-link a document against its peers
+    ///
+    /// Replaces the links from and to `uri` and keeps the links between
+    /// peers, so the cost follows the document's detections rather than
+    /// those of every document. Two detections are linked when they share a
+    /// parameter value and, if both carry timestamps, happened at most
+    /// `window_ms` apart. Each detection keeps at most `max_links` links.
+    ///
+    /// Returns the documents linked with `uri` before or after, whose
+    /// links changed.
+    pub fn update(
+        &mut self,
+        uri: &Url,
+        index: &CorrelationIndex,
+        peers: &[(Url, Arc<CorrelationIndex>)],
+        window_ms: i64,
+        max_links: usize,
+    ) -> HashSet<Url> {
+        let mut changed = self.remove(uri);
+
+        // (peer, own detection, peer detection) pairs already linked
+        let mut linked: HashSet<(usize, usize, usize)> = HashSet::new();
+
+        for (value, own) in &index.keys {
+            let group_len = own.len()
+                + peers
+                    .iter()
+                    .map(|(_, peer)| peer.group_len(value))
+                    .sum::<usize>();
+            if !(2..=MAX_GROUP_SIZE).contains(&group_len) {
+                continue;
+            }
+
+            for (peer_index, (peer_uri, peer)) in peers.iter().enumerate() {
+                let Some(theirs) = peer.keys.get(value).filter(|_| peer_uri != uri) else {
+                    continue;
+                };
+                for (own_det, own_name) in own {
+                    for (their_det, their_name) in theirs {
+                        if !linked.insert((peer_index, *own_det, *their_det)) {
+                            continue;
+                        }
+
+                        let (from, to) =
+                            (&index.timestamps[*own_det], &peer.timestamps[*their_det]);
+                        let (forward, backward) = match (from, to) {
+                            (Some(from), Some(to)) => {
+                                (Some(delta_ms(from, to)), Some(delta_ms(to, from)))
+                            }
+                            _ => (None, None),
+                        };
+                        if forward.is_some_and(|ms| ms.abs() > window_ms) {
+                            continue;
+                        }
+
+                        changed.insert(peer_uri.clone());
+                        let own_detection = &index.detections[*own_det];
+                        let their_detection = &peer.detections[*their_det];
+                        self.link(
+                            uri,
+                            own_detection,
+                            peer_uri,
+                            their_detection,
+                            their_name,
+                            value,
+                            forward,
+                            max_links,
+                        );
+                        self.link(
+                            peer_uri,
+                            their_detection,
+                            uri,
+                            own_detection,
+                            own_name,
+                            value,
+                            backward,
+                            max_links,
+                        );
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    /// Add a link from a detection, keeping its links ordered by document
+    /// then line
+    #[allow(clippy::too_many_arguments)]
+    fn link(
+        &mut self,
+        from_uri: &Url,
+        from: &Detection,
+        to_uri: &Url,
+        to: &Detection,
+        parameter: &str,
+        value: &str,
+        delta_ms: Option<i64>,
+        max_links: usize,
+    ) {
+        let links = self
+            .links
+            .entry(from_uri.clone())
+            .or_default()
+            .entry(key(from))
+            .or_default();
+        if links.len() >= max_links {
+            return;
+        }
+
+        let position =
+            links.partition_point(|link| (&link.uri, link.line_number) <= (to_uri, to.line_number));
+        links.insert(
+            position,
+            CorrelationLink {
+                uri: to_uri.clone(),
+                line_number: to.line_number,
+                column_range: to.column_range,
+                pattern_name: to.pattern.name.clone(),
+                parameter: parameter.to_string(),
+                value: value.to_string(),
+                delta_ms,
+            },
+        );
+    }
+}
+
+/// Link detections across documents
+///
+/// Correlates each document against the ones before it; see
+/// `Correlations::update`.
+pub fn correlate(
+    documents: &[(Url, Arc<Vec<Detection>>)],
+    window_ms: i64,
+    max_links: usize,
+) -> Correlations {
+    let mut correlations = Correlations::default();
+    let mut indexes: Vec<(Url, Arc<CorrelationIndex>)> = Vec::with_capacity(documents.len());
+    for (uri, detections) in documents {
+        let index = Arc::new(CorrelationIndex::new(Arc::clone(detections)));
+        correlations.update(uri, &index, &indexes, window_ms, max_links);
+        indexes.push((uri.clone(), index));
+    }
+    correlations
+}
+
+/// Whether a parameter value is specific enough to link detections on
+///
+/// Short values and plain small numbers (status codes, counters, enum
+/// values) are shared by unrelated events, so they are ignored.
+pub fn is_correlation_key(value: &str) -> bool {
+    let value = value.trim();
+    if value.len() < MIN_KEY_LEN {
+        return false;
+    }
+
+    let all_digits = value.chars().all(|c| c.is_ascii_digit());
+    !all_digits || value.len() >= 6
+}
+
+/// Milliseconds from `from` to `to`
+///
+/// When either log only has times of day, only the times are compared.
+fn delta_ms(from: &LogTimestamp, to: &LogTimestamp) -> i64 {
+    if from.has_date && to.has_date {
+        to.datetime
+            .signed_duration_since(from.datetime)
+            .num_milliseconds()
+    } else {
+        to.datetime
+            .time()
+            .signed_duration_since(from.datetime.time())
+            .num_milliseconds()
+    }
+}
+
+fn key(detection: &Detection) -> DetectionKey {
+    (
+        detection.line_number,
+        detection.column_range,
+        detection.pattern.id.clone(),
+    )
+}
+
+fn file_name(uri: &Url) -> String {
+    uri.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| uri.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, ParameterExtractor, Pattern};
+
+    fn pattern(id: &str, name: &str, regex: &str, extractor: (&str, &str)) -> Pattern {
+        Pattern {
+            name: name.to_string(),
+            category: "auth".to_string(),
+            parameter_extractors: vec![ParameterExtractor {
+                name: extractor.0.to_string(),
+                regex: extractor.1.to_string(),
+                enum_name: None,
+            }],
+            ..Pattern::test(id, regex)
+        }
+    }
+
+    fn documents() -> Vec<(Url, Arc<Vec<Detection>>)> {
+        let patterns = vec![
+            pattern(
+                "http-500",
+                "HTTP 500",
+                "status=500",
+                ("reqId", r"req=(\S+)"),
+            ),
+            pattern(
+                "login-failed",
+                "Login failed",
+                "login failed",
+                ("requestId", r"request (\S+)"),
+            ),
+        ];
+        let detect = |text: &str| Arc::new(detect_lines(patterns.clone(), text));
+
+        vec![
+            (
+                Url::parse("file:///logs/server.log").unwrap(),
+                detect(
+                    "2024-01-15 10:00:00,000 req=ab12cd status=500\n\
+                     2024-01-15 11:00:00,000 req=zz99 status=500",
+                ),
+            ),
+            (
+                Url::parse("file:///logs/client.log").unwrap(),
+                detect(
+                    "2024-01-15 10:00:00,250 login failed for request ab12cd\n\
+                     2024-01-15 10:00:01,000 login failed for request zz99",
+                ),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_links_shared_values_within_window() {
+        let documents = documents();
+        let correlations = correlate(&documents, 5_000, 10);
+        let (server_uri, server) = &documents[0];
+        let (client_uri, client) = &documents[1];
+
+        let links = correlations.links_for(server_uri, &server[0]);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].uri, *client_uri);
+        assert_eq!(links[0].parameter, "requestId");
+        assert_eq!(links[0].delta_ms, Some(250));
+
+        let related = correlations.related_information(client_uri, &client[0]);
+        assert_eq!(related.len(), 1);
+        assert_eq!(
+            related[0].message,
+            "Same reqId=ab12cd in server.log: HTTP 500 (-250ms)"
+        );
+
+        // Same value but an hour apart
+        assert!(correlations.links_for(server_uri, &server[1]).is_empty());
+        assert_eq!(
+            correlations.linked_documents(server_uri),
+            HashSet::from([client_uri.clone()])
+        );
+    }
+
+    #[test]
+    fn test_update_replaces_links_of_one_document() {
+        let documents = documents();
+        let (server_uri, server) = &documents[0];
+        let (client_uri, client) = &documents[1];
+        let mut correlations = correlate(&documents, 5_000, 10);
+
+        // The client log changes and no longer mentions the request
+        let server_index = Arc::new(CorrelationIndex::new(Arc::clone(server)));
+        let peers = [(server_uri.clone(), server_index)];
+        let edited = Arc::new(client[1..].to_vec());
+        let changed = correlations.update(
+            client_uri,
+            &CorrelationIndex::new(edited),
+            &peers,
+            5_000,
+            10,
+        );
+        assert!(correlations.links_for(server_uri, &server[0]).is_empty());
+        // The server log was linked before the change and must be refreshed
+        assert_eq!(changed, HashSet::from([server_uri.clone()]));
+
+        let changed = correlations.update(
+            client_uri,
+            &CorrelationIndex::new(Arc::clone(client)),
+            &peers,
+            5_000,
+            10,
+        );
+        assert_eq!(correlations.links_for(server_uri, &server[0]).len(), 1);
+        assert_eq!(correlations.links_for(client_uri, &client[0]).len(), 1);
+        assert_eq!(changed, HashSet::from([server_uri.clone()]));
+
+        let linked = correlations.remove(client_uri);
+        assert!(correlations.is_empty());
+        assert_eq!(linked, HashSet::from([server_uri.clone()]));
+    }
+
+    #[test]
+    fn test_correlation_keys() {
+        assert!(is_correlation_key("ab12cd"));
+        assert!(is_correlation_key("550e8400-e29b-41d4-a716-446655440000"));
+        assert!(is_correlation_key("1234567"));
+        assert!(!is_correlation_key("500"));
+        assert!(!is_correlation_key("4031"));
+    }
+}
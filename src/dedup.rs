@@ -0,0 +1,308 @@
+//! Overlap-Aware Deduplication
+//!
+//! Several patterns often match the same (or overlapping) text on a line,
+//! e.g. a generic "HTTP response" pattern and a specific "HTTP 503 from the
+//! media server" one. Only one of them is published: candidates are ranked
+//! by a configurable precedence (explicit priority, severity, product match,
+//! regex specificity), and the losers are kept on the winner as
+//! alternatives so clients can show the other interpretations.
+
+use crate::pattern_engine::{Detection, Severity};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Criteria deciding which of two overlapping detections is published
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precedence {
+    /// Higher `priority` of the pattern
+    Priority,
+
+    /// More severe final severity
+    Severity,
+
+    /// Pattern for the product of the logs (see `Settings::product`)
+    Product,
+
+    /// More literal text in the regex
+    Specificity,
+}
+
+/// Order used when none is configured
+pub const DEFAULT_PRECEDENCE: [Precedence; 4] = [
+    Precedence::Priority,
+    Precedence::Severity,
+    Precedence::Product,
+    Precedence::Specificity,
+];
+
+/// How overlapping detections are ranked
+#[derive(Debug, Clone, Copy)]
+pub struct DedupOptions<'a> {
+    /// Criteria, most important first; ties go to the pattern listed first
+    pub precedence: &'a [Precedence],
+
+    /// Product of the analyzed logs, compared with the patterns' service
+    pub product: Option<&'a str>,
+}
+
+impl Default for DedupOptions<'_> {
+    fn default() -> Self {
+        Self {
+            precedence: &DEFAULT_PRECEDENCE,
+            product: None,
+        }
+    }
+}
+
+impl DedupOptions<'_> {
+    /// Value of a criterion for a detection (higher wins)
+    fn score(&self, precedence: Precedence, detection: &Detection) -> i64 {
+        match precedence {
+            Precedence::Priority => i64::from(detection.pattern.priority),
+            Precedence::Severity => i64::from(detection.final_severity.rank()),
+            Precedence::Product => {
+                let matches = self.product.is_some_and(|product| {
+                    detection
+                        .pattern
+                        .service
+                        .as_ref()
+                        .is_some_and(|service| service.eq_ignore_ascii_case(product))
+                });
+                i64::from(matches)
+            }
+            Precedence::Specificity => regex_specificity(&detection.pattern.pattern) as i64,
+        }
+    }
+
+    /// Scores of a detection in precedence order
+    fn scores(&self, detection: &Detection) -> Vec<i64> {
+        self.precedence
+            .iter()
+            .map(|&precedence| self.score(precedence, detection))
+            .collect()
+    }
+}
+
+/// Another pattern that matched overlapping text but lost on precedence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alternative {
+    pub pattern_id: String,
+    pub pattern_name: String,
+    pub category: String,
+    pub severity: Severity,
+    pub column_range: (usize, usize),
+    pub matched_text: String,
+
+    /// First criterion on which it lost (`None` when tied on all of them)
+    pub decided_by: Option<Precedence>,
+}
+
+/// Number of literal characters in a regex, a rough measure of how
+/// specific it is
+///
+/// Metacharacters, character classes, repetition counts and group syntax
+/// do not count; escaped punctuation does.
+pub fn regex_specificity(regex: &str) -> usize {
+    let mut literals = 0;
+    let mut chars = regex.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if chars
+                    .next()
+                    .is_some_and(|next| !next.is_ascii_alphanumeric())
+                {
+                    literals += 1;
+                }
+            }
+            '[' => {
+                // Skip the class, including escaped `]`
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        ']' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            '(' if chars.peek() == Some(&'?') => {
+                chars.next();
+                if chars.next_if(|&c| c == 'P').is_some() || chars.peek() == Some(&'<') {
+                    // Skip `?P<name>` and `?<name>`
+                    for c in chars.by_ref() {
+                        if c == '>' {
+                            break;
+                        }
+                    }
+                } else {
+                    // Skip `?:` and flag groups such as `?i)`, `?i:` and `?-s:`,
+                    // up to and including their closing `)` or `:`
+                    chars
+                        .by_ref()
+                        .take_while(|&c| c.is_ascii_alphabetic() || c == '-')
+                        .for_each(drop);
+                }
+            }
+            '.' | '*' | '+' | '?' | '(' | ')' | '|' | '^' | '$' => {}
+            _ => literals += 1,
+        }
+    }
+
+    literals
+}
+
+fn overlaps(a: &Detection, b: &Detection) -> bool {
+    let (a_start, a_end) = a.column_range;
+    let (b_start, b_end) = b.column_range;
+    a.column_range == b.column_range || (a_start < b_end && b_start < a_end)
+}
+
+/// Keep one detection per overlapping span on each line
+///
+/// Candidates are visited from the highest precedence down; a detection
+/// overlapping an already kept one becomes one of its alternatives. The
+/// result is in document order.
+pub fn deduplicate(detections: Vec<Detection>, options: &DedupOptions) -> Vec<Detection> {
+    let mut lines: BTreeMap<usize, Vec<Detection>> = BTreeMap::new();
+    for detection in detections {
+        lines
+            .entry(detection.line_number)
+            .or_default()
+            .push(detection);
+    }
+
+    let mut deduplicated = Vec::new();
+    for (_, mut candidates) in lines {
+        if candidates.len() > 1 {
+            // Stable, so ties keep the order of the patterns
+            candidates.sort_by_cached_key(|d| std::cmp::Reverse(options.scores(d)));
+        }
+
+        let mut kept: Vec<(Detection, Vec<i64>)> = Vec::new();
+        for candidate in candidates {
+            let scores = options.scores(&candidate);
+            match kept
+                .iter_mut()
+                .find(|(winner, _)| overlaps(winner, &candidate))
+            {
+                Some((winner, winner_scores)) => {
+                    let decided_by = winner_scores
+                        .iter()
+                        .zip(&scores)
+                        .position(|(w, c)| w != c)
+                        .map(|index| options.precedence[index]);
+                    winner.alternatives.push(Alternative {
+                        pattern_id: candidate.pattern.id.clone(),
+                        pattern_name: candidate.pattern.name.clone(),
+                        category: candidate.pattern.category.clone(),
+                        severity: candidate.final_severity,
+                        column_range: candidate.column_range,
+                        matched_text: candidate.matched_text.clone(),
+                        decided_by,
+                    });
+                }
+                None => kept.push((candidate, scores)),
+            }
+        }
+
+        let mut kept: Vec<Detection> = kept.into_iter().map(|(detection, _)| detection).collect();
+        kept.sort_by_key(|d| d.column_range);
+        deduplicated.extend(kept);
+    }
+
+    deduplicated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+
+    #[test]
+    fn test_regex_specificity() {
+        assert_eq!(regex_specificity(r"HTTP \d+"), 5);
+        assert_eq!(regex_specificity(r"(?P<code>\d{3}) [A-Z]+"), 1);
+        assert_eq!(regex_specificity(r"a\.b(?:c|d)"), 5);
+        assert_eq!(regex_specificity(r"(?i)connection refused"), 18);
+        assert_eq!(regex_specificity(r"(?i:connection) (?-s:refused)"), 18);
+    }
+
+    #[test]
+    fn test_overlapping_spans_keep_alternatives() {
+        let detections = detect_lines(
+            vec![
+                Pattern::test_with("http-response", r"HTTP/1\.1 \d+", Severity::Info, "http"),
+                Pattern::test_with(
+                    "http-503",
+                    r"1\.1 503 Service Unavailable",
+                    Severity::Info,
+                    "http",
+                ),
+                Pattern::test_with("unrelated", "upstream", Severity::Hint, "http"),
+            ],
+            "GET /media HTTP/1.1 503 Service Unavailable from upstream",
+        );
+        assert_eq!(detections.len(), 3);
+
+        let deduplicated = deduplicate(detections, &DedupOptions::default());
+        assert_eq!(deduplicated.len(), 2);
+
+        // Same severity and priority: the more specific regex wins
+        let winner = &deduplicated[0];
+        assert_eq!(winner.pattern.id, "http-503");
+        assert_eq!(winner.alternatives.len(), 1);
+        assert_eq!(winner.alternatives[0].pattern_id, "http-response");
+        assert_eq!(
+            winner.alternatives[0].decided_by,
+            Some(Precedence::Specificity)
+        );
+        assert_eq!(deduplicated[1].pattern.id, "unrelated");
+    }
+
+    #[test]
+    fn test_configured_precedence() {
+        let generic = Pattern {
+            service: Some("Webex".to_string()),
+            ..Pattern::test("generic", r"HTTP/1\.1 \d+")
+        };
+        let specific = Pattern {
+            priority: 1,
+            ..Pattern::test_with("specific", r"HTTP/1\.1 503", Severity::Warning, "http")
+        };
+        let detections = detect_lines(vec![generic, specific], "HTTP/1.1 503");
+
+        let winner = |options: &DedupOptions| {
+            deduplicate(detections.clone(), options)[0]
+                .pattern
+                .id
+                .clone()
+        };
+        assert_eq!(winner(&DedupOptions::default()), "specific");
+        assert_eq!(
+            winner(&DedupOptions {
+                precedence: &[Precedence::Severity, Precedence::Priority],
+                product: None,
+            }),
+            "generic"
+        );
+        assert_eq!(
+            winner(&DedupOptions {
+                precedence: &[Precedence::Product, Precedence::Priority],
+                product: Some("webex"),
+            }),
+            "generic"
+        );
+    }
+}
@@ -167,14 +167,14 @@ impl DiagnosticCollection {
     pub fn add(&mut self, uri: String, diagnostic: Diagnostic) {
         self.diagnostics
             .entry(uri)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(diagnostic);
     }
 
     pub fn add_multiple(&mut self, uri: String, diagnostics: Vec<Diagnostic>) {
         self.diagnostics
             .entry(uri)
-            .or_insert_with(Vec::new)
+            .or_default()
             .extend(diagnostics);
     }
 
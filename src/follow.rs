@@ -0,0 +1,413 @@
+//! Live Follow Mode
+//!
+//! Watches a log file on disk that is being appended to and analyzes only
+//! the new bytes, keeping multi-line context across appends. Rotation and
+//! truncation restart the analysis from the beginning of the new file.
+//! Like archive members, at most `max_file_size_mb` is read at once: when
+//! more is pending (e.g. when following a large existing log), only its
+//! tail is analyzed and the skipped lines are counted.
+
+use crate::pattern_engine::{self, ContextProcessor, Detection, PatternEngine, PatternMode};
+use std::collections::HashSet;
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Command that starts (or stops) following a log file
+pub const FOLLOW_COMMAND: &str = "logScout.follow";
+
+/// How often the file is checked for new bytes
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Minimum time between two diagnostic publications for a followed file
+pub const PUBLISH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Result of one poll of a followed file
+#[derive(Debug, Default)]
+pub struct FollowUpdate {
+    /// The file was rotated or truncated; earlier detections are stale
+    pub reset: bool,
+
+    /// Detections in the lines completed since the last poll
+    pub detections: Vec<Detection>,
+
+    /// Number of lines completed since the last poll
+    pub lines_read: usize,
+}
+
+/// Incremental reader and analyzer for a growing log file
+pub struct LogFollower {
+    path: PathBuf,
+
+    /// Bytes of the file consumed so far
+    offset: u64,
+
+    /// Inode of the file being read, to notice rotation
+    identity: Option<u64>,
+
+    /// Bytes of a trailing line that has no newline yet
+    partial: Vec<u8>,
+
+    /// 0-based number of the next complete line
+    next_line: usize,
+
+    /// Recent lines for multi-line patterns
+    context: ContextProcessor,
+
+    /// Multi-line matches already reported (pattern ID, line, column)
+    seen_multiline: HashSet<(String, usize, usize)>,
+
+    context_window: usize,
+
+    /// Most bytes read in one poll
+    max_bytes: u64,
+
+    /// The start of the current line was skipped; drop it up to its newline
+    skip_partial: bool,
+}
+
+impl LogFollower {
+    /// Follow a file from its beginning, reading at most `max_bytes` at once
+    pub fn new<P: AsRef<Path>>(path: P, context_window: usize, max_bytes: u64) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            offset: 0,
+            identity: None,
+            partial: Vec::new(),
+            next_line: 0,
+            context: ContextProcessor::new(context_window),
+            seen_multiline: HashSet::new(),
+            context_window,
+            max_bytes,
+            skip_partial: false,
+        }
+    }
+
+    /// Path of the followed file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read and analyze the bytes appended since the last poll
+    pub fn poll(&mut self, engine: &PatternEngine) -> io::Result<FollowUpdate> {
+        let mut file = File::open(&self.path)?;
+        let metadata = file.metadata()?;
+        let identity = file_identity(&metadata);
+
+        let mut update = FollowUpdate::default();
+        let rotated = self.identity.is_some() && identity != self.identity;
+        if rotated || metadata.len() < self.offset {
+            tracing::info!("{} was rotated or truncated", self.path.display());
+            self.reset();
+            update.reset = true;
+        }
+        self.identity = identity;
+
+        if metadata.len() == self.offset {
+            return Ok(update);
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut pending = metadata.len() - self.offset;
+        if pending > self.max_bytes {
+            let skip = pending - self.max_bytes;
+            self.skip(file.by_ref().take(skip))?;
+            pending -= skip;
+        }
+
+        let mut appended = Vec::new();
+        file.take(pending).read_to_end(&mut appended)?;
+        self.offset += appended.len() as u64;
+
+        let mut appended = appended.as_slice();
+        if self.skip_partial {
+            let Some(newline) = appended.iter().position(|&b| b == b'\n') else {
+                return Ok(update);
+            };
+            appended = &appended[newline + 1..];
+            self.next_line += 1;
+            self.skip_partial = false;
+        }
+        self.partial.extend_from_slice(appended);
+
+        // Only complete lines are analyzed; the rest waits for its newline
+        let Some(last_newline) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return Ok(update);
+        };
+        let remainder = self.partial.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.partial, remainder);
+        let text = String::from_utf8_lossy(&complete);
+
+        let mut detections = Vec::new();
+        for line in text.lines() {
+            detections.extend(engine.process_line(line, self.next_line));
+            self.context.push_line(line.to_string());
+            detections.extend(self.multiline_detections(engine));
+            self.next_line += 1;
+            update.lines_read += 1;
+        }
+
+        update.detections = pattern_engine::deduplicate_detections(detections);
+        Ok(update)
+    }
+
+    /// Skip the bytes of `reader`, counting the lines they end
+    ///
+    /// Multi-line context does not span the gap, and the line the skipped
+    /// bytes stop in is dropped as well.
+    fn skip(&mut self, mut reader: impl Read) -> io::Result<()> {
+        let mut buffer = [0u8; 64 * 1024];
+        let mut lines = 0;
+        let mut ends_line = false;
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            lines += buffer[..read].iter().filter(|&&b| b == b'\n').count();
+            ends_line = buffer[read - 1] == b'\n';
+            self.offset += read as u64;
+        }
+
+        tracing::warn!(
+            "Skipped {} lines of {} exceeding the {} MB read limit",
+            lines,
+            self.path.display(),
+            self.max_bytes / (1024 * 1024)
+        );
+        self.next_line += lines;
+        self.partial.clear();
+        self.skip_partial = !ends_line;
+        self.context.reset();
+        self.seen_multiline.clear();
+        Ok(())
+    }
+
+    /// Multi-line matches in the current context that were not reported yet
+    ///
+    /// Matches are placed on the line and column where they start, so a
+    /// match that stays inside the window across appends is reported once.
+    fn multiline_detections(&mut self, engine: &PatternEngine) -> Vec<Detection> {
+        let mut detections = Vec::new();
+
+        for pattern in engine.get_patterns() {
+            let PatternMode::MultiLine { context_lines } = pattern.pattern.mode else {
+                continue;
+            };
+
+            let context = self.context.get_context(context_lines);
+            let first_line = self.next_line + 1 - context.len();
+            let combined = context.join("\n");
+
+            for mut detection in self
+                .context
+                .check_multiline_patterns(std::slice::from_ref(pattern))
+            {
+                // The match offset is in the same joined context
+                let start = detection.column_range.0;
+                let line_offset = combined[..start].matches('\n').count();
+                let line_start = combined[..start].rfind('\n').map_or(0, |i| i + 1);
+                let column = start - line_start;
+
+                detection.line_number = first_line + line_offset;
+                detection.column_range = (column, column + detection.matched_text.len());
+                // Context starts on the match's line, like single-line detections
+                detection.context.drain(..line_offset);
+
+                let key = (detection.pattern.id.clone(), detection.line_number, column);
+                if self.seen_multiline.insert(key) {
+                    detections.push(detection);
+                }
+            }
+        }
+
+        // Matches that left the window can no longer be reported again
+        let oldest = self.next_line.saturating_sub(self.context_window);
+        self.seen_multiline.retain(|(_, line, _)| *line >= oldest);
+
+        detections
+    }
+
+    fn reset(&mut self) {
+        self.offset = 0;
+        self.partial.clear();
+        self.skip_partial = false;
+        self.next_line = 0;
+        self.context.reset();
+        self.seen_multiline.clear();
+    }
+}
+
+#[cfg(unix)]
+fn file_identity(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_identity(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::Pattern;
+    use std::io::Write;
+
+    fn pattern(id: &str, regex: &str, mode: PatternMode) -> Pattern {
+        Pattern {
+            mode,
+            ..Pattern::test(id, regex)
+        }
+    }
+
+    fn engine() -> PatternEngine {
+        PatternEngine::new(
+            vec![
+                pattern("failed", "failed", PatternMode::SingleLine),
+                pattern(
+                    "crash",
+                    r"Exception\n\s+at ",
+                    PatternMode::MultiLine { context_lines: 3 },
+                ),
+            ],
+            0.7,
+            10,
+        )
+        .unwrap()
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn temp_log(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("logscout-follow-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_follow_appends_and_partial_lines() {
+        let path = temp_log("append.log");
+        let engine = engine();
+        let mut follower = LogFollower::new(&path, 10, 1024 * 1024);
+
+        append(&path, "start\nlogin fai");
+        let update = follower.poll(&engine).unwrap();
+        assert_eq!(update.lines_read, 1);
+        assert!(update.detections.is_empty());
+
+        append(&path, "led\nok\n");
+        let update = follower.poll(&engine).unwrap();
+        assert_eq!(update.lines_read, 2);
+        assert_eq!(update.detections.len(), 1);
+        assert_eq!(update.detections[0].line_number, 1);
+
+        assert_eq!(follower.poll(&engine).unwrap().lines_read, 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_follow_multiline_across_appends() {
+        let path = temp_log("multiline.log");
+        let engine = engine();
+        let mut follower = LogFollower::new(&path, 10, 1024 * 1024);
+
+        append(&path, "ok\nNullPointerException\n");
+        assert!(follower.poll(&engine).unwrap().detections.is_empty());
+
+        append(&path, "    at Foo.bar\nnext\n");
+        let update = follower.poll(&engine).unwrap();
+        let crashes: Vec<&Detection> = update
+            .detections
+            .iter()
+            .filter(|d| d.pattern.id == "crash")
+            .collect();
+        assert_eq!(crashes.len(), 1);
+        assert_eq!(crashes[0].line_number, 1);
+        assert_eq!(crashes[0].column_range.0, 11);
+        assert_eq!(
+            crashes[0].context,
+            vec!["NullPointerException", "    at Foo.bar"]
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_follow_repeated_multiline_match() {
+        let path = temp_log("repeated.log");
+        let engine = PatternEngine::new(
+            vec![pattern(
+                "crash",
+                r"Exception\n\s+at ",
+                PatternMode::MultiLine { context_lines: 4 },
+            )],
+            0.7,
+            10,
+        )
+        .unwrap();
+        let mut follower = LogFollower::new(&path, 10, 1024 * 1024);
+
+        append(
+            &path,
+            "NullPointerException\n    at Foo\n".repeat(2).as_str(),
+        );
+        let lines: Vec<usize> = follower
+            .poll(&engine)
+            .unwrap()
+            .detections
+            .iter()
+            .map(|d| d.line_number)
+            .collect();
+        assert_eq!(lines, vec![0, 2]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_follow_skips_beyond_read_limit() {
+        let path = temp_log("limit.log");
+        let engine = engine();
+        let mut follower = LogFollower::new(&path, 10, 16);
+
+        // Only the last 16 bytes are read; the line they start in is dropped
+        append(&path, "failed 1\nfailed 2\nfailed 3\n");
+        let update = follower.poll(&engine).unwrap();
+        assert_eq!(update.detections.len(), 1);
+        assert_eq!(update.detections[0].line_number, 2);
+
+        append(&path, "failed 4\n");
+        let update = follower.poll(&engine).unwrap();
+        assert_eq!(update.detections[0].line_number, 3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_follow_truncation_resets() {
+        let path = temp_log("truncate.log");
+        let engine = engine();
+        let mut follower = LogFollower::new(&path, 10, 1024 * 1024);
+
+        append(&path, "one\ntwo\nthree failed\n");
+        assert_eq!(follower.poll(&engine).unwrap().detections.len(), 1);
+
+        std::fs::write(&path, "failed\n").unwrap();
+        let update = follower.poll(&engine).unwrap();
+        assert!(update.reset);
+        assert_eq!(update.detections.len(), 1);
+        assert_eq!(update.detections[0].line_number, 0);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,298 @@
+//! Inlay Hint Provider
+//!
+//! Shows decoded meanings next to extracted parameter values (enum lookups,
+//! epoch timestamps, byte counts) and the time elapsed between log lines.
+
+use crate::pattern_engine::{Detection, PatternEngine};
+use crate::tagscout::EnumTables;
+use crate::timestamp::{self, LogTimestamp};
+use std::collections::HashSet;
+use tower_lsp::lsp_types::{
+    InlayHint, InlayHintKind, InlayHintLabel, InlayHintTooltip, Position, Range,
+};
+
+/// Command that sets (or clears) the anchor line for elapsed-time hints
+pub const SET_TIME_ANCHOR_COMMAND: &str = "logScout.setTimeAnchor";
+
+/// Gaps between consecutive lines at or above this are flagged as stalls
+const STALL_THRESHOLD_MS: i64 = 5_000;
+
+/// Epoch seconds accepted as timestamps (2000-01-01 to 2100-01-01)
+const EPOCH_SECONDS_RANGE: std::ops::Range<i64> = 946_684_800..4_102_444_800;
+
+/// Decode an extracted parameter value into a human-readable meaning
+///
+/// Enum lookups take precedence, then epoch timestamps, then byte counts
+/// (for parameters whose name mentions bytes or size).
+pub fn decode_value(
+    name: &str,
+    value: &str,
+    enum_name: Option<&str>,
+    enums: &EnumTables,
+) -> Option<String> {
+    if let Some(meaning) = enum_name
+        .and_then(|enum_name| enums.get(enum_name))
+        .and_then(|table| table.get(value))
+    {
+        return Some(meaning.clone());
+    }
+
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        let number: i64 = value.parse().ok()?;
+
+        let epoch_millis = match value.len() {
+            10 if EPOCH_SECONDS_RANGE.contains(&number) => Some(number * 1_000),
+            13 if EPOCH_SECONDS_RANGE.contains(&(number / 1_000)) => Some(number),
+            _ => None,
+        };
+        if let Some(millis) = epoch_millis {
+            let datetime = chrono::DateTime::from_timestamp_millis(millis)?;
+            return Some(datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string());
+        }
+
+        let lower = name.to_lowercase();
+        if (lower.contains("byte") || lower.contains("size")) && number >= 1024 {
+            return Some(format_bytes(number as u64));
+        }
+    }
+
+    None
+}
+
+/// Hints with decoded meanings for the parameter values of detections in `range`
+pub fn parameter_hints(
+    text: &str,
+    range: Range,
+    detections: &[Detection],
+    engine: &PatternEngine,
+    enums: &EnumTables,
+) -> Vec<InlayHint> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut seen = HashSet::new();
+    let mut hints = Vec::new();
+
+    for detection in detections {
+        let line_number = detection.line_number as u32;
+        if line_number < range.start.line || line_number > range.end.line {
+            continue;
+        }
+        let (Some(line), Some(compiled)) = (
+            lines.get(detection.line_number),
+            engine.get_pattern(&detection.pattern.id),
+        ) else {
+            continue;
+        };
+
+        for span in compiled.parameter_spans(line) {
+            // Several patterns can extract the same value; hint it once
+            if !seen.insert((line_number, span.start, span.end)) {
+                continue;
+            }
+
+            let enum_name = compiled
+                .pattern
+                .parameter_extractors
+                .iter()
+                .find(|extractor| extractor.name == span.name)
+                .and_then(|extractor| extractor.enum_name.as_deref());
+
+            if let Some(meaning) = decode_value(&span.name, &span.value, enum_name, enums) {
+                hints.push(InlayHint {
+                    position: Position {
+                        line: line_number,
+                        character: span.end as u32,
+                    },
+                    label: InlayHintLabel::String(format!("= {}", meaning)),
+                    kind: Some(InlayHintKind::PARAMETER),
+                    text_edits: None,
+                    tooltip: Some(InlayHintTooltip::String(format!(
+                        "{} = {} ({})",
+                        span.name, span.value, meaning
+                    ))),
+                    padding_left: Some(true),
+                    padding_right: None,
+                    data: None,
+                });
+            }
+        }
+    }
+
+    hints.sort_by_key(|hint| (hint.position.line, hint.position.character));
+    hints
+}
+
+/// Hints with the elapsed time for each timestamped line in `range`
+///
+/// Without an anchor, each line shows the time since the previous
+/// timestamped line. With an anchor line, each line shows the time since
+/// the anchor instead.
+pub fn time_delta_hints(text: &str, range: Range, anchor: Option<u32>) -> Vec<InlayHint> {
+    let anchor_timestamp = anchor.and_then(|anchor| {
+        text.lines()
+            .nth(anchor as usize)
+            .and_then(timestamp::extract_timestamp)
+    });
+
+    let mut previous: Option<LogTimestamp> = None;
+    let mut hints = Vec::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line_number = line_number as u32;
+        if line_number > range.end.line {
+            break;
+        }
+
+        let Some(current) = timestamp::extract_timestamp(line) else {
+            continue;
+        };
+
+        if line_number >= range.start.line {
+            let hint = match (&anchor_timestamp, &previous) {
+                (Some(_), _) if Some(line_number) == anchor => Some(("anchor".to_string(), None)),
+                (Some(anchor_ts), _) => {
+                    let millis = elapsed_millis(anchor_ts, &current);
+                    Some((
+                        format!("{} from anchor", timestamp::format_delta(millis)),
+                        None,
+                    ))
+                }
+                (None, Some(previous_ts)) => {
+                    let millis = elapsed_millis(previous_ts, &current);
+                    (millis != 0).then(|| {
+                        let tooltip = (millis >= STALL_THRESHOLD_MS).then(|| {
+                            format!(
+                                "Stall: {} since the previous timestamped line",
+                                timestamp::format_delta(millis)
+                            )
+                        });
+                        (timestamp::format_delta(millis), tooltip)
+                    })
+                }
+                (None, None) => None,
+            };
+
+            if let Some((label, tooltip)) = hint {
+                hints.push(InlayHint {
+                    position: Position {
+                        line: line_number,
+                        character: (current.start + current.raw.len()) as u32,
+                    },
+                    label: InlayHintLabel::String(label),
+                    kind: None,
+                    text_edits: None,
+                    tooltip: tooltip.map(InlayHintTooltip::String),
+                    padding_left: Some(true),
+                    padding_right: Some(true),
+                    data: None,
+                });
+            }
+        }
+
+        previous = Some(current);
+    }
+
+    hints
+}
+
+fn elapsed_millis(from: &LogTimestamp, to: &LogTimestamp) -> i64 {
+    to.datetime
+        .signed_duration_since(from.datetime)
+        .num_milliseconds()
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+
+    format!("{:.1} {}", value, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn full_range() -> Range {
+        Range {
+            start: Position {
+                line: 0,
+                character: 0,
+            },
+            end: Position {
+                line: u32::MAX,
+                character: 0,
+            },
+        }
+    }
+
+    fn label(hint: &InlayHint) -> &str {
+        match &hint.label {
+            InlayHintLabel::String(label) => label,
+            InlayHintLabel::LabelParts(_) => "",
+        }
+    }
+
+    #[test]
+    fn test_decode_value() {
+        let mut enums: EnumTables = HashMap::new();
+        enums.insert(
+            "RegistrationState".to_string(),
+            HashMap::from([("3".to_string(), "Registered".to_string())]),
+        );
+
+        assert_eq!(
+            decode_value("STATE", "3", Some("RegistrationState"), &enums).as_deref(),
+            Some("Registered")
+        );
+        assert_eq!(
+            decode_value("time", "1705314645", None, &enums).as_deref(),
+            Some("2024-01-15 10:30:45 UTC")
+        );
+        assert_eq!(
+            decode_value("time", "1705314645123", None, &enums).as_deref(),
+            Some("2024-01-15 10:30:45 UTC")
+        );
+        assert_eq!(
+            decode_value("payloadBytes", "1572864", None, &enums).as_deref(),
+            Some("1.5 MiB")
+        );
+        assert_eq!(decode_value("count", "1572864", None, &enums), None);
+    }
+
+    #[test]
+    fn test_time_deltas_since_previous_line() {
+        let text = "2024-01-15 10:00:00,000 start\n\
+                    no timestamp\n\
+                    2024-01-15 10:00:00,250 next\n\
+                    2024-01-15 10:00:30,250 after stall";
+
+        let hints = time_delta_hints(text, full_range(), None);
+        assert_eq!(hints.len(), 2);
+        assert_eq!(label(&hints[0]), "+250ms");
+        assert_eq!(hints[0].position.line, 2);
+        assert_eq!(hints[0].position.character, 23);
+        assert_eq!(label(&hints[1]), "+30.0s");
+        assert!(hints[1].tooltip.is_some());
+    }
+
+    #[test]
+    fn test_time_deltas_since_anchor() {
+        let text = "10:00:00 start\n10:00:05 anchor here\n10:01:10 later";
+
+        let hints = time_delta_hints(text, full_range(), Some(1));
+        let labels: Vec<&str> = hints.iter().map(label).collect();
+        assert_eq!(
+            labels,
+            vec!["-5.0s from anchor", "anchor", "+1m 05s from anchor"]
+        );
+    }
+}
@@ -2,11 +2,35 @@
 //!
 //! Core modules for the Language Server Protocol implementation.
 
+pub mod aggregation;
+pub mod archive;
+pub mod batch;
+pub mod code_actions;
+pub mod code_lens;
 pub mod config;
+pub mod correlation;
+pub mod dedup;
 pub mod diagnostics;
 pub mod document;
+pub mod follow;
+pub mod inlay_hints;
+pub mod pattern_details;
+pub mod pattern_draft;
 pub mod pattern_engine;
+pub mod payload;
+pub mod pipeline;
+pub mod position;
+pub mod process;
+pub mod references;
+pub mod remediation;
+pub mod scenario;
 pub mod server;
+pub mod severity_policy;
+pub mod signature;
+pub mod suppression;
 pub mod tagscout;
+pub mod timestamp;
+pub mod virtual_document;
+pub mod workspace_symbols;
 
 pub use server::LogScoutServer;
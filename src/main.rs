@@ -3,15 +3,95 @@
 //! Language Server Protocol implementation for log file analysis.
 //! Provides pattern matching, diagnostics, and timeline analysis for log files.
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use log_scout_lsp_server::archive::OPEN_ARCHIVE_METHOD;
+use log_scout_lsp_server::batch::{self, BatchOptions, OutputFormat};
+use log_scout_lsp_server::pattern_details::PATTERN_DETAILS_METHOD;
+use log_scout_lsp_server::pattern_engine::Severity;
+use log_scout_lsp_server::payload;
+use log_scout_lsp_server::process::PROCESSES_METHOD;
+use log_scout_lsp_server::references::REFERENCES_METHOD;
+use log_scout_lsp_server::virtual_document::VIRTUAL_DOCUMENT_METHOD;
 use log_scout_lsp_server::LogScoutServer;
 use std::fs::{self, OpenOptions};
-use std::path::PathBuf;
-use tower_lsp::{LspService, Server};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tower_lsp::{ClientSocket, LspService, Server};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
+/// Default port for `--tcp` when none is given
+const DEFAULT_TCP_PORT: u16 = 8080;
+
+/// Default bind address for `--tcp`
+const DEFAULT_TCP_HOST: &str = "127.0.0.1";
+
+const USAGE: &str = "\
+Usage: log-scout-lsp-server [OPTIONS]
+       log-scout-lsp-server analyze [ANALYZE OPTIONS] [FILES...]
+       log-scout-lsp-server schema
+
+Transport (default: --stdio):
+  --stdio               Communicate over stdin/stdout
+  --tcp [PORT]          Listen for clients on a TCP port (default 8080)
+  --port PORT           TCP port (alternative to --tcp PORT)
+  --host ADDR           TCP bind address (default 127.0.0.1)
+  --listen [PATH]       Listen for clients on a Unix domain socket, or on
+                        a named pipe (\\\\.\\pipe\\NAME) on Windows
+
+Analyze (headless; reads stdin when no files or `-` are given):
+  --format FORMAT       json, sarif or text (default text)
+  --fail-on SEVERITY    Exit 1 on findings at or above error, warning,
+                        info or hint; `none` never fails (default error)
+  --patterns PATH       Extra YAML pattern file or directory (repeatable)
+  --cache-dir DIR       TagScout cache directory (default ./.tagscout_cache)
+  --online              Fetch from TagScout when the cache is stale
+  --settings FILE       JSON settings, as sent in initializationOptions
+
+Schema:
+  schema                Print the JSON Schema of diagnostic data payloads
+
+Other:
+  -h, --help            Print this help
+  -V, --version         Print the version
+";
+
+/// How the server talks to its clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Transport {
+    Stdio,
+    Tcp { host: String, port: u16 },
+    Listen(PathBuf),
+}
+
+/// What the binary was asked to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Serve(Transport),
+    Analyze(BatchOptions),
+    Schema,
+    Help,
+    Version,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let transport = match parse_args(std::env::args().skip(1))? {
+        Command::Serve(transport) => transport,
+        Command::Analyze(options) => run_analyze_mode(&options).await,
+        Command::Schema => {
+            println!("{}", serde_json::to_string_pretty(&payload::json_schema())?);
+            return Ok(());
+        }
+        Command::Help => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+        Command::Version => {
+            println!("log-scout-lsp-server {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+    };
+
     // Get log file path
     let log_path = get_log_file_path();
 
@@ -39,12 +119,129 @@ async fn main() -> Result<()> {
     );
     tracing::info!("Log file: {}", log_path.display());
 
-    // Run server in stdio mode (communicates via stdin/stdout)
-    run_stdio_mode().await?;
+    match transport {
+        Transport::Stdio => run_stdio_mode().await?,
+        Transport::Tcp { host, port } => run_tcp_mode(&host, port).await?,
+        Transport::Listen(path) => run_socket_mode(&path).await?,
+    }
 
     Ok(())
 }
 
+/// Parse command line arguments (without the program name)
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let mut args = args.into_iter().peekable();
+    let mut mode: Option<&str> = None;
+    let mut host = DEFAULT_TCP_HOST.to_string();
+    let mut port: Option<u16> = None;
+    let mut socket_path: Option<PathBuf> = None;
+
+    if args.next_if(|arg| arg == "analyze").is_some() {
+        return parse_analyze_args(args);
+    }
+    if args.next_if(|arg| arg == "schema").is_some() {
+        return Ok(Command::Schema);
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--stdio" => mode = Some("stdio"),
+            "--tcp" => {
+                mode = Some("tcp");
+                if let Some(value) = value_of(&mut args) {
+                    port = Some(parse_port(&value)?);
+                }
+            }
+            "--port" => {
+                let value = value_of(&mut args).context("--port requires a value")?;
+                port = Some(parse_port(&value)?);
+            }
+            "--host" => {
+                host = value_of(&mut args).context("--host requires a value")?;
+            }
+            "--listen" => {
+                mode = Some("listen");
+                socket_path = value_of(&mut args).map(PathBuf::from);
+            }
+            other => bail!("Unknown argument: {}\n\n{}", other, USAGE),
+        }
+    }
+
+    let transport = match mode {
+        Some("tcp") => Transport::Tcp {
+            host,
+            port: port.unwrap_or(DEFAULT_TCP_PORT),
+        },
+        Some("listen") => Transport::Listen(socket_path.unwrap_or_else(default_listen_path)),
+        _ if port.is_some() => Transport::Tcp {
+            host,
+            port: port.unwrap_or(DEFAULT_TCP_PORT),
+        },
+        _ => Transport::Stdio,
+    };
+
+    Ok(Command::Serve(transport))
+}
+
+/// Parse the arguments following `analyze`
+fn parse_analyze_args(args: impl IntoIterator<Item = String>) -> Result<Command> {
+    let mut args = args.into_iter().peekable();
+    let mut options = BatchOptions::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--format" => {
+                let value = value_of(&mut args).context("--format requires a value")?;
+                options.format = OutputFormat::from_str(&value)
+                    .with_context(|| format!("Unknown format: {}", value))?;
+            }
+            "--fail-on" => {
+                let value = value_of(&mut args).context("--fail-on requires a value")?;
+                options.fail_on = match value.as_str() {
+                    "none" => None,
+                    _ => Some(
+                        Severity::from_str(&value)
+                            .with_context(|| format!("Unknown severity: {}", value))?,
+                    ),
+                };
+            }
+            "--patterns" => {
+                let value = value_of(&mut args).context("--patterns requires a value")?;
+                options.pattern_paths.push(PathBuf::from(value));
+            }
+            "--cache-dir" => {
+                let value = value_of(&mut args).context("--cache-dir requires a value")?;
+                options.cache_dir = Some(PathBuf::from(value));
+            }
+            "--online" => options.online = true,
+            "--settings" => {
+                let value = value_of(&mut args).context("--settings requires a value")?;
+                options.settings_path = Some(PathBuf::from(value));
+            }
+            input if input == batch::STDIN_INPUT || !input.starts_with('-') => {
+                options.inputs.push(arg)
+            }
+            other => bail!("Unknown argument: {}\n\n{}", other, USAGE),
+        }
+    }
+
+    Ok(Command::Analyze(options))
+}
+
+/// Value following a flag, unless the next argument is another flag
+fn value_of(args: &mut std::iter::Peekable<impl Iterator<Item = String>>) -> Option<String> {
+    args.next_if(|arg| !arg.starts_with('-'))
+}
+
+fn parse_port(value: &str) -> Result<u16> {
+    value
+        .parse()
+        .with_context(|| format!("Invalid port: {}", value))
+}
+
 /// Get the log file path in user's home directory or temp directory
 fn get_log_file_path() -> PathBuf {
     let log_dir = if let Some(home) = dirs::home_dir() {
@@ -61,15 +258,281 @@ fn get_log_file_path() -> PathBuf {
     log_dir.join(format!("lsp-server-{}.log", date))
 }
 
+/// Analyze files without an editor and exit
+///
+/// Exits 1 when findings reach the `--fail-on` threshold and 2 on errors.
+async fn run_analyze_mode(options: &BatchOptions) -> ! {
+    // Only warnings and errors on stderr; stdout carries the report
+    tracing_subscriber::registry()
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(EnvFilter::from_default_env().add_directive(tracing::Level::WARN.into()))
+        .init();
+
+    let mut stdout = std::io::stdout().lock();
+    match batch::run(options, &mut stdout).await {
+        Ok(true) => std::process::exit(1),
+        Ok(false) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("log-scout-lsp-server analyze: {}", e);
+            std::process::exit(2)
+        }
+    }
+}
+
+/// Build the LSP service with all custom methods registered
+fn build_service() -> (LspService<LogScoutServer>, ClientSocket) {
+    LspService::build(LogScoutServer::new)
+        .custom_method(VIRTUAL_DOCUMENT_METHOD, LogScoutServer::virtual_document)
+        .custom_method(OPEN_ARCHIVE_METHOD, LogScoutServer::open_archive)
+        .custom_method(
+            REFERENCES_METHOD,
+            LogScoutServer::references_with_detections,
+        )
+        .custom_method(PROCESSES_METHOD, LogScoutServer::processes)
+        .custom_method(PATTERN_DETAILS_METHOD, LogScoutServer::pattern_details)
+        .finish()
+}
+
+/// Serve one client over a pair of byte streams
+async fn serve_connection<I, O>(input: I, output: O)
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite,
+{
+    let (service, socket) = build_service();
+    Server::new(input, output, socket).serve(service).await;
+}
+
 /// Run server in stdio mode (default for embedded deployment)
 async fn run_stdio_mode() -> Result<()> {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(|client| LogScoutServer::new(client));
-
     tracing::info!("LSP Server running in stdio mode");
-    Server::new(stdin, stdout, socket).serve(service).await;
+    serve_connection(stdin, stdout).await;
 
     Ok(())
 }
+
+/// Run server in TCP mode (containers and remote development)
+///
+/// Each connection gets its own server instance.
+async fn run_tcp_mode(host: &str, port: u16) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind((host, port))
+        .await
+        .with_context(|| format!("Failed to bind {}:{}", host, port))?;
+
+    tracing::info!("LSP Server listening on tcp://{}:{}", host, port);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        tracing::info!("Client connected from {}", peer);
+
+        tokio::spawn(async move {
+            let (read, write) = tokio::io::split(stream);
+            serve_connection(read, write).await;
+            tracing::info!("Client {} disconnected", peer);
+        });
+    }
+}
+
+/// Default endpoint for `--listen` when no path is given
+#[cfg(not(windows))]
+fn default_listen_path() -> PathBuf {
+    std::env::temp_dir().join("log-scout-lsp.sock")
+}
+
+/// Default endpoint for `--listen` when no path is given
+#[cfg(windows)]
+fn default_listen_path() -> PathBuf {
+    PathBuf::from(r"\\.\pipe\log-scout-lsp")
+}
+
+/// Run server on a Unix domain socket (local long-running server)
+///
+/// Each connection gets its own server instance.
+#[cfg(unix)]
+async fn run_socket_mode(path: &Path) -> Result<()> {
+    remove_stale_socket(path)?;
+
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("Failed to bind {}", path.display()))?;
+
+    tracing::info!("LSP Server listening on unix://{}", path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tracing::info!("Client connected on {}", path.display());
+
+        tokio::spawn(async move {
+            let (read, write) = tokio::io::split(stream);
+            serve_connection(read, write).await;
+            tracing::info!("Socket client disconnected");
+        });
+    }
+}
+
+/// Remove a socket file left behind by a previous run, which would make bind fail
+///
+/// Anything else at the path is left alone and reported as an error.
+#[cfg(unix)]
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to inspect {}", path.display())),
+    };
+    if !metadata.file_type().is_socket() {
+        bail!(
+            "{} exists and is not a socket; refusing to replace it",
+            path.display()
+        );
+    }
+
+    fs::remove_file(path)
+        .with_context(|| format!("Failed to remove stale socket {}", path.display()))
+}
+
+/// Run server on a named pipe (local long-running server on Windows)
+///
+/// `PATH` may be a full pipe path (`\\.\pipe\NAME`) or a bare name.
+/// Each connection gets its own server instance.
+#[cfg(windows)]
+async fn run_socket_mode(path: &Path) -> Result<()> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = pipe_name(path);
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&name)
+        .with_context(|| format!("Failed to create pipe {}", name))?;
+
+    tracing::info!("LSP Server listening on {}", name);
+
+    loop {
+        server.connect().await?;
+        tracing::info!("Client connected on {}", name);
+
+        // Create the next instance before serving so new clients can connect
+        let connected = server;
+        server = ServerOptions::new()
+            .create(&name)
+            .with_context(|| format!("Failed to create pipe {}", name))?;
+
+        tokio::spawn(async move {
+            let (read, write) = tokio::io::split(connected);
+            serve_connection(read, write).await;
+            tracing::info!("Pipe client disconnected");
+        });
+    }
+}
+
+/// Full named pipe path for a `--listen` argument
+#[cfg(windows)]
+fn pipe_name(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path.starts_with(r"\\") {
+        path.into_owned()
+    } else {
+        format!(r"\\.\pipe\{}", path)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+async fn run_socket_mode(_path: &Path) -> Result<()> {
+    bail!("--listen is not supported on this platform; use --tcp instead")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Command {
+        parse_args(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_parse_transports() {
+        assert_eq!(parse(&[]), Command::Serve(Transport::Stdio));
+        assert_eq!(parse(&["--stdio"]), Command::Serve(Transport::Stdio));
+        assert_eq!(
+            parse(&["--tcp", "9000"]),
+            Command::Serve(Transport::Tcp {
+                host: DEFAULT_TCP_HOST.to_string(),
+                port: 9000
+            })
+        );
+        assert_eq!(
+            parse(&["--tcp", "--port", "8081", "--host", "0.0.0.0"]),
+            Command::Serve(Transport::Tcp {
+                host: "0.0.0.0".to_string(),
+                port: 8081
+            })
+        );
+        assert_eq!(
+            parse(&["--listen", "/tmp/ls.sock"]),
+            Command::Serve(Transport::Listen(PathBuf::from("/tmp/ls.sock")))
+        );
+        assert_eq!(parse(&["--version"]), Command::Version);
+        assert_eq!(parse(&["schema"]), Command::Schema);
+    }
+
+    #[test]
+    fn test_parse_analyze() {
+        let Command::Analyze(options) = parse(&[
+            "analyze",
+            "--format",
+            "sarif",
+            "--fail-on",
+            "warning",
+            "--patterns",
+            "packs",
+            "--settings",
+            "settings.json",
+            "jabber.log",
+            "-",
+        ]) else {
+            panic!("expected analyze command");
+        };
+        assert_eq!(options.format, OutputFormat::Sarif);
+        assert_eq!(options.fail_on, Some(Severity::Warning));
+        assert_eq!(options.pattern_paths, vec![PathBuf::from("packs")]);
+        assert_eq!(options.settings_path, Some(PathBuf::from("settings.json")));
+        assert_eq!(options.inputs, vec!["jabber.log", "-"]);
+
+        let Command::Analyze(options) = parse(&["analyze", "--fail-on", "none"]) else {
+            panic!("expected analyze command");
+        };
+        assert_eq!(options.fail_on, None);
+        assert!(options.inputs.is_empty());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_arguments() {
+        assert!(parse_args(["--tcp".to_string(), "http".to_string()]).is_err());
+        assert!(parse_args(["--bogus".to_string()]).is_err());
+        assert!(parse_args(["--port".to_string()]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_stale_socket_keeps_regular_files() {
+        let dir = std::env::temp_dir().join(format!("log-scout-listen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let notes = dir.join("notes.txt");
+        fs::write(&notes, "keep me").unwrap();
+        assert!(remove_stale_socket(&notes).is_err());
+        assert_eq!(fs::read_to_string(&notes).unwrap(), "keep me");
+
+        let socket = dir.join("stale.sock");
+        drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+        remove_stale_socket(&socket).unwrap();
+        assert!(!socket.exists());
+
+        remove_stale_socket(&dir.join("missing.sock")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,179 @@
+//! Pattern Details
+//!
+//! Diagnostics carry a compact `DetectionPayload`: the pattern's ID and
+//! name, the evaluated severity and the extracted values. Everything else
+//! about the pattern (template, regex, the full TagScout annotation) and
+//! about the detection (log line, severity source, alternatives) is
+//! resolved on demand through `logScout/patternDetails`, or by resolving
+//! one of the detection's code actions.
+
+use crate::dedup::Alternative;
+use crate::pattern_engine::{Detection, LogLevel, Pattern, Severity, SeveritySource};
+use crate::payload::{DetectionPayload, SCHEMA_VERSION};
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::Url;
+
+/// Custom request returning the details of a pattern
+pub const PATTERN_DETAILS_METHOD: &str = "logScout/patternDetails";
+
+/// Parameters of the `logScout/patternDetails` request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternDetailsParams {
+    pub pattern_id: String,
+
+    /// Document of a detection to describe as well
+    #[serde(default)]
+    pub uri: Option<Url>,
+
+    /// 0-based line of that detection
+    #[serde(default)]
+    pub line: Option<u32>,
+}
+
+/// Full description of a pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternDetails {
+    /// `SCHEMA_VERSION` of the payload module
+    pub version: u32,
+    pub pattern_id: String,
+    pub name: String,
+
+    /// Message template with `{{ FIELD }}` placeholders
+    pub template: String,
+    pub regex: String,
+    pub category: String,
+    pub severity: Severity,
+    pub product: Option<String>,
+    pub tags: Vec<String>,
+    pub action: Option<String>,
+
+    /// Original TagScout annotation, when the pattern came from TagScout
+    pub metadata: Option<serde_json::Value>,
+
+    /// The requested detection, when a document and line were given
+    pub detection: Option<DetectionDetails>,
+}
+
+/// Description of one detection of a pattern
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectionDetails {
+    /// Same payload as the detection's diagnostic
+    pub payload: DetectionPayload,
+    pub line: usize,
+
+    /// Columns in the negotiated position encoding, as in the diagnostic
+    pub column_range: (usize, usize),
+
+    /// Template with the extracted values filled in
+    pub message: String,
+    pub log_line: String,
+    pub matched_text: String,
+    pub timestamp: Option<String>,
+    pub log_level: Option<LogLevel>,
+    pub severity_source: SeveritySource,
+
+    /// Patterns that matched overlapping text, with the criterion they lost on
+    pub alternatives: Vec<Alternative>,
+}
+
+impl PatternDetails {
+    pub fn new(pattern: &Pattern) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            pattern_id: pattern.id.clone(),
+            name: pattern.name.clone(),
+            template: pattern.annotation.clone(),
+            regex: pattern.pattern.clone(),
+            category: pattern.category.clone(),
+            severity: pattern.severity,
+            product: pattern.service.clone(),
+            tags: pattern.tags.clone(),
+            action: pattern.action.clone(),
+            metadata: pattern.tagscout_metadata.clone(),
+            detection: None,
+        }
+    }
+
+    /// Add the description of a detection; `message` is its substituted template
+    pub fn with_detection(mut self, detection: &Detection, message: String) -> Self {
+        self.detection = Some(DetectionDetails {
+            payload: DetectionPayload::new(detection),
+            line: detection.line_number,
+            column_range: detection.column_range,
+            message,
+            log_line: detection.context.first().cloned().unwrap_or_default(),
+            matched_text: detection.matched_text.clone(),
+            timestamp: detection.timestamp.clone(),
+            log_level: detection.log_level,
+            severity_source: detection.severity_source.clone(),
+            alternatives: detection.alternatives.clone(),
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{deduplicate_detections, PatternEngine};
+
+    #[test]
+    fn test_pattern_details() {
+        let pattern = Pattern {
+            name: "SIP unauthorized".to_string(),
+            annotation: "Registration rejected for {{ user }}".to_string(),
+            category: "registration".to_string(),
+            service: Some("Jabber".to_string()),
+            tagscout_metadata: Some(serde_json::json!({ "raw_data": "x".repeat(4096) })),
+            ..Pattern::test("sip-401", r"SIP/2\.0 401 user=(?P<user>\w+)")
+        };
+        let generic = Pattern::test("sip-response", r"SIP/2\.0 \d+");
+        let engine = PatternEngine::new(vec![pattern, generic], 0.7, 10).unwrap();
+        let detection =
+            deduplicate_detections(engine.process_line("10:00:00 SIP/2.0 401 user=alice", 4))
+                .remove(0);
+
+        let details = PatternDetails::new(&detection.pattern)
+            .with_detection(&detection, "Registration rejected for alice".to_string());
+        let json = serde_json::to_value(&details).unwrap();
+        assert_eq!(json["patternId"], "sip-401");
+        assert_eq!(json["detection"]["columnRange"], serde_json::json!([9, 31]));
+        assert_eq!(
+            json["detection"]["logLine"],
+            "10:00:00 SIP/2.0 401 user=alice"
+        );
+        assert!(!json["detection"]["severitySource"].is_null());
+        let alternative = &json["detection"]["alternatives"][0];
+        assert_eq!(alternative["patternId"], "sip-response");
+        assert_eq!(alternative["columnRange"], serde_json::json!([9, 20]));
+        assert_eq!(alternative["decidedBy"], "specificity");
+        assert!(alternative.get("pattern_id").is_none());
+        assert_eq!(
+            details.metadata.unwrap()["raw_data"]
+                .as_str()
+                .unwrap()
+                .len(),
+            4096
+        );
+        let detection = details.detection.unwrap();
+        assert_eq!(detection.line, 4);
+        assert_eq!(detection.log_line, "10:00:00 SIP/2.0 401 user=alice");
+        assert_eq!(detection.payload.parameters["user"], "alice");
+        assert_eq!(detection.payload.severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_params_are_camel_case() {
+        let params: PatternDetailsParams = serde_json::from_value(serde_json::json!({
+            "patternId": "sip-401",
+            "uri": "file:///logs/jabber.log",
+            "line": 41,
+        }))
+        .unwrap();
+        assert_eq!(params.pattern_id, "sip-401");
+        assert_eq!(params.line, Some(41));
+    }
+}
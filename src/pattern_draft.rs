@@ -0,0 +1,367 @@
+//! Pattern Drafts
+//!
+//! Turns a log line into a proposed pattern for a local pattern pack, so a
+//! line no pattern explains yet can be annotated from the editor. Variable
+//! parts (timestamps, GUIDs, IPs, hex IDs, numbers) are generalized in the
+//! regex, get a parameter extractor each, and become `{{ FIELD }}`
+//! placeholders in the template. Drafts are written to `drafts.yaml` in the
+//! workspace's `.logscout/patterns` directory and picked up like any other
+//! local pack.
+
+use crate::pattern_engine::{CompiledPattern, ParameterExtractor, Pattern, PatternMode, Severity};
+use crate::timestamp;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Pack file receiving drafted patterns
+pub const DRAFT_PACK_FILE: &str = "drafts.yaml";
+
+/// Category of drafted patterns
+pub const DRAFT_CATEGORY: &str = "draft";
+
+/// Number of message words used to name a draft
+const NAME_WORDS: usize = 6;
+
+/// Longest literal text kept in front of a parameter extractor's capture
+const EXTRACTOR_CONTEXT: usize = 24;
+
+lazy_static! {
+    /// Variable parts of a message, most specific first
+    static ref VARIABLE: Regex = Regex::new(concat!(
+        r"(?P<timestamp>(?:\d{4}-\d{2}-\d{2}[T ])?\d{2}:\d{2}:\d{2}(?:[.,]\d+)?)",
+        r"|(?P<guid>\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b)",
+        r"|(?P<ip>\b\d{1,3}(?:\.\d{1,3}){3}\b)",
+        r"|(?P<hex>\b0x[0-9a-fA-F]+\b|\b[0-9a-fA-F]{6,}\b)",
+        r"|(?P<number>\b\d+(?:\.\d+)?\b)",
+    ))
+    .unwrap();
+
+    /// `name=` or `name:` right before a value
+    static ref FIELD_NAME: Regex = Regex::new(r"([A-Za-z][A-Za-z0-9_]*)\s*[=:]\s*$").unwrap();
+}
+
+/// Kind of a variable part of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variable {
+    Timestamp,
+    Guid,
+    Ip,
+    Hex,
+    Number,
+}
+
+impl Variable {
+    /// Regex matching any value of this kind
+    fn regex(self) -> &'static str {
+        match self {
+            Variable::Timestamp => r"(?:\d{4}-\d{2}-\d{2}[T ])?\d{2}:\d{2}:\d{2}(?:[.,]\d+)?",
+            Variable::Guid => {
+                r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}"
+            }
+            Variable::Ip => r"\d{1,3}(?:\.\d{1,3}){3}",
+            Variable::Hex => r"(?:0x)?[0-9a-fA-F]+",
+            Variable::Number => r"\d+(?:\.\d+)?",
+        }
+    }
+
+    /// Field name used when the text gives no better one
+    fn field_name(self) -> &'static str {
+        match self {
+            Variable::Timestamp => "TIMESTAMP",
+            Variable::Guid => "GUID",
+            Variable::Ip => "IP",
+            Variable::Hex => "HEX_ID",
+            Variable::Number => "NUMBER",
+        }
+    }
+}
+
+/// A piece of a message: literal text or a variable value
+#[derive(Debug)]
+enum Part<'a> {
+    Literal(&'a str),
+    Variable(Variable, &'a str),
+}
+
+/// Split a message into literal text and variable values
+fn split_message(message: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut literal_start = 0;
+
+    for captures in VARIABLE.captures_iter(message) {
+        let value = captures.get(0).unwrap();
+        let kind = if captures.name("timestamp").is_some() {
+            Variable::Timestamp
+        } else if captures.name("guid").is_some() {
+            Variable::Guid
+        } else if captures.name("ip").is_some() {
+            Variable::Ip
+        } else if captures.name("number").is_some() {
+            Variable::Number
+        } else {
+            // Long runs of hex digits: all digits is a number, all letters
+            // is a word
+            let text = value.as_str().trim_start_matches("0x");
+            if value.as_str().starts_with("0x") {
+                Variable::Hex
+            } else if text.chars().all(|c| c.is_ascii_digit()) {
+                Variable::Number
+            } else if text.chars().any(|c| c.is_ascii_digit()) {
+                Variable::Hex
+            } else {
+                continue;
+            }
+        };
+
+        if value.start() > literal_start {
+            parts.push(Part::Literal(&message[literal_start..value.start()]));
+        }
+        parts.push(Part::Variable(kind, value.as_str()));
+        literal_start = value.end();
+    }
+
+    if literal_start < message.len() {
+        parts.push(Part::Literal(&message[literal_start..]));
+    }
+    parts
+}
+
+/// The message part of a log line, without a leading timestamp
+pub fn message_of(line: &str) -> &str {
+    let message = match timestamp::extract_timestamp(line) {
+        Some(ts) if line[..ts.start].trim().is_empty() => &line[ts.start + ts.raw.len()..],
+        _ => line,
+    };
+    message.trim_start_matches(|c: char| c.is_whitespace() || c == ']' || c == '-' || c == ':')
+}
+
+/// Identifier made of the first words of a message, e.g. `connection-refused-by-peer`
+pub fn slug(message: &str) -> String {
+    let words: Vec<String> = message
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| word.chars().any(|c| c.is_ascii_alphabetic()))
+        .take(NAME_WORDS)
+        .map(str::to_ascii_lowercase)
+        .collect();
+
+    if words.is_empty() {
+        "pattern".to_string()
+    } else {
+        words.join("-")
+    }
+}
+
+/// Tail of a literal used as an extractor's left context, cut at a char boundary
+fn literal_tail(literal: &str) -> &str {
+    let mut start = literal.len().saturating_sub(EXTRACTOR_CONTEXT);
+    while !literal.is_char_boundary(start) {
+        start += 1;
+    }
+    &literal[start..]
+}
+
+/// Head of a literal used as an extractor's right context
+fn literal_head(literal: &str) -> &str {
+    let mut end = literal.len().min(EXTRACTOR_CONTEXT);
+    while !literal.is_char_boundary(end) {
+        end -= 1;
+    }
+    &literal[..end]
+}
+
+/// Regex of the whole message with only the part at `index` captured
+fn capture_in_context(parts: &[Part], index: usize) -> String {
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| match part {
+            Part::Literal(text) => regex::escape(text),
+            Part::Variable(kind, _) if i == index => format!("({})", kind.regex()),
+            Part::Variable(kind, _) => format!("(?:{})", kind.regex()),
+        })
+        .collect()
+}
+
+/// Extractor regex for the variable at `index`
+///
+/// Extractors run on the whole line, so the neighbouring literal text is
+/// used as context only when that finds the same value in `line`; otherwise
+/// the regex spells out the whole message.
+fn extractor_regex(line: &str, parts: &[Part], index: usize) -> String {
+    let Part::Variable(kind, value) = parts[index] else {
+        return String::new();
+    };
+
+    let before = index.checked_sub(1).and_then(|i| match parts[i] {
+        Part::Literal(text) if !text.trim().is_empty() => Some(literal_tail(text)),
+        _ => None,
+    });
+    let after = parts.get(index + 1).and_then(|part| match part {
+        Part::Literal(text) if !text.trim().is_empty() => Some(literal_head(text)),
+        _ => None,
+    });
+
+    let short = match (before, after) {
+        (Some(before), _) => Some(format!("{}({})", regex::escape(before), kind.regex())),
+        (None, Some(after)) => Some(format!("({}){}", kind.regex(), regex::escape(after))),
+        (None, None) => None,
+    };
+
+    let finds_value = |regex: &str| {
+        Regex::new(regex)
+            .ok()
+            .and_then(|re| re.captures(line))
+            .and_then(|captures| captures.get(1))
+            .is_some_and(|capture| capture.as_str() == value)
+    };
+    match short {
+        Some(short) if finds_value(&short) => short,
+        _ => capture_in_context(parts, index),
+    }
+}
+
+/// Propose a pattern for `line`
+///
+/// The regex matches the message (without a leading timestamp) with its
+/// variable parts generalized; each variable part gets a parameter
+/// extractor, named after a preceding `name=`/`name:` when there is one,
+/// and a placeholder in the template. The ID is derived from the literal
+/// words and made unique with a numeric suffix when `is_taken` reports a
+/// clash. The severity follows the line's log level (warning when there
+/// is none).
+pub fn draft_from_line(line: &str, is_taken: impl Fn(&str) -> bool) -> Pattern {
+    let message = message_of(line).trim_end();
+    let parts = split_message(message);
+
+    let mut pattern = String::new();
+    let mut template = String::new();
+    let mut words = String::new();
+    let mut parameter_extractors: Vec<ParameterExtractor> = Vec::new();
+
+    for (index, part) in parts.iter().enumerate() {
+        match *part {
+            Part::Literal(text) => {
+                pattern.push_str(&regex::escape(text));
+                template.push_str(text);
+                words.push_str(text);
+            }
+            Part::Variable(kind, _) => {
+                pattern.push_str(kind.regex());
+
+                let preceding = match index.checked_sub(1).map(|i| &parts[i]) {
+                    Some(Part::Literal(text)) => *text,
+                    _ => "",
+                };
+                let base = FIELD_NAME
+                    .captures(preceding)
+                    .map(|captures| captures[1].to_ascii_uppercase())
+                    .unwrap_or_else(|| kind.field_name().to_string());
+                let name = std::iter::once(base.clone())
+                    .chain((2..).map(|n| format!("{}_{}", base, n)))
+                    .find(|name| parameter_extractors.iter().all(|e| e.name != *name))
+                    .unwrap_or(base);
+
+                template.push_str(&format!("{{{{ {} }}}}", name));
+                words.push(' ');
+                parameter_extractors.push(ParameterExtractor {
+                    name,
+                    regex: extractor_regex(line, &parts, index),
+                    enum_name: None,
+                });
+            }
+        }
+    }
+
+    let base = format!("draft-{}", slug(&words));
+    let id = std::iter::once(base.clone())
+        .chain((2..).map(|n| format!("{}-{}", base, n)))
+        .find(|id| !is_taken(id))
+        .unwrap_or(base);
+
+    let name: String = words
+        .split_whitespace()
+        .take(NAME_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let severity = CompiledPattern::detect_log_level(line)
+        .map(|level| level.to_severity())
+        .unwrap_or(Severity::Warning);
+
+    Pattern {
+        id,
+        name,
+        annotation: template,
+        pattern,
+        mode: PatternMode::SingleLine,
+        severity,
+        category: DRAFT_CATEGORY.to_string(),
+        service: None,
+        tags: vec![],
+        action: None,
+        expected_frequency: None,
+        enabled: true,
+        log_level_triggers: HashMap::new(),
+        condition_triggers: Vec::new(),
+        capture_fields: Vec::new(),
+        parameter_extractors,
+        priority: 0,
+        tagscout_metadata: None,
+        source: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::PatternEngine;
+
+    #[test]
+    fn test_draft_generalizes_variable_parts() {
+        let line = "2024-01-15 10:00:02,123 ERROR [sip] Connection refused by peer 10.0.0.1 \
+                    port=5060 call=3f2a9c1b-0d4e-4a6b-9c8d-1e2f3a4b5c6d session 0x7ffe12 at 10:00:01";
+        assert!(message_of(line).starts_with("ERROR [sip] Connection refused"));
+
+        let draft = draft_from_line(line, |id| {
+            id == "draft-error-sip-connection-refused-by-peer"
+        });
+        assert_eq!(draft.id, "draft-error-sip-connection-refused-by-peer-2");
+        assert_eq!(draft.name, "ERROR [sip] Connection refused by peer");
+        assert_eq!(draft.severity, Severity::Error);
+        assert_eq!(
+            draft.annotation,
+            "ERROR [sip] Connection refused by peer {{ IP }} port={{ PORT }} \
+             call={{ CALL }} session {{ HEX_ID }} at {{ TIMESTAMP }}"
+        );
+        let names: Vec<&str> = draft
+            .parameter_extractors
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["IP", "PORT", "CALL", "HEX_ID", "TIMESTAMP"]);
+
+        // The draft matches other occurrences and extracts their values
+        let engine = PatternEngine::new(vec![draft], 0.7, 10).unwrap();
+        let other = "2024-01-16 08:00:00,000 ERROR [sip] Connection refused by peer 192.168.1.20 \
+                     port=5061 call=00000000-1111-2222-3333-444444444444 session 0xabc at 08:00:00";
+        let detections = engine.process_line(other, 0);
+        assert_eq!(detections.len(), 1);
+
+        let fields = &detections[0].field_values;
+        assert_eq!(fields["IP"], "192.168.1.20");
+        assert_eq!(fields["PORT"], "5061");
+        assert_eq!(fields["CALL"], "00000000-1111-2222-3333-444444444444");
+        assert_eq!(fields["HEX_ID"], "0xabc");
+        assert_eq!(fields["TIMESTAMP"], "08:00:00");
+    }
+
+    #[test]
+    fn test_hex_runs_without_digits_stay_literal() {
+        let draft = draft_from_line("cache facade flushed 1234567 entries", |_| false);
+        assert_eq!(
+            draft.annotation,
+            "cache facade flushed {{ NUMBER }} entries"
+        );
+        assert_eq!(draft.id, "draft-cache-facade-flushed-entries");
+    }
+}
@@ -6,11 +6,14 @@
 //! - Baseline deviation detection
 //! - Performance-optimized streaming processing
 
+use crate::dedup::{self, Alternative, DedupOptions};
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use thiserror::Error;
+use tower_lsp::lsp_types::{DiagnosticSeverity, Position, Range};
 
 /// Error types for pattern engine operations
 #[derive(Error, Debug)]
@@ -29,7 +32,7 @@ pub enum PatternError {
 }
 
 /// Severity level for pattern matches
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Error,
@@ -38,6 +41,66 @@ pub enum Severity {
     Hint,
 }
 
+impl Severity {
+    /// Parse severity from string
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "error" => Some(Severity::Error),
+            "warning" | "warn" => Some(Severity::Warning),
+            "info" | "information" => Some(Severity::Info),
+            "hint" => Some(Severity::Hint),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as used in configuration and reports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+            Severity::Hint => "hint",
+        }
+    }
+
+    /// Rank for comparisons; higher is more severe
+    pub fn rank(&self) -> u8 {
+        match self {
+            Severity::Error => 3,
+            Severity::Warning => 2,
+            Severity::Info => 1,
+            Severity::Hint => 0,
+        }
+    }
+}
+
+impl From<Severity> for DiagnosticSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Error => DiagnosticSeverity::ERROR,
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Info => DiagnosticSeverity::INFORMATION,
+            Severity::Hint => DiagnosticSeverity::HINT,
+        }
+    }
+}
+
+/// Range of the columns `start..end` on one line
+pub fn line_range(line: usize, (start, end): (usize, usize)) -> Range {
+    let line = line as u32;
+    Range {
+        start: Position {
+            line,
+            character: start as u32,
+        },
+        end: Position {
+            line,
+            character: end as u32,
+        },
+    }
+}
+
 /// Log level detected in the log line
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
@@ -54,6 +117,7 @@ pub enum LogLevel {
 
 impl LogLevel {
     /// Parse log level from string
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
             "FATAL" | "CRITICAL" | "CRIT" => Some(LogLevel::FATAL),
@@ -109,6 +173,33 @@ pub struct SeverityTrigger {
     pub description: Option<String>,
 }
 
+/// Why a detection has its final severity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SeveritySource {
+    /// The pattern's own severity
+    Default,
+
+    /// A log-level trigger of the pattern
+    LogLevel { level: LogLevel },
+
+    /// A condition trigger of the pattern (index into `condition_triggers`)
+    Condition {
+        index: usize,
+        field: String,
+        operator: ConditionOperator,
+        value: String,
+        description: Option<String>,
+    },
+
+    /// A rule of the user's severity policy (index into the policy)
+    Policy {
+        rule: usize,
+        scope: String,
+        original: Severity,
+    },
+}
+
 /// Pattern matching mode
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -185,9 +276,74 @@ pub struct Pattern {
     #[serde(default)]
     pub parameter_extractors: Vec<ParameterExtractor>,
 
+    /// Precedence over patterns matching overlapping text (higher wins)
+    #[serde(default)]
+    pub priority: i32,
+
     /// Original TagScout annotation metadata (if from TagScout)
     #[serde(default)]
     pub tagscout_metadata: Option<serde_json::Value>,
+
+    /// Where the pattern was defined (if loaded from a local pattern pack)
+    #[serde(skip)]
+    pub source: Option<PatternSource>,
+}
+
+#[cfg(test)]
+impl Pattern {
+    /// Enabled single-line error pattern with every optional field left empty
+    pub fn test(id: &str, regex: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            annotation: String::new(),
+            pattern: regex.to_string(),
+            mode: PatternMode::SingleLine,
+            severity: Severity::Error,
+            category: "test".to_string(),
+            service: None,
+            tags: vec![],
+            action: None,
+            expected_frequency: None,
+            enabled: true,
+            log_level_triggers: std::collections::HashMap::new(),
+            condition_triggers: vec![],
+            capture_fields: vec![],
+            parameter_extractors: vec![],
+            priority: 0,
+            tagscout_metadata: None,
+            source: None,
+        }
+    }
+
+    /// `Pattern::test` with the severity and category set
+    pub fn test_with(id: &str, regex: &str, severity: Severity, category: &str) -> Self {
+        Self {
+            severity,
+            category: category.to_string(),
+            ..Self::test(id, regex)
+        }
+    }
+}
+
+/// Detections of `patterns` in each line of `text`
+#[cfg(test)]
+pub fn detect_lines(patterns: Vec<Pattern>, text: &str) -> Vec<Detection> {
+    let engine = PatternEngine::new(patterns, 0.7, 10).unwrap();
+    text.lines()
+        .enumerate()
+        .flat_map(|(n, line)| engine.process_line(line, n))
+        .collect()
+}
+
+/// Location of a pattern definition in a local pattern pack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternSource {
+    /// Pattern pack file
+    pub path: std::path::PathBuf,
+
+    /// Line of the pattern's `id` entry (0-based)
+    pub line: usize,
 }
 
 /// Parameter extractor for field extraction (from TagScout parameters)
@@ -195,6 +351,10 @@ pub struct Pattern {
 pub struct ParameterExtractor {
     pub name: String,
     pub regex: String,
+
+    /// Name of the TagScout enum used to decode extracted values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enum_name: Option<String>,
 }
 
 fn default_pattern_mode() -> PatternMode {
@@ -376,21 +536,50 @@ impl CompiledPattern {
         fields
     }
 
+    /// Locate the values captured by the parameter extractors in a line
+    ///
+    /// Same extraction as `extract_fields`, but keeps the byte offsets of
+    /// each value so editor features can point at them.
+    pub fn parameter_spans(&self, line: &str) -> Vec<ParameterSpan> {
+        self.parameter_regexes
+            .iter()
+            .filter_map(|(name, regex)| {
+                let value = regex.captures(line)?.get(1)?;
+                Some(ParameterSpan {
+                    name: name.clone(),
+                    value: value.as_str().to_string(),
+                    start: value.start(),
+                    end: value.end(),
+                })
+            })
+            .collect()
+    }
+
     /// Evaluate severity based on log level and condition triggers
     pub fn evaluate_severity(
         &self,
         log_level: Option<LogLevel>,
         field_values: &HashMap<String, String>,
     ) -> Severity {
+        self.evaluate_severity_with_source(log_level, field_values)
+            .0
+    }
+
+    /// Evaluate severity and record which trigger (if any) decided it
+    pub fn evaluate_severity_with_source(
+        &self,
+        log_level: Option<LogLevel>,
+        field_values: &HashMap<String, String>,
+    ) -> (Severity, SeveritySource) {
         // Check log level triggers first
         if let Some(level) = log_level {
             if let Some(severity) = self.pattern.log_level_triggers.get(&level) {
-                return *severity;
+                return (*severity, SeveritySource::LogLevel { level });
             }
         }
 
         // Check condition triggers
-        for trigger in &self.pattern.condition_triggers {
+        for (index, trigger) in self.pattern.condition_triggers.iter().enumerate() {
             if let Some(value) = field_values.get(&trigger.field) {
                 let matches = match trigger.operator {
                     ConditionOperator::Equals => value == &trigger.value,
@@ -423,16 +612,32 @@ impl CompiledPattern {
                 };
 
                 if matches {
-                    return trigger.severity;
+                    let source = SeveritySource::Condition {
+                        index,
+                        field: trigger.field.clone(),
+                        operator: trigger.operator.clone(),
+                        value: trigger.value.clone(),
+                        description: trigger.description.clone(),
+                    };
+                    return (trigger.severity, source);
                 }
             }
         }
 
         // Default to pattern severity
-        self.pattern.severity
+        (self.pattern.severity, SeveritySource::Default)
     }
 }
 
+/// An extracted parameter value and its location in the line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterSpan {
+    pub name: String,
+    pub value: String,
+    pub start: usize,
+    pub end: usize,
+}
+
 /// A pattern match result
 #[derive(Debug, Clone)]
 pub struct PatternMatch {
@@ -473,8 +678,68 @@ pub struct Detection {
     /// Final severity (after evaluating triggers)
     pub final_severity: Severity,
 
+    /// What decided the final severity
+    pub severity_source: SeveritySource,
+
     /// Extracted field values from named captures
     pub field_values: HashMap<String, String>,
+
+    /// Patterns that matched overlapping text but lost on precedence
+    pub alternatives: Vec<Alternative>,
+}
+
+impl Detection {
+    /// Check whether a (0-based) line and column fall inside the matched span
+    pub fn covers(&self, line: usize, column: usize) -> bool {
+        let (start, end) = self.column_range;
+        self.line_number == line && start <= column && column <= end
+    }
+}
+
+/// Deduplicate detections that overlap on the same line
+///
+/// When multiple patterns match overlapping text on a line, keep only the
+/// one with the highest precedence (see `dedup::DEFAULT_PRECEDENCE`); the
+/// others are recorded as its alternatives. This handles cases where
+/// TagScout has multiple patterns with the same regex but different templates
+/// (e.g., HTTP success vs error patterns that both match any HTTP response).
+///
+/// In the analysis pipeline this runs after the signature, process and
+/// scenario stages, so those see all matches, and before remediation.
+pub fn deduplicate_detections(detections: Vec<Detection>) -> Vec<Detection> {
+    dedup::deduplicate(detections, &DedupOptions::default())
+}
+
+/// Replace template placeholders like {{ fieldName }} with actual values from field_values
+/// Handles all spacing variations: {{CODE}}, {{ CODE }}, {{ CODE}}, {{CODE }}
+pub fn substitute_template(template: &str, field_values: &HashMap<String, String>) -> String {
+    tracing::trace!("=== SUBSTITUTE_TEMPLATE ===");
+    tracing::trace!("  Input template: '{}'", template);
+    tracing::trace!("  Field values: {:?}", field_values);
+
+    let mut result = template.to_string();
+
+    // Replace each placeholder with the actual value
+    for (field_name, field_value) in field_values {
+        // Create regex to match {{optional_spaces field_name optional_spaces}}
+        // This handles: {{CODE}}, {{ CODE }}, {{ CODE}}, {{CODE }}, etc.
+        let pattern_str = format!(r"\{{\{{\s*{}\s*\}}\}}", regex::escape(field_name));
+        if let Ok(re) = Regex::new(&pattern_str) {
+            let before = result.clone();
+            result = re.replace_all(&result, field_value.as_str()).to_string();
+            if before != result {
+                tracing::trace!("  Replaced {{{{ {} }}}} with '{}'", field_name, field_value);
+            }
+        }
+    }
+
+    // Keep unsubstituted placeholders as-is (shows {{ FIELD }} instead of [...])
+    // This makes it clear which fields weren't extracted
+
+    tracing::trace!("  Output result: '{}'", result);
+    tracing::trace!("=== END SUBSTITUTE_TEMPLATE ===");
+
+    result
 }
 
 /// Pattern engine for log analysis
@@ -524,8 +789,9 @@ impl PatternEngine {
     pub fn process_line(&self, line: &str, line_number: usize) -> Vec<Detection> {
         let mut detections = Vec::new();
 
-        // Detect log level once for the entire line
+        // Detect log level and timestamp once for the entire line
         let log_level = CompiledPattern::detect_log_level(line);
+        let timestamp = crate::timestamp::extract_timestamp(line).map(|ts| ts.raw);
 
         for compiled_pattern in &self.patterns {
             match compiled_pattern.pattern.mode {
@@ -538,8 +804,8 @@ impl PatternEngine {
                         let field_values = compiled_pattern.extract_fields(&cap, line);
 
                         // Evaluate final severity based on log level and conditions
-                        let final_severity =
-                            compiled_pattern.evaluate_severity(log_level, &field_values);
+                        let (final_severity, severity_source) = compiled_pattern
+                            .evaluate_severity_with_source(log_level, &field_values);
 
                         // Extract all capture groups as strings
                         let captures: Vec<String> = cap
@@ -555,9 +821,11 @@ impl PatternEngine {
                             matched_text: full_match.as_str().to_string(),
                             captures,
                             context: vec![line.to_string()],
-                            timestamp: None, // TODO: Parse timestamp
+                            timestamp: timestamp.clone(),
                             log_level,
                             final_severity,
+                            severity_source,
+                            alternatives: Vec::new(),
                             field_values,
                         });
                     }
@@ -586,7 +854,7 @@ impl PatternEngine {
     pub fn get_patterns_by_service(&self, service: &str) -> Vec<&CompiledPattern> {
         self.patterns
             .iter()
-            .filter(|p| p.pattern.service.as_ref().map_or(false, |s| s == service))
+            .filter(|p| p.pattern.service.as_deref() == Some(service))
             .map(|arc| arc.as_ref())
             .collect()
     }
@@ -647,8 +915,9 @@ impl ContextProcessor {
                 let context = self.get_context(context_lines);
                 let combined = context.join("\n");
 
-                // Detect log level from the combined text
+                // Detect log level and timestamp from the combined text
                 let log_level = CompiledPattern::detect_log_level(&combined);
+                let timestamp = crate::timestamp::extract_timestamp(&combined).map(|ts| ts.raw);
 
                 // Get all regex captures
                 for cap in pattern.regex.captures_iter(&combined) {
@@ -658,7 +927,8 @@ impl ContextProcessor {
                     let field_values = pattern.extract_fields(&cap, &combined);
 
                     // Evaluate final severity
-                    let final_severity = pattern.evaluate_severity(log_level, &field_values);
+                    let (final_severity, severity_source) =
+                        pattern.evaluate_severity_with_source(log_level, &field_values);
 
                     // Extract capture groups
                     let captures: Vec<String> = cap
@@ -674,9 +944,11 @@ impl ContextProcessor {
                         matched_text: full_match.as_str().to_string(),
                         captures,
                         context: context.clone(),
-                        timestamp: None,
+                        timestamp: timestamp.clone(),
                         log_level,
                         final_severity,
+                        severity_source,
+                        alternatives: Vec::new(),
                         field_values,
                     });
                 }
@@ -716,6 +988,9 @@ mod tests {
             condition_triggers: Vec::new(),
             capture_fields: Vec::new(),
             parameter_extractors: Vec::new(),
+            priority: 0,
+            tagscout_metadata: None,
+            source: None,
         };
 
         let compiled = CompiledPattern::new(pattern);
@@ -741,6 +1016,9 @@ mod tests {
             condition_triggers: Vec::new(),
             capture_fields: Vec::new(),
             parameter_extractors: Vec::new(),
+            priority: 0,
+            tagscout_metadata: None,
+            source: None,
         };
 
         let compiled = CompiledPattern::new(pattern).unwrap();
@@ -767,6 +1045,9 @@ mod tests {
             condition_triggers: Vec::new(),
             capture_fields: Vec::new(),
             parameter_extractors: Vec::new(),
+            priority: 0,
+            tagscout_metadata: None,
+            source: None,
         }];
 
         let engine = PatternEngine::new(patterns, 0.85, 10).unwrap();
@@ -776,6 +1057,34 @@ mod tests {
         assert_eq!(detections[0].pattern.id, "error-pattern");
     }
 
+    #[test]
+    fn test_parameter_spans() {
+        let pattern = Pattern {
+            name: "Registration".to_string(),
+            annotation: "Registration state {{ STATE }}".to_string(),
+            severity: Severity::Info,
+            category: "sip".to_string(),
+            parameter_extractors: vec![ParameterExtractor {
+                name: "STATE".to_string(),
+                regex: r"state=(\d+)".to_string(),
+                enum_name: None,
+            }],
+            ..Pattern::test("registration", r"Registration")
+        };
+
+        let compiled = CompiledPattern::new(pattern).unwrap();
+        let spans = compiled.parameter_spans("Registration update state=3 done");
+        assert_eq!(
+            spans,
+            vec![ParameterSpan {
+                name: "STATE".to_string(),
+                value: "3".to_string(),
+                start: 26,
+                end: 27,
+            }]
+        );
+    }
+
     #[test]
     fn test_context_processor() {
         let mut processor = ContextProcessor::new(5);
@@ -0,0 +1,437 @@
+//! Diagnostic Payloads
+//!
+//! The typed, versioned `data` of the diagnostics of detections, signatures
+//! and scenarios, tagged by `kind`. The detection payload is also shared by
+//! JSON/SARIF exports and custom requests. Keys are fixed here, so TagScout
+//! metadata cannot collide with them; it is only served by
+//! `logScout/patternDetails`. The JSON Schema (`log-scout-lsp-server
+//! schema`) is derived from these types and checked in as
+//! `schema/diagnostic-payload.schema.json`.
+
+use crate::pattern_engine::{Detection, Severity};
+use crate::scenario::ScenarioMatch;
+use crate::signature::SignatureMatch;
+use crate::suppression::SuppressionRule;
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version of the payload shapes
+///
+/// Version 1 was the untyped map with the full TagScout annotation, and
+/// version 2 described detections only, without `kind`.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// `$id` of the payloads' JSON Schema
+pub const SCHEMA_ID: &str = "urn:log-scout:diagnostic-payload";
+
+/// Diagnostic `data` written by this server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DiagnosticPayload {
+    Detection(DetectionPayload),
+    Signature(SignaturePayload),
+    Scenario(ScenarioPayload),
+}
+
+impl DiagnosticPayload {
+    /// Payload of a diagnostic's `data`
+    ///
+    /// `None` if there is no data; an error if it is not a payload of this
+    /// version.
+    pub fn from_data(data: Option<&serde_json::Value>) -> Option<Result<Self, serde_json::Error>> {
+        data.map(|data| serde_json::from_value(data.clone()))
+    }
+
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+}
+
+/// What a client needs to know about a detection without asking the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DetectionPayload {
+    /// Always `SCHEMA_VERSION` when produced by this server
+    #[schemars(schema_with = "version_schema")]
+    pub version: u32,
+    pub pattern_id: String,
+    pub pattern_name: String,
+
+    /// Evaluated severity (after conditions and the severity policy)
+    pub severity: Severity,
+
+    /// Values extracted by the pattern
+    pub parameters: BTreeMap<String, String>,
+
+    /// IDs of patterns that matched overlapping text but lost on precedence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<String>,
+
+    /// Whether this diagnostic summarizes several occurrences
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub aggregated: bool,
+
+    /// Number of summarized occurrences
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(range(min = 1))]
+    pub occurrences: Option<usize>,
+
+    /// 0-based lines of the first summarized occurrences, at most
+    /// `aggregation::MAX_RELATED_LOCATIONS`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub occurrence_lines: Vec<usize>,
+
+    /// Rule muting the detection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suppressed_by: Option<SuppressionRule>,
+}
+
+impl DetectionPayload {
+    pub fn new(detection: &Detection) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            pattern_id: detection.pattern.id.clone(),
+            pattern_name: detection.pattern.name.clone(),
+            severity: detection.final_severity,
+            parameters: detection
+                .field_values
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            alternatives: detection
+                .alternatives
+                .iter()
+                .map(|alternative| alternative.pattern_id.clone())
+                .collect(),
+            aggregated: false,
+            occurrences: None,
+            occurrence_lines: Vec::new(),
+            suppressed_by: None,
+        }
+    }
+
+    /// Payload of a diagnostic's `data`, if it holds a detection payload
+    pub fn from_data(data: Option<&serde_json::Value>) -> Option<Self> {
+        match DiagnosticPayload::from_data(data)? {
+            Ok(DiagnosticPayload::Detection(payload)) => Some(payload),
+            _ => None,
+        }
+    }
+
+    /// The payload as diagnostic `data`, tagged with its kind
+    pub fn to_value(&self) -> serde_json::Value {
+        DiagnosticPayload::Detection(self.clone()).to_value()
+    }
+}
+
+/// What a client needs to know about a signature diagnostic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SignaturePayload {
+    /// Always `SCHEMA_VERSION` when produced by this server
+    #[schemars(schema_with = "version_schema")]
+    pub version: u32,
+    pub signature_id: String,
+    pub signature_name: String,
+    pub category: String,
+
+    /// IDs of the member patterns, in line order
+    pub pattern_ids: Vec<String>,
+}
+
+impl SignaturePayload {
+    pub fn new(signature: &SignatureMatch) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            signature_id: signature.signature.id.clone(),
+            signature_name: signature.signature.name.clone(),
+            category: signature.signature.category.clone(),
+            pattern_ids: signature
+                .detections
+                .iter()
+                .map(|detection| detection.pattern.id.clone())
+                .collect(),
+        }
+    }
+}
+
+/// What a client needs to know about a scenario diagnostic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ScenarioPayload {
+    /// Always `SCHEMA_VERSION` when produced by this server
+    #[schemars(schema_with = "version_schema")]
+    pub version: u32,
+    pub scenario_id: String,
+    pub scenario_name: String,
+
+    /// How well the document matches the scenario (0.0 - 1.0)
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub confidence: f32,
+
+    /// Whether the events happened in the expected order
+    pub in_order: bool,
+
+    /// Labels of the steps that were not found
+    pub missing_steps: Vec<String>,
+}
+
+impl ScenarioPayload {
+    pub fn new(scenario: &ScenarioMatch) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            scenario_id: scenario.scenario.id.clone(),
+            scenario_name: scenario.scenario.name.clone(),
+            confidence: scenario.confidence,
+            in_order: scenario.in_order,
+            missing_steps: scenario.missing.clone(),
+        }
+    }
+}
+
+fn version_schema(_: &mut SchemaGenerator) -> Schema {
+    schemars::json_schema!({ "const": SCHEMA_VERSION })
+}
+
+/// JSON Schema (draft 2020-12) of `DiagnosticPayload`
+pub fn json_schema() -> serde_json::Value {
+    let mut schema = schemars::schema_for!(DiagnosticPayload);
+    schema.insert("$id".to_string(), SCHEMA_ID.into());
+    schema.to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn payload() -> DetectionPayload {
+        DetectionPayload {
+            version: SCHEMA_VERSION,
+            pattern_id: "sip-401".to_string(),
+            pattern_name: "SIP unauthorized".to_string(),
+            severity: Severity::Warning,
+            parameters: BTreeMap::from([("user".to_string(), "alice".to_string())]),
+            alternatives: Vec::new(),
+            aggregated: false,
+            occurrences: None,
+            occurrence_lines: Vec::new(),
+            suppressed_by: None,
+        }
+    }
+
+    fn signature() -> DiagnosticPayload {
+        DiagnosticPayload::Signature(SignaturePayload {
+            version: SCHEMA_VERSION,
+            signature_id: "registration-loop".to_string(),
+            signature_name: "Registration loop".to_string(),
+            category: "registration".to_string(),
+            pattern_ids: vec!["sip-401".to_string(), "sip-register".to_string()],
+        })
+    }
+
+    fn scenario() -> DiagnosticPayload {
+        DiagnosticPayload::Scenario(ScenarioPayload {
+            version: SCHEMA_VERSION,
+            scenario_id: "expired-password".to_string(),
+            scenario_name: "Expired password".to_string(),
+            confidence: 0.75,
+            in_order: true,
+            missing_steps: vec!["Password prompt".to_string()],
+        })
+    }
+
+    /// Whether a value has the types, constants, bounds and required keys
+    /// its schema gives; `root` resolves `$ref`s
+    fn conforms(
+        value: &serde_json::Value,
+        schema: &serde_json::Value,
+        root: &serde_json::Value,
+    ) -> bool {
+        use serde_json::Value;
+
+        if let Some(reference) = schema["$ref"].as_str() {
+            let target = root.pointer(reference.trim_start_matches('#')).unwrap();
+            return conforms(value, target, root);
+        }
+        if let Some(options) = schema["oneOf"].as_array() {
+            return options
+                .iter()
+                .filter(|option| conforms(value, option, root))
+                .count()
+                == 1;
+        }
+        if let Some(options) = schema["anyOf"].as_array() {
+            return options.iter().any(|option| conforms(value, option, root));
+        }
+        if let Some(expected) = schema.get("const") {
+            return value == expected;
+        }
+        if let Some(options) = schema["enum"].as_array() {
+            return options.contains(value);
+        }
+        let types: Vec<&str> = match &schema["type"] {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let has_type = |name: &str| match name {
+            "string" => value.is_string(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            "null" => value.is_null(),
+            _ => false,
+        };
+        if !types.is_empty() && !types.into_iter().any(has_type) {
+            return false;
+        }
+
+        match value {
+            Value::Object(map) => {
+                schema["required"].as_array().is_none_or(|keys| {
+                    keys.iter()
+                        .all(|key| map.contains_key(key.as_str().unwrap()))
+                }) && map
+                    .iter()
+                    .all(|(key, value)| match schema["properties"].get(key) {
+                        Some(property) => conforms(value, property, root),
+                        None => match schema.get("additionalProperties") {
+                            Some(Value::Bool(allowed)) => *allowed,
+                            Some(extra) => conforms(value, extra, root),
+                            None => true,
+                        },
+                    })
+            }
+            Value::Array(items) => items.iter().enumerate().all(|(i, item)| {
+                schema["prefixItems"]
+                    .get(i)
+                    .or_else(|| schema.get("items"))
+                    .is_none_or(|item_schema| conforms(item, item_schema, root))
+            }),
+            Value::Number(number) => {
+                let number = number.as_f64().unwrap();
+                schema["minimum"]
+                    .as_f64()
+                    .is_none_or(|minimum| number >= minimum)
+                    && schema["maximum"]
+                        .as_f64()
+                        .is_none_or(|maximum| number <= maximum)
+            }
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn test_schema_covers_payloads() {
+        let schema = json_schema();
+        let variants = schema["oneOf"].as_array().unwrap();
+        let keys = |value: serde_json::Value| -> Vec<String> {
+            let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        let required = |variant: &serde_json::Value| -> Vec<String> {
+            let mut keys: Vec<String> = variant["required"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|key| key.as_str().unwrap().to_string())
+                .collect();
+            keys.sort();
+            keys
+        };
+
+        // Minimal payloads have exactly the required keys of their variant
+        assert_eq!(variants.len(), 3);
+        assert_eq!(keys(payload().to_value()), required(&variants[0]));
+        assert_eq!(keys(signature().to_value()), required(&variants[1]));
+        assert_eq!(keys(scenario().to_value()), required(&variants[2]));
+
+        // Every key of a full payload is described
+        let mut full = payload();
+        full.alternatives = vec!["http-response".to_string()];
+        full.aggregated = true;
+        full.occurrences = Some(3);
+        full.occurrence_lines = vec![0, 4, 9];
+        full.suppressed_by = Some(SuppressionRule::mute(
+            "sip-401",
+            Some(PathBuf::from("jabber.log")),
+        ));
+        let properties = variants[0]["properties"].as_object().unwrap();
+        let full_keys = keys(full.to_value());
+        assert_eq!(full_keys.len(), properties.len());
+        assert!(full_keys.iter().all(|key| properties.contains_key(key)));
+
+        // Every value has the type the schema describes
+        for value in [
+            payload().to_value(),
+            full.to_value(),
+            signature().to_value(),
+            scenario().to_value(),
+        ] {
+            assert!(conforms(&value, &schema, &schema), "{} rejected", value);
+        }
+        for (key, wrong) in [
+            ("kind", serde_json::json!("signature")),
+            ("version", serde_json::json!(2)),
+            ("patternId", serde_json::json!(401)),
+            ("severity", serde_json::json!("fatal")),
+            ("parameters", serde_json::json!({ "user": 1 })),
+            ("aggregated", serde_json::json!("true")),
+            ("occurrences", serde_json::json!(0)),
+            ("occurrenceLines", serde_json::json!(["4"])),
+            (
+                "suppressedBy",
+                serde_json::json!({ "parameters": [], "line": "3" }),
+            ),
+            ("pattern_id", serde_json::json!("sip-401")),
+        ] {
+            let mut value = full.to_value();
+            value[key] = wrong;
+            assert!(!conforms(&value, &schema, &schema), "{} accepted", key);
+        }
+        let mut value = scenario().to_value();
+        value["confidence"] = serde_json::json!(1.5);
+        assert!(!conforms(&value, &schema, &schema));
+    }
+
+    #[test]
+    fn test_payload_kinds() {
+        let value = payload().to_value();
+        assert_eq!(value["kind"], "detection");
+        assert_eq!(DetectionPayload::from_data(Some(&value)), Some(payload()));
+        assert_eq!(
+            DiagnosticPayload::from_data(Some(&value)).unwrap().unwrap(),
+            DiagnosticPayload::Detection(payload())
+        );
+
+        let value = signature().to_value();
+        assert_eq!(value["kind"], "signature");
+        assert_eq!(DetectionPayload::from_data(Some(&value)), None);
+        assert_eq!(
+            DiagnosticPayload::from_data(Some(&value)).unwrap().unwrap(),
+            signature()
+        );
+
+        // Data that is not a payload is an error, no data is not
+        assert!(DiagnosticPayload::from_data(Some(&serde_json::json!({})))
+            .unwrap()
+            .is_err());
+        assert!(DiagnosticPayload::from_data(None).is_none());
+    }
+
+    #[test]
+    fn test_checked_in_schema_is_current() {
+        let checked_in: serde_json::Value =
+            serde_json::from_str(include_str!("../schema/diagnostic-payload.schema.json")).unwrap();
+        assert_eq!(
+            checked_in,
+            json_schema(),
+            "regenerate with `log-scout-lsp-server schema > schema/diagnostic-payload.schema.json`"
+        );
+    }
+}
@@ -0,0 +1,515 @@
+//! Analysis Pipeline
+//!
+//! Runs the analysis of one document as an ordered list of stages sharing a
+//! typed context: pattern matching, severity policy, suppression, signature
+//! detection, process correlation, scenario analysis, deduplication and
+//! remediation. Stages can be added for a product, tested in isolation, and
+//! disabled by name through `Settings::disabled_stages`.
+
+use crate::config::Settings;
+use crate::dedup::{self, DedupOptions};
+use crate::pattern_engine::{Detection, PatternEngine};
+use crate::process::{ProcessEngine, ProcessInstance};
+use crate::remediation::{self, RemediationAction};
+use crate::scenario::{ScenarioEngine, ScenarioMatch};
+use crate::severity_policy;
+use crate::signature::{SignatureEngine, SignatureMatch};
+use crate::suppression::{Suppressed, Suppressions};
+use std::path::Path;
+
+/// Number of lines between two progress reports of the matching stage
+pub const PROGRESS_INTERVAL: usize = 1_000;
+
+/// Stages relating detections on different lines
+///
+/// A followed log runs them over all its detections instead of each batch
+/// of new lines, so groups spread over several reads are found.
+pub const CORRELATION_STAGES: [&str; 3] = ["signatures", "processes", "scenarios"];
+
+/// State shared by the stages while analyzing one document
+pub struct AnalysisContext<'a> {
+    /// Full text of the document
+    pub text: &'a str,
+
+    /// Engine with the compiled patterns
+    pub engine: &'a PatternEngine,
+
+    pub detections: Vec<Detection>,
+    pub signatures: Vec<SignatureMatch>,
+    pub processes: Vec<ProcessInstance>,
+    pub scenarios: Vec<ScenarioMatch>,
+    pub remediations: Vec<RemediationAction>,
+
+    /// Detections muted by the suppression rules
+    pub suppressed: Vec<Suppressed>,
+
+    /// Settings of the document, if any
+    pub settings: Option<&'a Settings>,
+
+    /// Suppression rules and the path of the document they are matched against
+    pub suppressions: Option<&'a Suppressions>,
+    pub path: Option<&'a Path>,
+
+    /// Detections were matched before the pipeline ran
+    prematched: bool,
+
+    /// Called with the number of lines processed so far
+    progress: Option<&'a (dyn Fn(usize) + Sync)>,
+}
+
+impl<'a> AnalysisContext<'a> {
+    /// Create an empty context for a document
+    pub fn new(text: &'a str, engine: &'a PatternEngine) -> Self {
+        Self {
+            text,
+            engine,
+            detections: Vec::new(),
+            signatures: Vec::new(),
+            processes: Vec::new(),
+            scenarios: Vec::new(),
+            remediations: Vec::new(),
+            suppressed: Vec::new(),
+            settings: None,
+            suppressions: None,
+            path: None,
+            prematched: false,
+            progress: None,
+        }
+    }
+
+    /// Start from detections matched outside the pipeline, such as the new
+    /// lines of a followed log; the matching stage leaves them as they are
+    pub fn with_detections(mut self, detections: Vec<Detection>) -> Self {
+        self.detections = detections;
+        self.prematched = true;
+        self
+    }
+
+    /// Report progress of long-running stages to `progress`
+    pub fn with_progress(mut self, progress: &'a (dyn Fn(usize) + Sync)) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Analyze with the settings of the document
+    ///
+    /// Stages listed in their `disabled_stages` are skipped, and stages
+    /// such as the severity policy read their options from them.
+    pub fn with_settings(mut self, settings: &'a Settings) -> Self {
+        self.settings = Some(settings);
+        self
+    }
+
+    /// Mute detections matching `suppressions` in the log at `path`
+    pub fn with_suppressions(
+        mut self,
+        suppressions: &'a Suppressions,
+        path: Option<&'a Path>,
+    ) -> Self {
+        self.suppressions = Some(suppressions);
+        self.path = path;
+        self
+    }
+
+    /// Report the number of lines processed so far
+    pub fn report_progress(&self, processed: usize) {
+        if let Some(progress) = self.progress {
+            progress(processed);
+        }
+    }
+
+    /// Take the stage outputs, releasing the borrowed text and settings
+    pub fn into_results(self) -> AnalysisResults {
+        AnalysisResults {
+            detections: self.detections,
+            signatures: self.signatures,
+            processes: self.processes,
+            scenarios: self.scenarios,
+            remediations: self.remediations,
+            suppressed: self.suppressed,
+        }
+    }
+}
+
+/// Outputs of the stages, owned
+#[derive(Debug, Default)]
+pub struct AnalysisResults {
+    pub detections: Vec<Detection>,
+    pub signatures: Vec<SignatureMatch>,
+    pub processes: Vec<ProcessInstance>,
+    pub scenarios: Vec<ScenarioMatch>,
+    pub remediations: Vec<RemediationAction>,
+    pub suppressed: Vec<Suppressed>,
+}
+
+/// One step of the analysis
+pub trait Stage: Send + Sync {
+    /// Name used to disable the stage in settings
+    fn name(&self) -> &str;
+
+    /// Read from and add to the shared context
+    fn run(&self, context: &mut AnalysisContext<'_>);
+}
+
+/// Ordered stages run over each document
+#[derive(Default)]
+pub struct AnalysisPipeline {
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl AnalysisPipeline {
+    /// Create a pipeline without stages
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in stages, in order
+    pub fn standard(
+        signatures: SignatureEngine,
+        processes: ProcessEngine,
+        scenarios: ScenarioEngine,
+    ) -> Self {
+        Self::new()
+            .with_stage(MatchingStage)
+            .with_stage(SeverityPolicyStage)
+            .with_stage(SuppressionStage)
+            .with_stage(SignatureStage(signatures))
+            .with_stage(ProcessStage(processes))
+            .with_stage(ScenarioStage(scenarios))
+            .with_stage(DeduplicationStage)
+            .with_stage(RemediationStage)
+    }
+
+    /// Append a stage
+    pub fn with_stage(mut self, stage: impl Stage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Insert a stage right after the stage named `after`, or at the end
+    /// if there is no such stage
+    pub fn insert_after(&mut self, after: &str, stage: impl Stage + 'static) {
+        let index = self
+            .stages
+            .iter()
+            .position(|s| s.name() == after)
+            .map_or(self.stages.len(), |i| i + 1);
+        self.stages.insert(index, Box::new(stage));
+    }
+
+    /// Names of the stages, in order
+    pub fn stage_names(&self) -> Vec<&str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    /// Whether a stage runs for a document with `settings`
+    ///
+    /// A stage is skipped when listed in the document's `disabled_stages`.
+    pub fn runs(&self, name: &str, settings: Option<&Settings>) -> bool {
+        !settings.is_some_and(|settings| {
+            settings
+                .disabled_stages
+                .iter()
+                .any(|disabled| disabled == name)
+        })
+    }
+
+    /// Run the stages that are enabled for the context's document
+    pub fn run(&self, context: &mut AnalysisContext<'_>) {
+        self.run_selected(context, |_| true);
+    }
+
+    /// Run the enabled stages whose name `select` accepts
+    pub fn run_selected(&self, context: &mut AnalysisContext<'_>, select: impl Fn(&str) -> bool) {
+        for stage in &self.stages {
+            if select(stage.name()) && self.runs(stage.name(), context.settings) {
+                stage.run(context);
+            }
+        }
+    }
+
+    /// Analyze a document from scratch
+    pub fn analyze<'a>(&self, text: &'a str, engine: &'a PatternEngine) -> AnalysisContext<'a> {
+        let mut context = AnalysisContext::new(text, engine);
+        self.run(&mut context);
+        context
+    }
+}
+
+/// Stage 1: match every line against the patterns
+pub struct MatchingStage;
+
+impl Stage for MatchingStage {
+    fn name(&self) -> &str {
+        "matching"
+    }
+
+    fn run(&self, context: &mut AnalysisContext<'_>) {
+        if context.prematched {
+            return;
+        }
+
+        for (line_num, line) in context.text.lines().enumerate() {
+            let detections = context.engine.process_line(line, line_num);
+            context.detections.extend(detections);
+
+            if (line_num + 1) % PROGRESS_INTERVAL == 0 {
+                context.report_progress(line_num + 1);
+            }
+        }
+
+        tracing::info!(
+            "Found {} detections (before deduplication)",
+            context.detections.len()
+        );
+    }
+}
+
+/// Stage 2: remap severities according to the user's severity policy
+pub struct SeverityPolicyStage;
+
+impl Stage for SeverityPolicyStage {
+    fn name(&self) -> &str {
+        "severity_policy"
+    }
+
+    fn run(&self, context: &mut AnalysisContext<'_>) {
+        if let Some(settings) = context.settings {
+            severity_policy::apply_policy(&settings.severity_policy, &mut context.detections);
+        }
+    }
+}
+
+/// Stage 3: move detections muted by `.logscout-ignore` out of the analysis
+pub struct SuppressionStage;
+
+impl Stage for SuppressionStage {
+    fn name(&self) -> &str {
+        "suppression"
+    }
+
+    fn run(&self, context: &mut AnalysisContext<'_>) {
+        let Some(suppressions) = context.suppressions else {
+            return;
+        };
+        let mode = context
+            .settings
+            .map(|settings| settings.suppression_mode)
+            .unwrap_or_default();
+        let today = chrono::Local::now().date_naive();
+
+        context.suppressed = suppressions.apply(&mut context.detections, context.path, mode, today);
+        if !context.suppressed.is_empty() {
+            tracing::info!("Suppressed {} detections", context.suppressed.len());
+        }
+    }
+}
+
+/// Stage 4: group co-occurring patterns of a category into signatures
+pub struct SignatureStage(pub SignatureEngine);
+
+impl Stage for SignatureStage {
+    fn name(&self) -> &str {
+        "signatures"
+    }
+
+    fn run(&self, context: &mut AnalysisContext<'_>) {
+        context.signatures = self.0.detect(&context.detections);
+        if !context.signatures.is_empty() {
+            tracing::info!("Found {} signature matches", context.signatures.len());
+        }
+    }
+}
+
+/// Stage 5: reconstruct functional flows
+pub struct ProcessStage(pub ProcessEngine);
+
+impl Stage for ProcessStage {
+    fn name(&self) -> &str {
+        "processes"
+    }
+
+    fn run(&self, context: &mut AnalysisContext<'_>) {
+        context.processes = self.0.correlate(&context.detections);
+        if !context.processes.is_empty() {
+            tracing::info!("Found {} flow instances", context.processes.len());
+        }
+    }
+}
+
+/// Stage 6: chain signatures and flow outcomes across categories
+pub struct ScenarioStage(pub ScenarioEngine);
+
+impl Stage for ScenarioStage {
+    fn name(&self) -> &str {
+        "scenarios"
+    }
+
+    fn run(&self, context: &mut AnalysisContext<'_>) {
+        context.scenarios = self.0.analyze(&context.signatures, &context.processes);
+        if !context.scenarios.is_empty() {
+            tracing::info!("Matched {} scenarios", context.scenarios.len());
+        }
+    }
+}
+
+/// Stage 7: keep one pattern match per overlapping span, using the
+/// precedence and product from the settings
+pub struct DeduplicationStage;
+
+impl Stage for DeduplicationStage {
+    fn name(&self) -> &str {
+        "deduplication"
+    }
+
+    fn run(&self, context: &mut AnalysisContext<'_>) {
+        let options = match context.settings {
+            Some(settings) => DedupOptions {
+                precedence: &settings.dedup_precedence,
+                product: settings.product.as_deref(),
+            },
+            None => DedupOptions::default(),
+        };
+        let detections = std::mem::take(&mut context.detections);
+        context.detections = dedup::deduplicate(detections, &options);
+
+        tracing::info!(
+            "Found {} unique detections (after deduplication)",
+            context.detections.len()
+        );
+    }
+}
+
+/// Stage 8: build the ranked action plan
+pub struct RemediationStage;
+
+impl Stage for RemediationStage {
+    fn name(&self) -> &str {
+        "remediation"
+    }
+
+    fn run(&self, context: &mut AnalysisContext<'_>) {
+        context.remediations =
+            remediation::recommend(&context.detections, &context.signatures, &context.scenarios);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{Pattern, Severity};
+    use crate::severity_policy::SeverityRule;
+
+    fn engine() -> PatternEngine {
+        PatternEngine::new(
+            vec![Pattern {
+                name: "Failure".to_string(),
+                action: Some("Retry".to_string()),
+                ..Pattern::test("failed", "failed")
+            }],
+            0.7,
+            10,
+        )
+        .unwrap()
+    }
+
+    /// Drops detections on even lines
+    struct OddLinesOnly;
+
+    impl Stage for OddLinesOnly {
+        fn name(&self) -> &str {
+            "odd-lines"
+        }
+
+        fn run(&self, context: &mut AnalysisContext<'_>) {
+            context.detections.retain(|d| d.line_number % 2 == 1);
+        }
+    }
+
+    fn standard() -> AnalysisPipeline {
+        AnalysisPipeline::standard(
+            SignatureEngine::default(),
+            ProcessEngine::default(),
+            ScenarioEngine::default(),
+        )
+    }
+
+    #[test]
+    fn test_standard_pipeline() {
+        let engine = engine();
+        let context = standard().analyze("ok\nlogin failed\nfailed again", &engine);
+
+        assert_eq!(context.detections.len(), 2);
+        assert_eq!(context.remediations.len(), 1);
+        assert_eq!(context.remediations[0].occurrences, 2);
+    }
+
+    #[test]
+    fn test_custom_stage_and_disabled_stages() {
+        let engine = engine();
+        let mut pipeline = standard();
+        pipeline.insert_after("matching", OddLinesOnly);
+        assert_eq!(
+            pipeline.stage_names(),
+            vec![
+                "matching",
+                "odd-lines",
+                "severity_policy",
+                "suppression",
+                "signatures",
+                "processes",
+                "scenarios",
+                "deduplication",
+                "remediation"
+            ]
+        );
+
+        let context = pipeline.analyze("failed\nfailed\nfailed", &engine);
+        assert_eq!(context.detections.len(), 1);
+
+        let settings = Settings {
+            disabled_stages: vec!["remediation".to_string()],
+            ..Settings::default()
+        };
+        let mut context =
+            AnalysisContext::new("failed\nfailed\nfailed", &engine).with_settings(&settings);
+        pipeline.run(&mut context);
+        assert!(context.remediations.is_empty());
+    }
+
+    #[test]
+    fn test_prematched_detections() {
+        let engine = engine();
+        let detections = engine.process_line("login failed", 7);
+        let mut context =
+            AnalysisContext::new("failed\nfailed", &engine).with_detections(detections);
+        standard().run(&mut context);
+
+        let results = context.into_results();
+        assert_eq!(results.detections.len(), 1);
+        assert_eq!(results.detections[0].line_number, 7);
+        assert_eq!(results.remediations[0].occurrences, 1);
+    }
+
+    #[test]
+    fn test_settings_of_the_document() {
+        let engine = engine();
+        let settings = Settings {
+            disabled_stages: vec!["deduplication".to_string()],
+            severity_policy: vec![SeverityRule {
+                pattern_id: Some("failed".to_string()),
+                category: None,
+                product: None,
+                from: None,
+                severity: Severity::Warning,
+            }],
+            ..Settings::default()
+        };
+
+        let mut context = AnalysisContext::new("login failed", &engine).with_settings(&settings);
+        standard().run(&mut context);
+
+        assert_eq!(context.detections[0].final_severity, Severity::Warning);
+        assert_eq!(context.remediations[0].severity, Severity::Warning);
+    }
+}
@@ -0,0 +1,273 @@
+//! Position Encoding
+//!
+//! Columns are byte offsets throughout the analysis, since they come from
+//! regex matches. LSP positions count UTF-16 code units unless client and
+//! server agree on another `positionEncoding` at initialize, so columns are
+//! converted at the protocol boundary. Only lines with non-ASCII text need
+//! converting; everywhere else all encodings agree.
+
+use crate::pattern_engine::Detection;
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{Position, PositionEncodingKind, Range};
+
+/// Unit of the `character` of LSP positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// Bytes, the unit of the analysis
+    Utf8,
+
+    /// UTF-16 code units, the LSP default
+    #[default]
+    Utf16,
+
+    /// Unicode scalar values
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Pick an encoding among those offered by the client
+    ///
+    /// UTF-8 needs no conversion and UTF-32 is cheaper than UTF-16, which
+    /// every client supports and is used when the client offers nothing.
+    pub fn negotiate(offered: Option<&[PositionEncodingKind]>) -> Self {
+        let offered = offered.unwrap_or_default();
+        [Self::Utf8, Self::Utf32]
+            .into_iter()
+            .find(|encoding| offered.contains(&encoding.kind()))
+            .unwrap_or(Self::Utf16)
+    }
+
+    pub fn kind(self) -> PositionEncodingKind {
+        match self {
+            Self::Utf8 => PositionEncodingKind::UTF8,
+            Self::Utf16 => PositionEncodingKind::UTF16,
+            Self::Utf32 => PositionEncodingKind::UTF32,
+        }
+    }
+
+    /// Column of a byte offset in `line`
+    ///
+    /// Offsets past the end or inside a character are clamped to the
+    /// preceding character boundary.
+    pub fn column(self, line: &str, byte: usize) -> u32 {
+        let mut byte = byte.min(line.len());
+        while !line.is_char_boundary(byte) {
+            byte -= 1;
+        }
+        let prefix = &line[..byte];
+        let column = match self {
+            Self::Utf8 => prefix.len(),
+            Self::Utf16 => prefix.encode_utf16().count(),
+            Self::Utf32 => prefix.chars().count(),
+        };
+        column as u32
+    }
+
+    /// Byte offset of a column in `line`
+    ///
+    /// Columns past the end map to the end of the line, and columns inside
+    /// a character to the next character boundary.
+    pub fn byte_offset(self, line: &str, column: u32) -> usize {
+        let column = column as usize;
+        let mut units = 0;
+        for (offset, c) in line.char_indices() {
+            if units >= column {
+                return offset;
+            }
+            units += match self {
+                Self::Utf8 => c.len_utf8(),
+                Self::Utf16 => c.len_utf16(),
+                Self::Utf32 => 1,
+            };
+        }
+        line.len()
+    }
+
+    /// Byte offset of a position in a whole text, e.g. of an edit
+    ///
+    /// Positions past the end of a line or of the text are clamped.
+    pub fn text_offset(self, text: &str, position: Position) -> usize {
+        let mut start = 0;
+        for _ in 0..position.line {
+            match text[start..].find('\n') {
+                Some(newline) => start += newline + 1,
+                None => return text.len(),
+            }
+        }
+        let end = text[start..]
+            .find('\n')
+            .map_or(text.len(), |newline| start + newline);
+        let line = text[start..end].trim_end_matches('\r');
+        start + self.byte_offset(line, position.character)
+    }
+}
+
+/// Converts positions on the non-ASCII lines of a document
+///
+/// Lines it does not know are passed through unchanged, which is exact for
+/// ASCII lines.
+#[derive(Debug, Clone, Default)]
+pub struct LineEncoder {
+    encoding: PositionEncoding,
+    lines: HashMap<usize, String>,
+}
+
+impl LineEncoder {
+    pub fn new(encoding: PositionEncoding) -> Self {
+        Self {
+            encoding,
+            lines: HashMap::new(),
+        }
+    }
+
+    /// Learn the non-ASCII lines of a document's text
+    pub fn with_text(mut self, text: &str) -> Self {
+        if self.encoding != PositionEncoding::Utf8 {
+            for (number, line) in text.lines().enumerate() {
+                if !line.is_ascii() {
+                    self.lines.entry(number).or_insert_with(|| line.to_string());
+                }
+            }
+        }
+        self
+    }
+
+    /// Learn the non-ASCII lines of detections' context, which starts on
+    /// the line of the detection
+    pub fn with_detections(mut self, detections: &[Detection]) -> Self {
+        if self.encoding != PositionEncoding::Utf8 {
+            for detection in detections {
+                for (offset, line) in detection.context.iter().enumerate() {
+                    if !line.is_ascii() {
+                        self.lines
+                            .entry(detection.line_number + offset)
+                            .or_insert_with(|| line.clone());
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    pub fn encoding(&self) -> PositionEncoding {
+        self.encoding
+    }
+
+    /// Position with a byte column converted to the encoding
+    pub fn position(&self, position: Position) -> Position {
+        match self.lines.get(&(position.line as usize)) {
+            Some(line) => Position {
+                line: position.line,
+                character: self.encoding.column(line, position.character as usize),
+            },
+            None => position,
+        }
+    }
+
+    pub fn range(&self, range: Range) -> Range {
+        Range {
+            start: self.position(range.start),
+            end: self.position(range.end),
+        }
+    }
+
+    /// Byte column range on a line converted to the encoding
+    pub fn column_range(&self, line: usize, (start, end): (usize, usize)) -> (usize, usize) {
+        match self.lines.get(&line) {
+            Some(text) => (
+                self.encoding.column(text, start) as usize,
+                self.encoding.column(text, end) as usize,
+            ),
+            None => (start, end),
+        }
+    }
+
+    /// Position with a column in the encoding converted to bytes
+    pub fn byte_position(&self, position: Position) -> Position {
+        match self.lines.get(&(position.line as usize)) {
+            Some(line) => Position {
+                line: position.line,
+                character: self.encoding.byte_offset(line, position.character) as u32,
+            },
+            None => position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+
+    #[test]
+    fn test_negotiate() {
+        use PositionEncodingKind as Kind;
+        assert_eq!(PositionEncoding::negotiate(None), PositionEncoding::Utf16);
+        assert_eq!(
+            PositionEncoding::negotiate(Some(&[Kind::UTF16, Kind::UTF32])),
+            PositionEncoding::Utf32
+        );
+        assert_eq!(
+            PositionEncoding::negotiate(Some(&[Kind::UTF16, Kind::UTF8])),
+            PositionEncoding::Utf8
+        );
+    }
+
+    #[test]
+    fn test_columns() {
+        // "é" is 2 bytes and 1 UTF-16 unit, "𝄞" 4 bytes and 2 units
+        let line = "user=José 𝄞 failed";
+        let failed = line.find("failed").unwrap();
+        assert_eq!(failed, 16);
+
+        assert_eq!(PositionEncoding::Utf8.column(line, failed), 16);
+        assert_eq!(PositionEncoding::Utf16.column(line, failed), 13);
+        assert_eq!(PositionEncoding::Utf32.column(line, failed), 12);
+        for encoding in [
+            PositionEncoding::Utf8,
+            PositionEncoding::Utf16,
+            PositionEncoding::Utf32,
+        ] {
+            let column = encoding.column(line, failed);
+            assert_eq!(encoding.byte_offset(line, column), failed);
+        }
+
+        // Inside a character: back to its start on the way out, forward to
+        // the next one on the way in
+        assert_eq!(PositionEncoding::Utf16.column(line, 9), 8);
+        assert_eq!(PositionEncoding::Utf16.byte_offset(line, 11), 15);
+        assert_eq!(PositionEncoding::Utf16.byte_offset(line, 99), line.len());
+
+        let text = format!("ok\r\n{}\n", line);
+        let offset = |line, character| {
+            PositionEncoding::Utf16.text_offset(&text, Position::new(line, character))
+        };
+        assert_eq!(offset(0, 9), 2);
+        assert_eq!(offset(1, 13), 4 + failed);
+        assert_eq!(offset(5, 0), text.len());
+
+        let encoder = LineEncoder::new(PositionEncoding::Utf16).with_text("ascii\n");
+        let position = Position::new(0, 3);
+        assert_eq!(encoder.position(position), position);
+        let encoder = LineEncoder::new(PositionEncoding::Utf16).with_text(&format!("ok\n{}", line));
+        assert_eq!(
+            encoder.range(Range::new(Position::new(1, 16), Position::new(1, 22))),
+            Range::new(Position::new(1, 13), Position::new(1, 19))
+        );
+        assert_eq!(
+            encoder.byte_position(Position::new(1, 13)),
+            Position::new(1, 16)
+        );
+    }
+
+    #[test]
+    fn test_multiline_detection_lines() {
+        let mut detections = detect_lines(vec![Pattern::test("crash", "Exception")], "\nException");
+        detections[0].context = vec!["Exception".to_string(), "  at café".to_string()];
+
+        let encoder = LineEncoder::new(PositionEncoding::Utf16).with_detections(&detections);
+        assert_eq!(encoder.position(Position::new(2, 10)), Position::new(2, 9));
+        assert_eq!(encoder.position(Position::new(1, 9)), Position::new(1, 9));
+        assert_eq!(encoder.column_range(2, (5, 10)), (5, 9));
+    }
+}
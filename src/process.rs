@@ -0,0 +1,432 @@
+//! Process Correlation
+//!
+//! Reconstructs functional flows (login, call setup, presence subscription)
+//! from detections. A process definition lists ordered steps, each matched
+//! by a pattern, and the parameter that ties the steps of one flow together
+//! (a call ID, a request ID). Every flow instance is reported with its
+//! start, end, duration and outcome.
+
+use crate::pattern_engine::Detection;
+use crate::timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower_lsp::lsp_types::Url;
+
+/// Custom request listing the flow instances of a document
+pub const PROCESSES_METHOD: &str = "logScout/processes";
+
+/// Definition of a process, as written in a pattern pack's `processes:` list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessDefinition {
+    /// Unique identifier
+    pub id: String,
+
+    /// Human-readable name
+    pub name: String,
+
+    /// Parameter whose value identifies one flow instance
+    pub key: String,
+
+    /// Steps in the order they are expected
+    pub steps: Vec<ProcessStep>,
+
+    /// Patterns that end a running flow as failed
+    #[serde(default)]
+    pub failure_patterns: Vec<String>,
+
+    /// Time after which a running flow without progress is stalled (in milliseconds)
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: i64,
+}
+
+/// One step of a process
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessStep {
+    /// Step name shown to the user (defaults to the pattern ID)
+    #[serde(default)]
+    pub name: String,
+
+    /// ID of the pattern marking this step
+    pub pattern: String,
+}
+
+impl ProcessStep {
+    fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            &self.pattern
+        } else {
+            &self.name
+        }
+    }
+}
+
+fn default_timeout_ms() -> i64 {
+    30_000
+}
+
+/// Outcome of a flow instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessStatus {
+    /// Every step was seen
+    Completed,
+    /// The flow stopped before its last step (timeout or end of log)
+    Stalled,
+    /// A failure pattern ended the flow
+    Failed,
+}
+
+/// A step or failure seen in a flow instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepOccurrence {
+    pub name: String,
+    pub pattern_id: String,
+
+    /// 0-based line of the detection
+    pub line: usize,
+    pub timestamp: Option<String>,
+}
+
+/// One flow instance in a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessInstance {
+    pub process_id: String,
+    pub process_name: String,
+
+    /// Value of the process key shared by the steps
+    pub key: String,
+    pub status: ProcessStatus,
+    pub steps: Vec<StepOccurrence>,
+
+    /// Names of the steps never reached
+    pub missing_steps: Vec<String>,
+
+    /// The detection that failed the flow, if it failed
+    pub failure: Option<StepOccurrence>,
+
+    /// 0-based lines of the first and last detection
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+
+    /// Time from the first to the last detection, when both have timestamps
+    pub duration_ms: Option<i64>,
+}
+
+/// Parameters of the `logScout/processes` request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessesParams {
+    pub uri: Url,
+
+    /// Only return instances of this process
+    #[serde(default)]
+    pub process_id: Option<String>,
+}
+
+/// Finds flow instances among a document's detections
+#[derive(Debug, Default)]
+pub struct ProcessEngine {
+    processes: Vec<Arc<ProcessDefinition>>,
+}
+
+impl ProcessEngine {
+    /// Create an engine; processes without steps are ignored
+    pub fn new(processes: Vec<ProcessDefinition>) -> Self {
+        Self {
+            processes: processes
+                .into_iter()
+                .filter(|process| !process.steps.is_empty())
+                .map(Arc::new)
+                .collect(),
+        }
+    }
+
+    /// Number of loaded processes
+    pub fn len(&self) -> usize {
+        self.processes.len()
+    }
+
+    /// Whether no processes are loaded
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty()
+    }
+
+    /// Reconstruct the flow instances of every process, ordered by start line
+    pub fn correlate(&self, detections: &[Detection]) -> Vec<ProcessInstance> {
+        let mut sorted: Vec<&Detection> = detections.iter().collect();
+        sorted.sort_by_key(|d| (d.line_number, d.column_range.0));
+
+        let mut instances = Vec::new();
+        for process in &self.processes {
+            instances.extend(correlate_process(process, &sorted));
+        }
+
+        instances.sort_by_key(|instance| (instance.start_line, instance.end_line));
+        instances
+    }
+}
+
+/// A flow instance that has not ended yet
+struct RunningFlow {
+    /// Index of the last step reached
+    step: usize,
+    occurrences: Vec<StepOccurrence>,
+    last_millis: Option<i64>,
+}
+
+fn correlate_process(
+    process: &ProcessDefinition,
+    detections: &[&Detection],
+) -> Vec<ProcessInstance> {
+    let mut running: HashMap<String, RunningFlow> = HashMap::new();
+    let mut instances = Vec::new();
+
+    for detection in detections {
+        let Some(key) = detection.field_values.get(&process.key) else {
+            continue;
+        };
+        let pattern_id = &detection.pattern.id;
+        let step = process.steps.iter().position(|s| &s.pattern == pattern_id);
+        let is_failure = process.failure_patterns.contains(pattern_id);
+        if step.is_none() && !is_failure {
+            continue;
+        }
+
+        let millis = detection
+            .timestamp
+            .as_deref()
+            .and_then(timestamp::extract_timestamp)
+            .map(|ts| ts.datetime.and_utc().timestamp_millis());
+
+        // A flow without progress for too long has stalled
+        let timed_out = running
+            .get(key)
+            .is_some_and(|flow| match (flow.last_millis, millis) {
+                (Some(last), Some(now)) => now - last > process.timeout_ms,
+                _ => false,
+            });
+        if timed_out {
+            let flow = running.remove(key).unwrap();
+            instances.push(finish(process, key, flow, ProcessStatus::Stalled, None));
+        }
+
+        let occurrence = |name: &str| StepOccurrence {
+            name: name.to_string(),
+            pattern_id: pattern_id.clone(),
+            line: detection.line_number,
+            timestamp: detection.timestamp.clone(),
+        };
+
+        if is_failure {
+            if let Some(flow) = running.remove(key) {
+                let failure = occurrence(pattern_id);
+                instances.push(finish(
+                    process,
+                    key,
+                    flow,
+                    ProcessStatus::Failed,
+                    Some(failure),
+                ));
+            }
+            continue;
+        }
+
+        let Some(step) = step else {
+            continue;
+        };
+        let name = process.steps[step].display_name();
+
+        match running.get_mut(key) {
+            // Steps may be skipped, but never go backwards
+            Some(flow) if step > flow.step => {
+                flow.step = step;
+                flow.occurrences.push(occurrence(name));
+                flow.last_millis = millis.or(flow.last_millis);
+            }
+            // The first step again starts a new flow for the same key
+            Some(_) if step == 0 => {
+                let flow = running.remove(key).unwrap();
+                instances.push(finish(process, key, flow, ProcessStatus::Stalled, None));
+                running.insert(
+                    key.clone(),
+                    RunningFlow {
+                        step,
+                        occurrences: vec![occurrence(name)],
+                        last_millis: millis,
+                    },
+                );
+            }
+            Some(_) => {}
+            None if step == 0 => {
+                running.insert(
+                    key.clone(),
+                    RunningFlow {
+                        step,
+                        occurrences: vec![occurrence(name)],
+                        last_millis: millis,
+                    },
+                );
+            }
+            None => {}
+        }
+
+        if step == process.steps.len() - 1 {
+            if let Some(flow) = running.remove(key) {
+                instances.push(finish(process, key, flow, ProcessStatus::Completed, None));
+            }
+        }
+    }
+
+    // Flows still running at the end of the log never finished
+    for (key, flow) in running {
+        instances.push(finish(process, &key, flow, ProcessStatus::Stalled, None));
+    }
+
+    instances
+}
+
+fn finish(
+    process: &ProcessDefinition,
+    key: &str,
+    flow: RunningFlow,
+    status: ProcessStatus,
+    failure: Option<StepOccurrence>,
+) -> ProcessInstance {
+    let first = &flow.occurrences[0];
+    let last = failure
+        .as_ref()
+        .unwrap_or_else(|| flow.occurrences.last().unwrap());
+
+    let parse = |ts: &Option<String>| {
+        ts.as_deref()
+            .and_then(timestamp::extract_timestamp)
+            .map(|ts| ts.datetime)
+    };
+    let duration_ms = match (parse(&first.timestamp), parse(&last.timestamp)) {
+        (Some(start), Some(end)) => Some(end.signed_duration_since(start).num_milliseconds()),
+        _ => None,
+    };
+
+    let missing_steps = process
+        .steps
+        .iter()
+        .filter(|step| {
+            !flow
+                .occurrences
+                .iter()
+                .any(|o| o.pattern_id == step.pattern)
+        })
+        .map(|step| step.display_name().to_string())
+        .collect();
+
+    ProcessInstance {
+        process_id: process.id.clone(),
+        process_name: process.name.clone(),
+        key: key.to_string(),
+        status,
+        start_line: first.line,
+        end_line: last.line,
+        start_time: first.timestamp.clone(),
+        end_time: last.timestamp.clone(),
+        duration_ms,
+        missing_steps,
+        failure,
+        steps: flow.occurrences,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, ParameterExtractor, Pattern, Severity};
+
+    fn engine() -> ProcessEngine {
+        let step = |name: &str, pattern: &str| ProcessStep {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+        };
+        ProcessEngine::new(vec![ProcessDefinition {
+            id: "call-setup".to_string(),
+            name: "Call setup".to_string(),
+            key: "callId".to_string(),
+            steps: vec![
+                step("Invite", "invite"),
+                step("Ringing", "ringing"),
+                step("Answered", "answered"),
+            ],
+            failure_patterns: vec!["busy".to_string()],
+            timeout_ms: 10_000,
+        }])
+    }
+
+    #[test]
+    fn test_flow_outcomes() {
+        let patterns = [
+            ("invite", "INVITE"),
+            ("ringing", "180 Ringing"),
+            ("answered", "200 OK"),
+            ("busy", "486 Busy"),
+        ]
+        .map(|(id, regex)| Pattern {
+            parameter_extractors: vec![ParameterExtractor {
+                name: "callId".to_string(),
+                regex: r"call=(\w+)".to_string(),
+                enum_name: None,
+            }],
+            ..Pattern::test_with(id, regex, Severity::Info, "call")
+        });
+        let detections = detect_lines(
+            patterns.into(),
+            "2024-01-15 10:00:00,000 INVITE call=a1\n\
+             2024-01-15 10:00:00,500 INVITE call=b2\n\
+             2024-01-15 10:00:01,000 180 Ringing call=a1\n\
+             2024-01-15 10:00:01,200 486 Busy call=b2\n\
+             2024-01-15 10:00:03,000 200 OK call=a1\n\
+             2024-01-15 10:00:04,000 INVITE call=c3",
+        );
+        let instances = engine().correlate(&detections);
+        assert_eq!(instances.len(), 3);
+
+        let a1 = &instances[0];
+        assert_eq!(a1.key, "a1");
+        assert_eq!(a1.status, ProcessStatus::Completed);
+        assert_eq!((a1.start_line, a1.end_line), (0, 4));
+        assert_eq!(a1.duration_ms, Some(3_000));
+        assert_eq!(a1.steps.len(), 3);
+
+        let b2 = &instances[1];
+        assert_eq!(b2.status, ProcessStatus::Failed);
+        assert_eq!(b2.failure.as_ref().unwrap().line, 3);
+        assert_eq!(b2.missing_steps, vec!["Ringing", "Answered"]);
+        assert_eq!(b2.duration_ms, Some(700));
+
+        assert_eq!(instances[2].status, ProcessStatus::Stalled);
+    }
+
+    #[test]
+    fn test_flow_stalls_after_timeout() {
+        let patterns =
+            [("invite", "INVITE"), ("ringing", "180 Ringing")].map(|(id, regex)| Pattern {
+                parameter_extractors: vec![ParameterExtractor {
+                    name: "callId".to_string(),
+                    regex: r"call=(\w+)".to_string(),
+                    enum_name: None,
+                }],
+                ..Pattern::test_with(id, regex, Severity::Info, "call")
+            });
+        let detections = detect_lines(
+            patterns.into(),
+            "2024-01-15 10:00:00,000 INVITE call=a1\n\
+             2024-01-15 10:01:00,000 180 Ringing call=a1",
+        );
+        let instances = engine().correlate(&detections);
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].status, ProcessStatus::Stalled);
+        assert_eq!(instances[0].end_line, 0);
+    }
+}
@@ -0,0 +1,162 @@
+//! Value References
+//!
+//! Finds every line in the open logs that contains a value, typically a
+//! correlation key (call ID, session ID, device ID) extracted by a pattern,
+//! so a single call can be followed across several logs.
+
+use crate::pattern_engine::{Detection, ParameterSpan};
+use crate::payload::DetectionPayload;
+use serde::Serialize;
+use tower_lsp::lsp_types::{Location, Position, Range, Url};
+
+/// Custom request returning references annotated with the detections they belong to
+pub const REFERENCES_METHOD: &str = "logScout/references";
+
+/// One occurrence of a value in a log
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueOccurrence {
+    pub location: Location,
+
+    /// Detections on the same line, if the occurrence is part of any
+    pub detections: Vec<DetectionPayload>,
+}
+
+/// The value under the cursor
+///
+/// An extracted parameter value covering the column wins; otherwise the
+/// token (letters, digits and `-_.@`) around the column is used.
+pub fn value_at(line: &str, column: usize, spans: &[ParameterSpan]) -> Option<String> {
+    if let Some(span) = spans
+        .iter()
+        .find(|span| span.start <= column && column <= span.end && !span.value.is_empty())
+    {
+        return Some(span.value.clone());
+    }
+
+    // A column inside a multi-byte character starts at that character
+    let mut column = column.min(line.len());
+    while !line.is_char_boundary(column) {
+        column -= 1;
+    }
+    let start = line[..column]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_token_char(*c))
+        .last()
+        .map_or(column, |(i, _)| i);
+    let end = line[column..]
+        .char_indices()
+        .find(|(_, c)| !is_token_char(*c))
+        .map_or(line.len(), |(i, _)| column + i);
+
+    let token = line.get(start..end)?.trim_matches('.');
+    (!token.is_empty()).then(|| token.to_string())
+}
+
+/// Find all whole-token occurrences of `value` in a document
+pub fn find_occurrences(
+    uri: &Url,
+    text: &str,
+    value: &str,
+    detections: &[Detection],
+) -> Vec<ValueOccurrence> {
+    if value.is_empty() {
+        return Vec::new();
+    }
+
+    let mut occurrences = Vec::new();
+    for (line_number, line) in text.lines().enumerate() {
+        for (start, _) in line.match_indices(value) {
+            let end = start + value.len();
+            let bounded_before = line[..start].chars().next_back().is_none_or(is_boundary);
+            let bounded_after = line[end..].chars().next().is_none_or(is_boundary);
+            if !bounded_before || !bounded_after {
+                continue;
+            }
+
+            let mut refs: Vec<DetectionPayload> = Vec::new();
+            for detection in detections.iter().filter(|d| d.line_number == line_number) {
+                let payload = DetectionPayload::new(detection);
+                if !refs.contains(&payload) {
+                    refs.push(payload);
+                }
+            }
+
+            occurrences.push(ValueOccurrence {
+                location: Location {
+                    uri: uri.clone(),
+                    range: Range {
+                        start: Position {
+                            line: line_number as u32,
+                            character: start as u32,
+                        },
+                        end: Position {
+                            line: line_number as u32,
+                            character: end as u32,
+                        },
+                    },
+                },
+                detections: refs,
+            });
+        }
+    }
+
+    occurrences
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '@')
+}
+
+/// Characters that may not directly precede or follow a whole-token match
+fn is_boundary(c: char) -> bool {
+    !(c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+
+    #[test]
+    fn test_value_at_prefers_parameter_span() {
+        let line = "call-id=abc-123 state=3";
+        let spans = vec![ParameterSpan {
+            name: "STATE".to_string(),
+            value: "3".to_string(),
+            start: 22,
+            end: 23,
+        }];
+
+        assert_eq!(value_at(line, 22, &spans).as_deref(), Some("3"));
+        assert_eq!(value_at(line, 10, &spans).as_deref(), Some("abc-123"));
+        assert_eq!(value_at(line, 7, &[]).as_deref(), Some("call-id"));
+        assert_eq!(value_at("end of line.", 12, &[]).as_deref(), Some("line"));
+        assert_eq!(value_at("user=José x", 9, &[]).as_deref(), Some("José"));
+        assert_eq!(value_at("a  b", 2, &[]), None);
+    }
+
+    #[test]
+    fn test_find_occurrences_flags_detections() {
+        let text = "setup call abc-123\nabc-1234 unrelated\ncall failed abc-123";
+        let detections = detect_lines(
+            vec![Pattern {
+                name: "Call failed".to_string(),
+                category: "call".to_string(),
+                ..Pattern::test("call-failed", "call failed")
+            }],
+            text,
+        );
+
+        let uri = Url::parse("file:///webex.log").unwrap();
+        let occurrences = find_occurrences(&uri, text, "abc-123", &detections);
+
+        assert_eq!(occurrences.len(), 2);
+        assert_eq!(occurrences[0].location.range.start.line, 0);
+        assert_eq!(occurrences[0].location.range.start.character, 11);
+        assert!(occurrences[0].detections.is_empty());
+        assert_eq!(occurrences[1].location.range.start.line, 2);
+        assert_eq!(occurrences[1].detections[0].pattern_id, "call-failed");
+    }
+}
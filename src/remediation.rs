@@ -0,0 +1,233 @@
+//! Remediation Planning
+//!
+//! Collects the suggested actions of the patterns, signatures and scenarios
+//! found in a document into one action plan. The same action suggested by
+//! several sources appears once, and the plan is ranked so the most severe
+//! and most frequent problems come first.
+
+use crate::pattern_engine::{Detection, Severity};
+use crate::scenario::ScenarioMatch;
+use crate::signature::SignatureMatch;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Command returning (and showing) the action plan of a document
+pub const REMEDIATION_PLAN_COMMAND: &str = "logScout.remediationPlan";
+
+/// What suggested an action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationSource {
+    /// `pattern`, `signature` or `scenario`
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+}
+
+/// One step of an action plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemediationAction {
+    /// 1-based position in the plan
+    pub rank: usize,
+    pub action: String,
+
+    /// Most severe source of the action
+    pub severity: Severity,
+
+    /// How many detections, signatures and scenarios suggested it
+    pub occurrences: usize,
+    pub sources: Vec<RemediationSource>,
+
+    /// 0-based line of the first occurrence
+    pub first_line: usize,
+}
+
+impl RemediationAction {
+    /// Whether the action was suggested by the pattern, signature or
+    /// scenario with this ID
+    pub fn suggested_by(&self, id: &str) -> bool {
+        self.sources.iter().any(|source| source.id == id)
+    }
+}
+
+/// Build the ranked action plan for a document
+///
+/// Actions are deduplicated ignoring case and whitespace, then ranked by
+/// severity, number of occurrences and first line.
+pub fn recommend(
+    detections: &[Detection],
+    signatures: &[SignatureMatch],
+    scenarios: &[ScenarioMatch],
+) -> Vec<RemediationAction> {
+    let mut plan = PlanBuilder::default();
+
+    for detection in detections {
+        if let Some(action) = &detection.pattern.action {
+            plan.add(
+                action,
+                detection.final_severity,
+                detection.line_number,
+                source("pattern", &detection.pattern.id, &detection.pattern.name),
+            );
+        }
+    }
+
+    for signature in signatures {
+        if let Some(action) = &signature.signature.action {
+            plan.add(
+                action,
+                signature.severity(),
+                signature.anchor().0,
+                source(
+                    "signature",
+                    &signature.signature.id,
+                    &signature.signature.name,
+                ),
+            );
+        }
+    }
+
+    for scenario in scenarios {
+        if let Some(action) = &scenario.scenario.action {
+            let first_line = scenario.events.first().map_or(0, |e| e.line_number);
+            plan.add(
+                action,
+                scenario.severity(),
+                first_line,
+                source("scenario", &scenario.scenario.id, &scenario.scenario.name),
+            );
+        }
+    }
+
+    plan.build()
+}
+
+/// Render a plan as a numbered list
+pub fn format_plan(plan: &[RemediationAction]) -> String {
+    plan.iter()
+        .map(|action| {
+            let sources = action
+                .sources
+                .iter()
+                .map(|source| source.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "{}. {} ({}, {}×; from {})",
+                action.rank,
+                action.action,
+                action.severity.as_str(),
+                action.occurrences,
+                sources
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn source(kind: &str, id: &str, name: &str) -> RemediationSource {
+    RemediationSource {
+        kind: kind.to_string(),
+        id: id.to_string(),
+        name: name.to_string(),
+    }
+}
+
+/// Actions collected so far, keyed by their normalized text
+#[derive(Default)]
+struct PlanBuilder {
+    actions: Vec<RemediationAction>,
+    index: HashMap<String, usize>,
+}
+
+impl PlanBuilder {
+    fn add(&mut self, action: &str, severity: Severity, line: usize, source: RemediationSource) {
+        let action = action.trim();
+        if action.is_empty() {
+            return;
+        }
+
+        let key = action
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        let position = *self.index.entry(key).or_insert_with(|| {
+            self.actions.push(RemediationAction {
+                rank: 0,
+                action: action.to_string(),
+                severity,
+                occurrences: 0,
+                sources: Vec::new(),
+                first_line: line,
+            });
+            self.actions.len() - 1
+        });
+
+        let entry = &mut self.actions[position];
+        entry.occurrences += 1;
+        entry.first_line = entry.first_line.min(line);
+        if severity.rank() > entry.severity.rank() {
+            entry.severity = severity;
+        }
+        if !entry.sources.contains(&source) {
+            entry.sources.push(source);
+        }
+    }
+
+    fn build(mut self) -> Vec<RemediationAction> {
+        self.actions.sort_by(|a, b| {
+            b.severity
+                .rank()
+                .cmp(&a.severity.rank())
+                .then(b.occurrences.cmp(&a.occurrences))
+                .then(a.first_line.cmp(&b.first_line))
+        });
+        for (index, action) in self.actions.iter_mut().enumerate() {
+            action.rank = index + 1;
+        }
+        self.actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+
+    #[test]
+    fn test_plan_dedupes_and_ranks() {
+        let detections = detect_lines(
+            vec![
+                Pattern {
+                    action: Some("Check DNS servers".to_string()),
+                    ..Pattern::test_with("dns", "DNS timeout", Severity::Warning, "dns")
+                },
+                Pattern {
+                    action: Some("check  DNS servers".to_string()),
+                    ..Pattern::test_with("dns-srv", "SRV lookup failed", Severity::Warning, "dns")
+                },
+                Pattern {
+                    action: Some("Renew certificate".to_string()),
+                    ..Pattern::test("cert", "certificate expired")
+                },
+            ],
+            "DNS timeout\nSRV lookup failed\ncertificate expired\nDNS timeout",
+        );
+
+        let plan = recommend(&detections, &[], &[]);
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan[0].action, "Renew certificate");
+        assert_eq!(plan[1].action, "Check DNS servers");
+        assert_eq!(plan[1].occurrences, 3);
+        assert_eq!(plan[1].sources.len(), 2);
+        assert!(plan[1].suggested_by("dns-srv"));
+
+        assert_eq!(
+            format_plan(&plan),
+            "1. Renew certificate (error, 1×; from cert)\n\
+             2. Check DNS servers (warning, 3×; from dns, dns-srv)"
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! Scenario Analysis
+//!
+//! A scenario chains signatures and process outcomes across categories into
+//! one explanation, e.g. "network change, then SSO failure, then every call
+//! failing". A matched scenario gets a confidence score from how much of the
+//! chain was found and whether it happened in the expected order, and is
+//! reported as a single document-level diagnostic.
+
+use crate::pattern_engine::{line_range, Severity};
+use crate::payload::{DiagnosticPayload, ScenarioPayload};
+use crate::process::{ProcessInstance, ProcessStatus};
+use crate::signature::SignatureMatch;
+use crate::timestamp;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString, Url,
+};
+
+/// Factor applied to the confidence when the chain happened out of order
+const OUT_OF_ORDER_FACTOR: f32 = 0.5;
+
+/// Definition of a scenario, as written in a pattern pack's `scenarios:` list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioDefinition {
+    /// Unique identifier
+    pub id: String,
+
+    /// Human-readable name
+    pub name: String,
+
+    /// What the scenario means and what to look at
+    #[serde(default)]
+    pub description: String,
+
+    /// Links of the chain, in the order they are expected
+    pub steps: Vec<ScenarioStep>,
+
+    /// Whether the steps must happen in order for full confidence
+    #[serde(default = "default_true")]
+    pub ordered: bool,
+
+    /// Maximum time between the first and last step (in milliseconds)
+    #[serde(default = "default_window_ms")]
+    pub window_ms: i64,
+
+    /// Minimum confidence (0.0 - 1.0) for the scenario to be reported
+    #[serde(default = "default_min_confidence")]
+    pub min_confidence: f32,
+
+    /// Severity of the scenario diagnostic (defaults to the most severe step)
+    #[serde(default)]
+    pub severity: Option<Severity>,
+
+    /// Suggested action or remediation
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+/// One link of a scenario chain: a signature, or a process outcome
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    /// Text used for this step in the explanation
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// ID of a signature that must match
+    #[serde(default)]
+    pub signature: Option<String>,
+
+    /// ID of a process with an instance ending in `status`
+    #[serde(default)]
+    pub process: Option<String>,
+
+    /// Required process outcome (defaults to failed or stalled)
+    #[serde(default)]
+    pub status: Option<ProcessStatus>,
+
+    /// Contribution of this step to the confidence
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_window_ms() -> i64 {
+    300_000
+}
+
+fn default_min_confidence() -> f32 {
+    0.5
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+/// A signature or flow instance that satisfied a scenario step
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioEvent {
+    pub label: String,
+
+    /// 0-based line and column range the event points at
+    pub line_number: usize,
+    pub column_range: (usize, usize),
+    pub timestamp: Option<String>,
+    pub severity: Severity,
+}
+
+/// One occurrence of a scenario in a document
+#[derive(Debug, Clone)]
+pub struct ScenarioMatch {
+    pub scenario: Arc<ScenarioDefinition>,
+
+    /// Events that satisfied a step, in step order
+    pub events: Vec<ScenarioEvent>,
+
+    /// Labels of the steps that were not found
+    pub missing: Vec<String>,
+
+    /// How well the document matches the scenario (0.0 - 1.0)
+    pub confidence: f32,
+
+    /// Whether the events happened in the expected order
+    pub in_order: bool,
+}
+
+impl ScenarioMatch {
+    /// Severity of the scenario diagnostic
+    pub fn severity(&self) -> Severity {
+        self.scenario.severity.unwrap_or_else(|| {
+            self.events
+                .iter()
+                .map(|event| event.severity)
+                .max_by_key(Severity::rank)
+                .unwrap_or(Severity::Warning)
+        })
+    }
+
+    /// The chain as one sentence: `a (line 3) → b (line 9)`
+    pub fn explanation(&self) -> String {
+        let mut chain = self
+            .events
+            .iter()
+            .map(|event| format!("{} (line {})", event.label, event.line_number + 1))
+            .collect::<Vec<_>>()
+            .join(" → ");
+        if !self.missing.is_empty() {
+            chain.push_str(&format!("; not found: {}", self.missing.join(", ")));
+        }
+        if !self.in_order {
+            chain.push_str("; out of the expected order");
+        }
+        chain
+    }
+
+    /// Build the document-level diagnostic for this scenario in `uri`
+    ///
+    /// It sits on the first line of the document and lists each event as
+    /// related information.
+    pub fn to_diagnostic(&self, uri: &Url) -> Diagnostic {
+        let severity = DiagnosticSeverity::from(self.severity());
+
+        let mut message = format!(
+            "Scenario: {} ({:.0}% confidence): {}",
+            self.scenario.name,
+            self.confidence * 100.0,
+            self.explanation()
+        );
+        if !self.scenario.description.is_empty() {
+            message.push_str(&format!("\n{}", self.scenario.description));
+        }
+
+        let related = self
+            .events
+            .iter()
+            .map(|event| DiagnosticRelatedInformation {
+                location: Location {
+                    uri: uri.clone(),
+                    range: line_range(event.line_number, event.column_range),
+                },
+                message: event.label.clone(),
+            })
+            .collect();
+
+        Diagnostic {
+            range: line_range(0, (0, 0)),
+            severity: Some(severity),
+            code: Some(NumberOrString::String(self.scenario.id.clone())),
+            code_description: None,
+            source: Some("log-scout".to_string()),
+            message,
+            related_information: Some(related),
+            tags: None,
+            data: Some(DiagnosticPayload::Scenario(ScenarioPayload::new(self)).to_value()),
+        }
+    }
+}
+
+/// Matches scenarios against a document's signatures and flow instances
+#[derive(Debug, Default)]
+pub struct ScenarioEngine {
+    scenarios: Vec<Arc<ScenarioDefinition>>,
+}
+
+impl ScenarioEngine {
+    /// Create an engine; scenarios without steps are ignored
+    pub fn new(scenarios: Vec<ScenarioDefinition>) -> Self {
+        Self {
+            scenarios: scenarios
+                .into_iter()
+                .filter(|scenario| !scenario.steps.is_empty())
+                .map(Arc::new)
+                .collect(),
+        }
+    }
+
+    /// Number of loaded scenarios
+    pub fn len(&self) -> usize {
+        self.scenarios.len()
+    }
+
+    /// Whether no scenarios are loaded
+    pub fn is_empty(&self) -> bool {
+        self.scenarios.is_empty()
+    }
+
+    /// Match every scenario, returning those above their minimum confidence
+    pub fn analyze(
+        &self,
+        signatures: &[SignatureMatch],
+        processes: &[ProcessInstance],
+    ) -> Vec<ScenarioMatch> {
+        let mut matches: Vec<ScenarioMatch> = self
+            .scenarios
+            .iter()
+            .filter_map(|scenario| analyze_scenario(scenario, signatures, processes))
+            .collect();
+
+        matches.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+        matches
+    }
+}
+
+/// Find the best chain for one scenario
+///
+/// Each step takes its earliest candidate after the previous step's event;
+/// if there is none, its earliest candidate anywhere (out of order). Events
+/// outside the time window of the first event do not count.
+fn analyze_scenario(
+    scenario: &Arc<ScenarioDefinition>,
+    signatures: &[SignatureMatch],
+    processes: &[ProcessInstance],
+) -> Option<ScenarioMatch> {
+    let mut events: Vec<ScenarioEvent> = Vec::new();
+    let mut missing = Vec::new();
+    let mut matched_weight = 0.0;
+    let mut in_order = true;
+    let mut first_millis: Option<i64> = None;
+
+    for step in &scenario.steps {
+        let label = step_label(step);
+        let mut candidates = step_candidates(step, &label, signatures, processes);
+        candidates.retain(|event| {
+            match (first_millis, event.timestamp.as_deref().and_then(millis)) {
+                (Some(first), Some(time)) => (time - first).abs() <= scenario.window_ms,
+                _ => true,
+            }
+        });
+        candidates.sort_by_key(|event| event.line_number);
+
+        let previous_line = events.last().map(|event| event.line_number);
+        let ordered = candidates
+            .iter()
+            .position(|event| previous_line.is_none_or(|line| event.line_number >= line));
+        let chosen = match ordered {
+            Some(index) => Some(candidates.swap_remove(index)),
+            None if !candidates.is_empty() => {
+                in_order = false;
+                Some(candidates.swap_remove(0))
+            }
+            None => None,
+        };
+
+        match chosen {
+            Some(event) => {
+                if first_millis.is_none() {
+                    first_millis = event.timestamp.as_deref().and_then(millis);
+                }
+                matched_weight += step.weight;
+                events.push(event);
+            }
+            None => missing.push(label),
+        }
+    }
+
+    let total_weight: f32 = scenario.steps.iter().map(|step| step.weight).sum();
+    if events.is_empty() || total_weight <= 0.0 {
+        return None;
+    }
+
+    let mut confidence = (matched_weight / total_weight).clamp(0.0, 1.0);
+    if scenario.ordered && !in_order {
+        confidence *= OUT_OF_ORDER_FACTOR;
+    }
+    if confidence < scenario.min_confidence {
+        return None;
+    }
+
+    Some(ScenarioMatch {
+        scenario: Arc::clone(scenario),
+        events,
+        missing,
+        confidence,
+        in_order,
+    })
+}
+
+/// Signatures and flow instances satisfying a step
+fn step_candidates(
+    step: &ScenarioStep,
+    label: &str,
+    signatures: &[SignatureMatch],
+    processes: &[ProcessInstance],
+) -> Vec<ScenarioEvent> {
+    let mut candidates = Vec::new();
+
+    if let Some(signature_id) = &step.signature {
+        candidates.extend(
+            signatures
+                .iter()
+                .filter(|m| &m.signature.id == signature_id)
+                .map(|m| {
+                    let (line_number, column_range) = m.anchor();
+                    ScenarioEvent {
+                        label: label.to_string(),
+                        line_number,
+                        column_range,
+                        timestamp: m.detections[0].timestamp.clone(),
+                        severity: m.severity(),
+                    }
+                }),
+        );
+    }
+
+    if let Some(process_id) = &step.process {
+        candidates.extend(
+            processes
+                .iter()
+                .filter(|instance| &instance.process_id == process_id)
+                .filter(|instance| match step.status {
+                    Some(status) => instance.status == status,
+                    None => instance.status != ProcessStatus::Completed,
+                })
+                .map(|instance| ScenarioEvent {
+                    label: format!(
+                        "{} {} ({})",
+                        label,
+                        status_text(instance.status),
+                        instance.key
+                    ),
+                    line_number: instance.end_line,
+                    column_range: (0, 0),
+                    timestamp: instance.end_time.clone(),
+                    severity: match instance.status {
+                        ProcessStatus::Failed => Severity::Error,
+                        ProcessStatus::Stalled => Severity::Warning,
+                        ProcessStatus::Completed => Severity::Info,
+                    },
+                }),
+        );
+    }
+
+    candidates
+}
+
+fn step_label(step: &ScenarioStep) -> String {
+    step.label
+        .clone()
+        .or_else(|| step.signature.clone())
+        .or_else(|| step.process.clone())
+        .unwrap_or_default()
+}
+
+fn status_text(status: ProcessStatus) -> &'static str {
+    match status {
+        ProcessStatus::Completed => "completed",
+        ProcessStatus::Stalled => "stalled",
+        ProcessStatus::Failed => "failed",
+    }
+}
+
+fn millis(text: &str) -> Option<i64> {
+    timestamp::extract_timestamp(text).map(|ts| ts.datetime.and_utc().timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(id: &str, status: ProcessStatus, line: usize) -> ProcessInstance {
+        ProcessInstance {
+            process_id: id.to_string(),
+            process_name: id.to_string(),
+            key: format!("k{}", line),
+            status,
+            steps: Vec::new(),
+            missing_steps: Vec::new(),
+            failure: None,
+            start_line: line,
+            end_line: line,
+            start_time: None,
+            end_time: None,
+            duration_ms: None,
+        }
+    }
+
+    fn step(label: &str, process: &str) -> ScenarioStep {
+        ScenarioStep {
+            label: Some(label.to_string()),
+            signature: None,
+            process: Some(process.to_string()),
+            status: None,
+            weight: 1.0,
+        }
+    }
+
+    fn engine() -> ScenarioEngine {
+        ScenarioEngine::new(vec![ScenarioDefinition {
+            id: "network-sso-calls".to_string(),
+            name: "Network change breaks SSO and calls".to_string(),
+            description: String::new(),
+            steps: vec![
+                step("Network change", "network"),
+                step("SSO", "sso-login"),
+                step("Call", "call-setup"),
+            ],
+            ordered: true,
+            window_ms: 300_000,
+            min_confidence: 0.6,
+            severity: None,
+            action: None,
+        }])
+    }
+
+    #[test]
+    fn test_scenario_full_chain() {
+        let processes = vec![
+            instance("network", ProcessStatus::Stalled, 3),
+            instance("sso-login", ProcessStatus::Failed, 10),
+            instance("call-setup", ProcessStatus::Completed, 12),
+            instance("call-setup", ProcessStatus::Failed, 20),
+        ];
+        let matches = engine().analyze(&[], &processes);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].confidence, 1.0);
+        assert_eq!(matches[0].severity(), Severity::Error);
+        assert_eq!(
+            matches[0].explanation(),
+            "Network change stalled (k3) (line 4) → SSO failed (k10) (line 11) → Call failed (k20) (line 21)"
+        );
+
+        let diagnostic = matches[0].to_diagnostic(&Url::parse("file:///a.log").unwrap());
+        assert_eq!(diagnostic.range.start.line, 0);
+        assert_eq!(diagnostic.related_information.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_scenario_confidence() {
+        // Two of three steps: 67%
+        let processes = vec![
+            instance("network", ProcessStatus::Stalled, 3),
+            instance("sso-login", ProcessStatus::Failed, 10),
+        ];
+        let matches = engine().analyze(&[], &processes);
+        assert_eq!(matches.len(), 1);
+        assert!((matches[0].confidence - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(matches[0].missing, vec!["Call"]);
+
+        // Out of order halves the confidence, below the minimum
+        let processes = vec![
+            instance("sso-login", ProcessStatus::Failed, 1),
+            instance("network", ProcessStatus::Stalled, 3),
+            instance("call-setup", ProcessStatus::Failed, 2),
+        ];
+        assert!(engine().analyze(&[], &processes).is_empty());
+    }
+}
@@ -2,23 +2,106 @@
 //!
 //! Implements the Language Server Protocol for log file analysis.
 
-use crate::pattern_engine::{Detection, PatternEngine, Severity};
-use crate::tagscout::{SyncMode, SyncService, SyncServiceConfig};
+use crate::aggregation;
+use crate::archive::{self, ArchiveListing, ArchiveMember, OpenArchiveParams};
+use crate::code_actions::{
+    self, OPEN_DOCUMENTATION_COMMAND, SHOW_OCCURRENCES_COMMAND, SUGGEST_PATTERN_COMMAND,
+    TICKET_MARKDOWN_COMMAND,
+};
+use crate::code_lens;
+use crate::config::{self, ClientSettings, Settings};
+use crate::correlation::{CorrelationIndex, Correlations};
+use crate::follow::{self, LogFollower};
+use crate::inlay_hints;
+use crate::pattern_details::{PatternDetails, PatternDetailsParams};
+use crate::pattern_draft;
+use crate::pattern_engine::{self, Detection, Pattern, PatternEngine, PatternError, Severity};
+use crate::payload::DetectionPayload;
+use crate::pipeline::{AnalysisContext, AnalysisPipeline, AnalysisResults, CORRELATION_STAGES};
+use crate::position::{LineEncoder, PositionEncoding};
+use crate::process::{ProcessEngine, ProcessInstance, ProcessesParams};
+use crate::references::{self, ValueOccurrence};
+use crate::remediation::{self, RemediationAction, REMEDIATION_PLAN_COMMAND};
+use crate::scenario::{ScenarioEngine, ScenarioMatch};
+use crate::signature::{SignatureEngine, SignatureMatch};
+use crate::suppression::{
+    self, ListedRule, Suppressed, SuppressedOccurrence, SuppressionList, SuppressionRule,
+    Suppressions, LIST_SUPPRESSIONS_COMMAND, MUTE_COMMAND,
+};
+use crate::tagscout::{EnumTables, SyncService};
+use crate::virtual_document::{self, VirtualDocument, VirtualDocumentParams};
+use crate::workspace_symbols::{self, MAX_SYMBOLS};
 
 use dashmap::DashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
-use tower_lsp::jsonrpc::Result;
+use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
+/// Maximum correlation links attached to one diagnostic
+const MAX_CORRELATION_LINKS: usize = 20;
+
+/// Detections of a file on disk with the modification time they were computed for
+type CachedDetections = (SystemTime, Arc<Vec<Detection>>);
+
 /// Main LSP server structure
 #[derive(Clone)]
 pub struct LogScoutServer {
     client: Client,
-    pattern_engine: Arc<RwLock<Option<PatternEngine>>>,
+    /// Shared so long analyses can run off the async tasks
+    pattern_engine: Arc<RwLock<Option<Arc<PatternEngine>>>>,
     tagscout_service: Arc<RwLock<Option<SyncService>>>,
+    /// Settings sent by the client, with per-workspace-folder overrides
+    settings: Arc<RwLock<ClientSettings>>,
+    /// Background task refreshing TagScout patterns
+    refresh_task: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
+    /// TagScout enum tables used to decode extracted values
+    enums: Arc<RwLock<EnumTables>>,
+    /// Patterns from local pattern packs (take precedence over TagScout)
+    local_patterns: Arc<RwLock<Vec<Pattern>>>,
+    /// Analysis stages, with the signatures, processes and scenarios
+    /// defined in local pattern packs
+    pipeline: Arc<RwLock<Arc<AnalysisPipeline>>>,
+    /// Workspace folders reported by the client
+    workspace_roots: Arc<RwLock<Vec<PathBuf>>>,
+    /// Rules from the `.logscout-ignore` files of the workspace folders
+    suppressions: Arc<RwLock<Suppressions>>,
+    /// Detections of workspace logs that are not open, keyed by path and
+    /// tagged with the file's modification time
+    workspace_detections: Arc<DashMap<PathBuf, CachedDetections>>,
     documents: Arc<DashMap<Url, String>>,
+    /// Deduplicated detections from the latest analysis of each document
+    detections: Arc<DashMap<Url, Arc<Vec<Detection>>>>,
+    /// Signature matches of the last analysis per document
+    signatures: Arc<DashMap<Url, Arc<Vec<SignatureMatch>>>>,
+    /// Flow instances of the last analysis per document
+    processes: Arc<DashMap<Url, Arc<Vec<ProcessInstance>>>>,
+    /// Scenario matches of the last analysis per document
+    scenarios: Arc<DashMap<Url, Arc<Vec<ScenarioMatch>>>>,
+    /// Ranked action plan of the last analysis per document
+    remediations: Arc<DashMap<Url, Arc<Vec<RemediationAction>>>>,
+    /// Detections muted by suppression rules in the last analysis per document
+    suppressed: Arc<DashMap<Url, Arc<Vec<Suppressed>>>>,
+    /// Click counters for code lenses cycling through occurrences
+    occurrence_cursors: Arc<DashMap<(Url, Option<String>), usize>>,
+    /// Anchor line per document for elapsed-time inlay hints
+    time_anchors: Arc<DashMap<Url, u32>>,
+    /// Background tasks following growing log files on disk
+    followers: Arc<DashMap<Url, tokio::task::JoinHandle<()>>>,
+    /// Correlation keys and timestamps of each analyzed document
+    correlation_indexes: Arc<DashMap<Url, Arc<CorrelationIndex>>>,
+    /// Links between detections of different documents
+    correlations: Arc<RwLock<Correlations>>,
+    /// Virtual documents of the logs inside each opened archive
+    archive_members: Arc<DashMap<Url, Vec<Url>>>,
+    /// Unit of position columns, negotiated at initialize
+    position_encoding: Arc<OnceLock<PositionEncoding>>,
+    /// Whether the client answers `workspace/configuration`
+    configuration_support: Arc<OnceLock<bool>>,
 }
 
 impl LogScoutServer {
@@ -35,9 +118,35 @@ impl LogScoutServer {
 
         Self {
             client,
-            pattern_engine: Arc::new(RwLock::new(pattern_engine)),
+            pattern_engine: Arc::new(RwLock::new(pattern_engine.map(Arc::new))),
             tagscout_service: Arc::new(RwLock::new(None)),
+            settings: Arc::new(RwLock::new(ClientSettings::default())),
+            refresh_task: Arc::new(RwLock::new(None)),
+            enums: Arc::new(RwLock::new(EnumTables::new())),
+            local_patterns: Arc::new(RwLock::new(Vec::new())),
+            pipeline: Arc::new(RwLock::new(Arc::new(AnalysisPipeline::standard(
+                SignatureEngine::default(),
+                ProcessEngine::default(),
+                ScenarioEngine::default(),
+            )))),
+            workspace_roots: Arc::new(RwLock::new(Vec::new())),
+            suppressions: Arc::new(RwLock::new(Suppressions::default())),
+            workspace_detections: Arc::new(DashMap::new()),
             documents: Arc::new(DashMap::new()),
+            detections: Arc::new(DashMap::new()),
+            signatures: Arc::new(DashMap::new()),
+            processes: Arc::new(DashMap::new()),
+            scenarios: Arc::new(DashMap::new()),
+            remediations: Arc::new(DashMap::new()),
+            suppressed: Arc::new(DashMap::new()),
+            occurrence_cursors: Arc::new(DashMap::new()),
+            time_anchors: Arc::new(DashMap::new()),
+            followers: Arc::new(DashMap::new()),
+            correlation_indexes: Arc::new(DashMap::new()),
+            correlations: Arc::new(RwLock::new(Correlations::default())),
+            archive_members: Arc::new(DashMap::new()),
+            position_encoding: Arc::new(OnceLock::new()),
+            configuration_support: Arc::new(OnceLock::new()),
         }
     }
 
@@ -45,11 +154,9 @@ impl LogScoutServer {
     pub async fn initialize_tagscout(&self) -> std::result::Result<(), String> {
         tracing::info!("Initializing TagScout integration");
 
-        // Configure sync service with cache-first mode for offline support
-        let mut config = SyncServiceConfig::default();
-        config.sync_mode = SyncMode::CacheFirst; // Try cache first, fallback to MongoDB
-        config.cache_ttl_seconds = 3600; // 1 hour cache
-        config.auto_refresh_interval = Some(300); // Auto-refresh every 5 minutes
+        // Sync mode, cache TTL and refresh interval come from the client settings
+        let config = self.settings.read().await.global.sync_service_config();
+        let auto_refresh_interval = config.auto_refresh_interval;
 
         let mut service = SyncService::new(config)
             .await
@@ -79,17 +186,23 @@ impl LogScoutServer {
             .map_err(|e| format!("Failed to get patterns: {}", e))?;
 
         if !patterns.is_empty() {
-            let engine = PatternEngine::new(patterns, 0.7, 10)
+            self.install_patterns(patterns)
+                .await
                 .map_err(|e| format!("Failed to create pattern engine: {}", e))?;
-
-            *self.pattern_engine.write().await = Some(engine);
             tracing::info!("Pattern engine updated with TagScout patterns");
         }
 
+        if let Ok(enums) = service.get_enums().await {
+            tracing::info!("Loaded {} TagScout enum tables", enums.len());
+            *self.enums.write().await = enums;
+        }
+
         // Store service
         *self.tagscout_service.write().await = Some(service);
         tracing::info!("TagScout service stored successfully");
 
+        self.restart_auto_refresh(auto_refresh_interval).await;
+
         Ok(())
     }
 
@@ -121,11 +234,15 @@ impl LogScoutServer {
                 .await
                 .map_err(|e| format!("Failed to get patterns: {}", e))?;
 
+            if let Ok(enums) = service.get_enums().await {
+                *self.enums.write().await = enums;
+            }
+
             let count = patterns.len();
             if !patterns.is_empty() {
-                let engine = PatternEngine::new(patterns, 0.7, 10)
+                self.install_patterns(patterns)
+                    .await
                     .map_err(|e| format!("Failed to update engine: {}", e))?;
-                *self.pattern_engine.write().await = Some(engine);
                 Ok(count)
             } else {
                 Ok(0)
@@ -135,6 +252,562 @@ impl LogScoutServer {
         }
     }
 
+    /// (Re)start the background TagScout refresh, if an interval is set
+    async fn restart_auto_refresh(&self, interval_secs: Option<u64>) {
+        let mut task = self.refresh_task.write().await;
+        if let Some(previous) = task.take() {
+            previous.abort();
+        }
+        let Some(interval_secs) = interval_secs else {
+            return;
+        };
+
+        tracing::info!(
+            "Refreshing TagScout patterns every {} seconds",
+            interval_secs
+        );
+        let server = self.clone();
+        *task = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+            // The first tick completes immediately; patterns were just loaded
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = server.refresh_tagscout_patterns().await {
+                    tracing::warn!("Background TagScout refresh failed: {}", e);
+                }
+            }
+        }));
+    }
+
+    /// Apply new client settings
+    ///
+    /// Rebuilds the pattern engine when its parameters changed, recreates
+    /// the TagScout sync service when sync settings changed, and re-analyzes
+    /// open documents.
+    async fn apply_settings(&self, settings: ClientSettings) {
+        let previous = std::mem::replace(&mut *self.settings.write().await, settings.clone());
+
+        if previous.global.engine_changed(&settings.global) {
+            if let Err(e) = self.reload_local_patterns().await {
+                tracing::warn!("Failed to rebuild pattern engine: {}", e);
+            }
+        }
+
+        if previous.global.sync_changed(&settings.global)
+            && self.tagscout_service.read().await.is_some()
+        {
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = server.initialize_tagscout().await {
+                    tracing::warn!("Failed to apply TagScout settings: {}", e);
+                }
+            });
+        }
+
+        self.reanalyze_open_documents().await;
+    }
+
+    /// The `logScout` section of the client's configuration, if the client
+    /// supports `workspace/configuration` and has one
+    async fn fetch_settings(&self) -> Option<serde_json::Value> {
+        if !self.configuration_support.get().copied().unwrap_or(false) {
+            return None;
+        }
+        let item = ConfigurationItem {
+            scope_uri: None,
+            section: Some(config::SETTINGS_SECTION.to_string()),
+        };
+        match self.client.configuration(vec![item]).await {
+            Ok(mut values) => values.pop().filter(|value| !value.is_null()),
+            Err(e) => {
+                tracing::warn!("Failed to fetch the client configuration: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Analyze and publish all open documents again
+    async fn reanalyze_open_documents(&self) {
+        let documents: Vec<(Url, String)> = self
+            .documents
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
+        for (uri, text) in documents {
+            self.analyze_and_publish(&uri, &text).await;
+        }
+    }
+
+    /// Read the `.logscout-ignore` files of the workspace folders
+    async fn reload_suppressions(&self) {
+        let suppressions = Suppressions::load(&self.workspace_roots.read().await);
+        tracing::info!("Loaded {} suppression rules", suppressions.rules().len());
+        *self.suppressions.write().await = suppressions;
+    }
+
+    /// Add a rule muting a pattern in one log (or everywhere) to the ignore
+    /// file of the log's workspace folder
+    async fn mute_pattern(
+        &self,
+        uri: &Url,
+        pattern_id: &str,
+        everywhere: bool,
+    ) -> std::result::Result<PathBuf, String> {
+        let path = uri
+            .to_file_path()
+            .map_err(|_| format!("Not a file: {}", uri))?;
+        let root = self.workspace_root_for(&path).await?;
+
+        let file = (!everywhere).then_some(path);
+        let ignore_file = suppression::append_rule(&root, &SuppressionRule::mute(pattern_id, file))
+            .map_err(|e| e.to_string())?;
+
+        self.reload_suppressions().await;
+        self.reanalyze_open_documents().await;
+        Ok(ignore_file)
+    }
+
+    /// Workspace folder holding a log, else the first workspace folder,
+    /// else the log's own directory
+    async fn workspace_root_for(&self, path: &Path) -> std::result::Result<PathBuf, String> {
+        let roots = self.workspace_roots.read().await;
+        roots
+            .iter()
+            .find(|root| path.starts_with(root))
+            .or(roots.first())
+            .cloned()
+            .or_else(|| path.parent().map(Path::to_path_buf))
+            .ok_or_else(|| format!("No folder for {}", path.display()))
+    }
+
+    /// Open the documentation of a pattern
+    ///
+    /// Links in the TagScout documentation open externally; otherwise local
+    /// patterns open their pack file and TagScout patterns their rendered
+    /// definition.
+    async fn open_documentation(&self, pattern_id: &str) -> std::result::Result<Url, String> {
+        let pattern = self
+            .pattern_engine
+            .read()
+            .await
+            .as_ref()
+            .and_then(|engine| engine.get_pattern(pattern_id))
+            .map(|compiled| compiled.pattern.clone())
+            .ok_or_else(|| format!("Unknown pattern: {}", pattern_id))?;
+
+        let annotation = match self.tagscout_service.read().await.as_ref() {
+            Some(service) => service.get_annotation(pattern_id).await,
+            None => None,
+        };
+        let link = annotation
+            .as_ref()
+            .and_then(|annotation| code_actions::documentation_url(&annotation.documentation));
+
+        let (uri, external, line) = match (link, &pattern.source) {
+            (Some(link), _) => (link, true, None),
+            (None, Some(source)) => (
+                Url::from_file_path(&source.path)
+                    .map_err(|_| format!("Invalid path: {}", source.path.display()))?,
+                false,
+                Some(source.line as u32),
+            ),
+            (None, None) => (virtual_document::pattern_uri(pattern_id), false, None),
+        };
+
+        let params = ShowDocumentParams {
+            uri: uri.clone(),
+            external: Some(external),
+            take_focus: Some(true),
+            selection: line.map(|line| Range {
+                start: Position { line, character: 0 },
+                end: Position { line, character: 0 },
+            }),
+        };
+        self.client
+            .show_document(params)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(uri)
+    }
+
+    /// Locations of a pattern in all analyzed documents, `uri` first
+    fn pattern_occurrences(&self, uri: &Url, pattern_id: &str) -> Vec<Location> {
+        let mut documents: Vec<(Url, Arc<Vec<Detection>>)> = self
+            .detections
+            .iter()
+            .map(|entry| (entry.key().clone(), Arc::clone(entry.value())))
+            .collect();
+        documents.sort_by_key(|(document, _)| (document != uri, document.to_string()));
+
+        let mut locations: Vec<Location> = documents
+            .iter()
+            .flat_map(|(document, detections)| {
+                detections
+                    .iter()
+                    .filter(|detection| detection.pattern.id == pattern_id)
+                    .map(|detection| {
+                        let line = detection.line_number as u32;
+                        let (start, end) = detection.column_range;
+                        Location {
+                            uri: document.clone(),
+                            range: Range {
+                                start: Position {
+                                    line,
+                                    character: start as u32,
+                                },
+                                end: Position {
+                                    line,
+                                    character: end as u32,
+                                },
+                            },
+                        }
+                    })
+            })
+            .collect();
+        self.encode_locations(&mut locations);
+        locations
+    }
+
+    /// Ticket Markdown of the detection of a pattern on a (0-based) line
+    async fn detection_ticket(&self, uri: &Url, line: usize, pattern_id: &str) -> Option<String> {
+        let detections = self.document_detections(uri).await?;
+        let detection = detections
+            .iter()
+            .find(|d| d.line_number == line && d.pattern.id == pattern_id)?;
+
+        let message = pattern_engine::substitute_template(
+            &detection.pattern.annotation,
+            &detection.field_values,
+        );
+        Some(code_actions::ticket_markdown(uri, detection, &message))
+    }
+
+    /// Propose a pattern for a (0-based) line of an open document, or for the
+    /// selected columns of it, add it to the workspace's draft pattern pack
+    /// and reload the local patterns
+    ///
+    /// Returns the proposal, the pack file and the line of the new pattern.
+    async fn suggest_pattern(
+        &self,
+        uri: &Url,
+        line: usize,
+        columns: Option<(usize, usize)>,
+    ) -> std::result::Result<(Pattern, PathBuf, usize), String> {
+        let text = self
+            .documents
+            .get(uri)
+            .and_then(|text| text.lines().nth(line).map(str::to_string))
+            .ok_or_else(|| format!("No line {} in {}", line + 1, uri))?;
+        let text = match columns {
+            Some((start, end)) => {
+                let encoding = self.position_encoding();
+                let start = encoding.byte_offset(&text, start as u32);
+                let end = encoding.byte_offset(&text, end as u32);
+                text.get(start..end)
+                    .ok_or_else(|| format!("Invalid selection on line {}", line + 1))?
+                    .to_string()
+            }
+            None => text,
+        };
+        if text.trim().is_empty() {
+            return Err("The line is empty".to_string());
+        }
+
+        let draft = {
+            let engine_guard = self.pattern_engine.read().await;
+            pattern_draft::draft_from_line(&text, |id| {
+                engine_guard
+                    .as_ref()
+                    .is_some_and(|engine| engine.get_pattern(id).is_some())
+            })
+        };
+
+        let path = uri.to_file_path().unwrap_or_default();
+        let pack = self
+            .workspace_root_for(&path)
+            .await?
+            .join(config::WORKSPACE_PATTERN_DIR)
+            .join(pattern_draft::DRAFT_PACK_FILE);
+        let entry_line = config::append_pattern(&pack, &draft).map_err(|e| e.to_string())?;
+
+        self.reload_local_patterns().await?;
+        self.reanalyze_open_documents().await;
+        Ok((draft, pack, entry_line))
+    }
+
+    /// Suppression rules with the detections they mute in open documents
+    async fn list_suppressions(&self) -> SuppressionList {
+        let today = chrono::Local::now().date_naive();
+        let suppressions = self.suppressions.read().await;
+
+        let rules = suppressions
+            .rules()
+            .iter()
+            .map(|rule| {
+                let occurrences = self
+                    .suppressed
+                    .iter()
+                    .flat_map(|entry| {
+                        let uri = entry.key().clone();
+                        entry
+                            .value()
+                            .iter()
+                            .filter(|suppressed| suppressed.rule == *rule)
+                            .map(|suppressed| SuppressedOccurrence {
+                                uri: uri.clone(),
+                                line: suppressed.detection.line_number,
+                                payload: DetectionPayload::new(&suppressed.detection),
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+
+                ListedRule {
+                    rule: rule.clone(),
+                    expired: rule.is_expired(today),
+                    occurrences,
+                }
+            })
+            .collect();
+
+        SuppressionList { rules }
+    }
+
+    /// Effective settings for a document, honoring workspace folder overrides
+    async fn settings_for(&self, uri: &Url) -> Settings {
+        self.settings.read().await.for_uri(uri).clone()
+    }
+
+    /// Load patterns from local pattern packs and rebuild the engine
+    ///
+    /// Packs are read from `.logscout/patterns` in each workspace folder and
+    /// from `~/.log-scout-analyzer/patterns`.
+    pub async fn reload_local_patterns(&self) -> std::result::Result<usize, String> {
+        let dirs = config::pattern_pack_dirs(&self.workspace_roots.read().await);
+        let local = config::load_pattern_packs(&dirs);
+        let count = local.len();
+        *self.local_patterns.write().await = local;
+
+        let signatures = SignatureEngine::new(config::load_signature_packs(&dirs));
+        tracing::info!(
+            "Loaded {} signatures from local pattern packs",
+            signatures.len()
+        );
+
+        let processes = ProcessEngine::new(config::load_process_packs(&dirs));
+        tracing::info!(
+            "Loaded {} processes from local pattern packs",
+            processes.len()
+        );
+
+        let scenarios = ScenarioEngine::new(config::load_scenario_packs(&dirs));
+        tracing::info!(
+            "Loaded {} scenarios from local pattern packs",
+            scenarios.len()
+        );
+        *self.pipeline.write().await =
+            Arc::new(AnalysisPipeline::standard(signatures, processes, scenarios));
+
+        let tagscout_patterns = match self.tagscout_service.read().await.as_ref() {
+            Some(service) => service.get_patterns().await.unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        if count > 0 || !tagscout_patterns.is_empty() {
+            self.install_patterns(tagscout_patterns)
+                .await
+                .map_err(|e| format!("Failed to update engine: {}", e))?;
+        }
+
+        tracing::info!("Loaded {} patterns from local pattern packs", count);
+        Ok(count)
+    }
+
+    /// Rebuild the pattern engine from local pattern packs and TagScout patterns
+    ///
+    /// Local patterns win when both define the same ID.
+    async fn install_patterns(
+        &self,
+        tagscout_patterns: Vec<Pattern>,
+    ) -> std::result::Result<(), PatternError> {
+        let local = self.local_patterns.read().await.clone();
+        let patterns = config::merge_patterns(vec![local, tagscout_patterns]);
+
+        let engine = {
+            let settings = self.settings.read().await;
+            PatternEngine::new(
+                patterns,
+                settings.global.detection_threshold,
+                settings.global.multiline_context_window,
+            )?
+        };
+        *self.pattern_engine.write().await = Some(Arc::new(engine));
+        self.workspace_detections.clear();
+        Ok(())
+    }
+
+    /// Handle `logScout/virtualDocument` requests
+    pub async fn virtual_document(&self, params: VirtualDocumentParams) -> Result<VirtualDocument> {
+        let unsupported =
+            || Error::invalid_params(format!("Unknown virtual document: {}", params.uri));
+
+        // Logs inside archives: served from the analyzed copy when available
+        if let Some((archive_path, member)) = archive::parse_member_uri(&params.uri) {
+            let content = match self.documents.get(&params.uri).map(|doc| doc.clone()) {
+                Some(content) => content,
+                None => {
+                    let limit =
+                        self.settings.read().await.global.max_file_size_mb as u64 * 1024 * 1024;
+                    tokio::task::spawn_blocking(move || {
+                        archive::read_member(&archive_path, &member, limit)
+                    })
+                    .await
+                    .map_err(|_| Error::internal_error())?
+                    .map_err(|e| Error::invalid_params(e.to_string()))?
+                }
+            };
+
+            return Ok(VirtualDocument {
+                uri: params.uri.clone(),
+                language_id: "log".to_string(),
+                content,
+            });
+        }
+
+        let id = virtual_document::pattern_id_from_uri(&params.uri).ok_or_else(unsupported)?;
+        let pattern = {
+            let engine_guard = self.pattern_engine.read().await;
+            engine_guard
+                .as_ref()
+                .and_then(|engine| engine.get_pattern(&id))
+                .map(|compiled| compiled.pattern.clone())
+                .ok_or_else(unsupported)?
+        };
+
+        let annotation = match self.tagscout_service.read().await.as_ref() {
+            Some(service) => service.get_annotation(&id).await,
+            None => None,
+        };
+
+        Ok(VirtualDocument {
+            uri: params.uri.clone(),
+            language_id: "markdown".to_string(),
+            content: virtual_document::render_pattern(&pattern, annotation.as_ref()),
+        })
+    }
+
+    /// Handle `logScout/openArchive` requests
+    ///
+    /// Analyzes every log in a zip or gzip file in place, publishes
+    /// diagnostics for each member and aggregate diagnostics for the bundle
+    /// on the archive itself.
+    pub async fn open_archive(&self, params: OpenArchiveParams) -> Result<ArchiveListing> {
+        let path = params
+            .uri
+            .to_file_path()
+            .map_err(|_| Error::invalid_params("Only archives on disk can be opened"))?;
+        if !archive::is_archive(&path) {
+            return Err(Error::invalid_params(format!(
+                "Not a zip or gzip file: {}",
+                path.display()
+            )));
+        }
+
+        // Each log is held in memory, so it gets the same size limit as an
+        // opened file
+        let limit = self.settings_for(&params.uri).await.max_file_size_mb as u64 * 1024 * 1024;
+        let archive_path = path.clone();
+        let members =
+            tokio::task::spawn_blocking(move || archive::read_members(&archive_path, limit))
+                .await
+                .map_err(|_| Error::internal_error())?
+                .map_err(|e| Error::invalid_params(e.to_string()))?;
+
+        tracing::info!("Opened {} with {} logs", path.display(), members.len());
+        self.release_archive(&params.uri).await;
+
+        let mut listing = Vec::with_capacity(members.len());
+        let mut member_detections = Vec::with_capacity(members.len());
+        let mut linked = HashSet::new();
+        for (name, text) in members {
+            let uri = archive::member_uri(&path, &name);
+            let (diagnostics, member_linked) =
+                self.analyze_text(&text, &uri, text.lines().count()).await;
+            linked.extend(member_linked);
+            let detections = self.detections.get(&uri).map(|d| Arc::clone(&d));
+
+            listing.push(ArchiveMember {
+                name,
+                uri: uri.clone(),
+                size: text.len() as u64,
+                detections: detections.as_ref().map_or(0, |d| d.len()),
+            });
+            self.documents.insert(uri.clone(), text);
+            self.client
+                .publish_diagnostics(uri.clone(), diagnostics, None)
+                .await;
+
+            if let Some(detections) = detections {
+                member_detections.push((uri, detections));
+            }
+        }
+
+        let members: Vec<(Url, &[Detection])> = member_detections
+            .iter()
+            .map(|(uri, detections)| (uri.clone(), detections.as_slice()))
+            .collect();
+        let mut bundle = archive::bundle_diagnostics(&members);
+        self.encode_diagnostics(&params.uri, &mut bundle);
+        self.client
+            .publish_diagnostics(params.uri.clone(), bundle, None)
+            .await;
+        linked.retain(|uri| !members.iter().any(|(member, _)| member == uri));
+        self.republish_linked_documents(linked).await;
+        self.archive_members.insert(
+            params.uri.clone(),
+            listing.iter().map(|member| member.uri.clone()).collect(),
+        );
+
+        Ok(ArchiveListing {
+            uri: params.uri,
+            members: listing,
+        })
+    }
+
+    /// Drop the documents of the logs of an archive opened earlier
+    async fn release_archive(&self, archive: &Url) {
+        let Some((_, members)) = self.archive_members.remove(archive) else {
+            return;
+        };
+        for uri in members {
+            self.forget_document(&uri).await;
+            self.client.publish_diagnostics(uri, vec![], None).await;
+        }
+    }
+
+    /// Drop everything kept about a document
+    async fn forget_document(&self, uri: &Url) {
+        self.documents.remove(uri);
+        self.detections.remove(uri);
+        self.signatures.remove(uri);
+        self.processes.remove(uri);
+        self.scenarios.remove(uri);
+        self.remediations.remove(uri);
+        self.suppressed.remove(uri);
+        self.occurrence_cursors
+            .retain(|(cursor_uri, _), _| cursor_uri != uri);
+        self.time_anchors.remove(uri);
+        if let Some((_, task)) = self.followers.remove(uri) {
+            task.abort();
+        }
+        self.correlation_indexes.remove(uri);
+
+        // Peers lose their links to the document
+        let linked = self.correlations.write().await.remove(uri);
+        self.republish_linked_documents(linked).await;
+    }
+
     /// Load default pattern set (fallback when TagScout unavailable)
     fn load_default_patterns() -> Option<PatternEngine> {
         // No default patterns - rely entirely on TagScout for meaningful categorization
@@ -143,117 +816,702 @@ impl LogScoutServer {
     }
 
     /// Analyze text and return diagnostics (shared by push and pull)
-    async fn analyze_text(&self, text: &str, _uri: &str, total_lines: usize) -> Vec<Diagnostic> {
+    ///
+    /// Runs the analysis pipeline, keeps its results for the per-document
+    /// features, then converts them to diagnostics. Also returns the
+    /// documents whose correlation links with `uri` changed.
+    async fn analyze_text(
+        &self,
+        text: &str,
+        uri: &Url,
+        total_lines: usize,
+    ) -> (Vec<Diagnostic>, HashSet<Url>) {
         let engine_guard = self.pattern_engine.read().await;
-        if let Some(engine) = engine_guard.as_ref() {
-            let mut all_detections = Vec::new();
-            let mut processed = 0;
-
-            // STAGE 1: Pattern Matching - Analyze each line
-            for (line_num, line) in text.lines().enumerate() {
-                let detections = engine.process_line(line, line_num);
-                all_detections.extend(detections);
-                processed += 1;
-
-                // Report progress every 1000 lines
-                if processed % 1000 == 0 {
-                    let percentage = (processed as f64 / total_lines as f64 * 100.0) as u32;
-                    self.client
-                        .log_message(
-                            MessageType::LOG,
-                            &format!(
-                                "Analyzing: {}% ({}/{} lines)",
-                                percentage, processed, total_lines
-                            ),
-                        )
-                        .await;
+        let Some(engine) = engine_guard.as_ref() else {
+            tracing::warn!("No pattern engine available");
+            return (vec![], HashSet::new());
+        };
+
+        // Report progress every 1000 lines
+        let client = self.client.clone();
+        let progress = move |processed: usize| {
+            let percentage = (processed as f64 / total_lines as f64 * 100.0) as u32;
+            let message = format!(
+                "Analyzing: {}% ({}/{} lines)",
+                percentage, processed, total_lines
+            );
+            let client = client.clone();
+            tokio::spawn(async move { client.log_message(MessageType::LOG, message).await });
+        };
+
+        // Analysis pipeline: matching, severity policy, suppression,
+        // signatures, processes, scenarios, deduplication and remediation
+        let settings = self.settings_for(uri).await;
+        let suppressions = self.suppressions.read().await;
+        let path = uri.to_file_path().ok();
+        let mut context = AnalysisContext::new(text, engine)
+            .with_progress(&progress)
+            .with_settings(&settings)
+            .with_suppressions(&suppressions, path.as_deref());
+        self.pipeline.read().await.run(&mut context);
+
+        self.signatures
+            .insert(uri.clone(), Arc::new(context.signatures));
+        self.processes
+            .insert(uri.clone(), Arc::new(context.processes));
+        self.scenarios
+            .insert(uri.clone(), Arc::new(context.scenarios));
+        self.remediations
+            .insert(uri.clone(), Arc::new(context.remediations));
+        self.suppressed
+            .insert(uri.clone(), Arc::new(context.suppressed));
+
+        // Keep detections for code lenses and other per-document features
+        let all_detections = Arc::new(context.detections);
+        self.detections
+            .insert(uri.clone(), Arc::clone(&all_detections));
+
+        // Diagnostic creation: convert to LSP diagnostics, linking
+        // detections correlated with other documents
+        let linked = self
+            .correlate_document(uri, Arc::clone(&all_detections), &settings)
+            .await;
+        let correlations = self.correlations(&settings).await;
+        let diagnostics =
+            self.build_diagnostics(uri, &all_detections, correlations.as_deref(), &settings);
+        (diagnostics, linked)
+    }
+
+    /// Index a document's new detections and re-link them with the other
+    /// analyzed documents, if correlation is enabled for its folder
+    ///
+    /// Peers in folders with correlation disabled are skipped. Returns the
+    /// documents linked to it before or after, whose related information
+    /// changed.
+    async fn correlate_document(
+        &self,
+        uri: &Url,
+        detections: Arc<Vec<Detection>>,
+        settings: &Settings,
+    ) -> HashSet<Url> {
+        if !settings.correlation_enabled {
+            self.correlation_indexes.remove(uri);
+            return self.correlations.write().await.remove(uri);
+        }
+
+        let index = Arc::new(CorrelationIndex::new(detections));
+        let peers: Vec<(Url, Arc<CorrelationIndex>)> = {
+            let client_settings = self.settings.read().await;
+            self.correlation_indexes
+                .iter()
+                .filter(|entry| entry.key() != uri)
+                .filter(|entry| client_settings.for_uri(entry.key()).correlation_enabled)
+                .map(|entry| (entry.key().clone(), Arc::clone(entry.value())))
+                .collect()
+        };
+        let linked = self.correlations.write().await.update(
+            uri,
+            &index,
+            &peers,
+            settings.correlation_window_ms,
+            MAX_CORRELATION_LINKS,
+        );
+        self.correlation_indexes.insert(uri.clone(), index);
+        linked
+    }
+
+    /// Links between documents, if correlation is enabled in `settings`
+    async fn correlations(
+        &self,
+        settings: &Settings,
+    ) -> Option<tokio::sync::RwLockReadGuard<'_, Correlations>> {
+        if !settings.correlation_enabled {
+            return None;
+        }
+        Some(self.correlations.read().await)
+    }
+
+    /// Convert a document's detections to diagnostics with correlation links,
+    /// followed by its signature and scenario diagnostics and the dimmed
+    /// diagnostics of suppressed detections
+    ///
+    /// The result is capped at the document's `max_diagnostics_per_document`.
+    fn build_diagnostics(
+        &self,
+        uri: &Url,
+        detections: &[Detection],
+        correlations: Option<&Correlations>,
+        settings: &Settings,
+    ) -> Vec<Diagnostic> {
+        let signatures = self
+            .signatures
+            .get(uri)
+            .map(|signatures| Arc::clone(&signatures))
+            .unwrap_or_default();
+        let scenarios = self
+            .scenarios
+            .get(uri)
+            .map(|scenarios| Arc::clone(&scenarios))
+            .unwrap_or_default();
+        let suppressed = self
+            .suppressed
+            .get(uri)
+            .map(|suppressed| Arc::clone(&suppressed))
+            .unwrap_or_default();
+
+        let mut diagnostics: Vec<Diagnostic> = self
+            .detection_diagnostics(uri, detections, correlations, settings)
+            .into_iter()
+            .chain(
+                signatures
+                    .iter()
+                    .map(|signature| signature.to_diagnostic(uri)),
+            )
+            .chain(scenarios.iter().map(|scenario| scenario.to_diagnostic(uri)))
+            .chain(
+                suppressed
+                    .iter()
+                    .filter(|suppressed| suppressed.visible)
+                    .map(|suppressed| self.suppressed_to_diagnostic(suppressed)),
+            )
+            .collect();
+
+        aggregation::cap_diagnostics(&mut diagnostics, settings.max_diagnostics_per_document);
+        self.encode_diagnostics(uri, &mut diagnostics);
+        diagnostics
+    }
+
+    /// Diagnostics of detections, collapsing noisy patterns into summaries
+    fn detection_diagnostics(
+        &self,
+        uri: &Url,
+        detections: &[Detection],
+        correlations: Option<&Correlations>,
+        settings: &Settings,
+    ) -> Vec<Diagnostic> {
+        aggregation::aggregate(detections, settings.aggregation_threshold)
+            .into_iter()
+            .map(|published| {
+                let detection = published.detection;
+                let mut diagnostic = self.detection_to_diagnostic(detection);
+                if let Some(correlations) = correlations {
+                    let related = correlations.related_information(uri, detection);
+                    if !related.is_empty() {
+                        diagnostic
+                            .related_information
+                            .get_or_insert_with(Vec::new)
+                            .extend(related);
+                    }
                 }
+                published.summarize(uri, &mut diagnostic);
+                diagnostic
+            })
+            .collect()
+    }
+
+    /// Republish diagnostics of documents whose correlation links changed
+    ///
+    /// Their related information follows the re-analyzed or closed document
+    /// they were linked to, before or after the change.
+    async fn republish_linked_documents(&self, linked: HashSet<Url>) {
+        for linked_uri in linked {
+            let Some(detections) = self.detections.get(&linked_uri).map(|d| Arc::clone(&d)) else {
+                continue;
+            };
+            let settings = self.settings_for(&linked_uri).await;
+            let diagnostics = {
+                let correlations = self.correlations(&settings).await;
+                self.build_diagnostics(&linked_uri, &detections, correlations.as_deref(), &settings)
+            };
+            self.client
+                .publish_diagnostics(linked_uri, diagnostics, None)
+                .await;
+        }
+    }
+
+    /// Get the detections for a document, analyzing it first if needed
+    async fn document_detections(&self, uri: &Url) -> Option<Arc<Vec<Detection>>> {
+        if let Some(detections) = self.detections.get(uri) {
+            return Some(Arc::clone(&detections));
+        }
+
+        let text = self.documents.get(uri)?.clone();
+        let total_lines = text.lines().count();
+        self.analyze_text(&text, uri, total_lines).await;
+
+        self.detections
+            .get(uri)
+            .map(|detections| Arc::clone(&detections))
+    }
+
+    /// Get the detections for a workspace log that is not open
+    ///
+    /// Results are cached until the file changes on disk.
+    async fn workspace_file_detections(&self, path: &Path) -> Option<Arc<Vec<Detection>>> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?;
+        if let Some(entry) = self.workspace_detections.get(path) {
+            if entry.0 == modified {
+                return Some(Arc::clone(&entry.1));
             }
+        }
 
-            tracing::info!(
-                "Found {} detections (before deduplication)",
-                all_detections.len()
+        // The file is held in memory, so it gets the same size limit as an
+        // opened file
+        let settings = self.settings.read().await.for_path(path).clone();
+        let limit = settings.max_file_size_mb as u64 * 1024 * 1024;
+        if metadata.len() > limit {
+            tracing::warn!(
+                "Skipping {} in symbol search: {} bytes exceeds max_file_size_mb ({} MB)",
+                path.display(),
+                metadata.len(),
+                settings.max_file_size_mb
             );
+            // Cached as empty so the warning is not repeated on every query
+            self.workspace_detections
+                .insert(path.to_path_buf(), (modified, Arc::new(Vec::new())));
+            return None;
+        }
 
-            // TODO: STAGE 2: Signature Detection - Group patterns in same category
-            // let signatures = signature_engine.detect(&all_detections);
+        // Same stages, settings and rules as an opened document, run on a
+        // blocking thread without holding the engine lock
+        let engine = self.pattern_engine.read().await.clone()?;
+        let pipeline = Arc::clone(&*self.pipeline.read().await);
+        let suppressions = self.suppressions.read().await.clone();
+        let file = path.to_path_buf();
+        let detections = tokio::task::spawn_blocking(move || {
+            let text = std::fs::read_to_string(&file).ok()?;
+            let mut context = AnalysisContext::new(&text, &engine)
+                .with_settings(&settings)
+                .with_suppressions(&suppressions, Some(&file));
+            pipeline.run(&mut context);
+            Some(Arc::new(context.detections))
+        })
+        .await
+        .ok()??;
 
-            // TODO: STAGE 3: Process Correlation - Identify functional flows
-            // let processes = process_engine.correlate(&signatures);
+        self.workspace_detections
+            .insert(path.to_path_buf(), (modified, Arc::clone(&detections)));
+        Some(detections)
+    }
 
-            // TODO: STAGE 4: Scenario Analysis - Cross-category event correlation
-            // let scenarios = scenario_engine.analyze(&processes);
+    /// Find every occurrence of the value under the cursor across open logs
+    async fn value_occurrences(&self, params: &TextDocumentPositionParams) -> Vec<ValueOccurrence> {
+        let uri = &params.text_document.uri;
+        let position = params.position;
 
-            // STAGE 5: Deduplication - Remove overlapping pattern matches
-            all_detections = Self::deduplicate_detections(all_detections);
+        let Some(text) = self.documents.get(uri).map(|doc| doc.clone()) else {
+            return Vec::new();
+        };
+        let Some(line) = text.lines().nth(position.line as usize) else {
+            return Vec::new();
+        };
 
-            tracing::info!(
-                "Found {} unique detections (after deduplication)",
-                all_detections.len()
-            );
+        // Parameter values extracted by detections on this line
+        let mut spans = Vec::new();
+        if let Some(detections) = self.document_detections(uri).await {
+            let engine_guard = self.pattern_engine.read().await;
+            if let Some(engine) = engine_guard.as_ref() {
+                spans = detections
+                    .iter()
+                    .filter(|d| d.line_number == position.line as usize)
+                    .filter_map(|d| engine.get_pattern(&d.pattern.id))
+                    .flat_map(|compiled| compiled.parameter_spans(line))
+                    .collect();
+            }
+        }
 
-            // TODO: STAGE 6: Remediation - Generate action plans for deduplicated issues
-            // let remediations = remediation_engine.recommend(&all_detections, &signatures, &scenarios);
+        let column = self
+            .position_encoding()
+            .byte_offset(line, position.character);
+        let Some(value) = references::value_at(line, column, &spans) else {
+            return Vec::new();
+        };
 
-            // STAGE 7: Diagnostic Creation - Convert to LSP diagnostics
-            all_detections
-                .into_iter()
-                .map(|detection| self.detection_to_diagnostic(&detection))
-                .collect()
-        } else {
-            tracing::warn!("No pattern engine available");
-            vec![]
+        let mut uris: Vec<Url> = self.documents.iter().map(|doc| doc.key().clone()).collect();
+        uris.sort();
+
+        let mut occurrences = Vec::new();
+        for uri in uris {
+            let Some(text) = self.documents.get(&uri).map(|doc| doc.clone()) else {
+                continue;
+            };
+            let detections = self.document_detections(&uri).await.unwrap_or_default();
+            occurrences.extend(references::find_occurrences(
+                &uri,
+                &text,
+                &value,
+                &detections,
+            ));
+        }
+
+        self.encode_locations(occurrences.iter_mut().map(|o| &mut o.location));
+        occurrences
+    }
+
+    /// Handle `logScout/references` requests
+    ///
+    /// Like `textDocument/references`, but each location carries the
+    /// detections it is part of.
+    pub async fn references_with_detections(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Vec<ValueOccurrence>> {
+        Ok(self.value_occurrences(&params).await)
+    }
+
+    /// Handle the `logScout/processes` request
+    ///
+    /// Lists the flow instances reconstructed from a document, analyzing it
+    /// first if needed.
+    pub async fn processes(&self, params: ProcessesParams) -> Result<Vec<ProcessInstance>> {
+        if self.document_detections(&params.uri).await.is_none() {
+            return Err(Error::invalid_params(format!(
+                "Document not open: {}",
+                params.uri
+            )));
         }
+
+        let instances = self
+            .processes
+            .get(&params.uri)
+            .map(|instances| Arc::clone(&instances))
+            .unwrap_or_default();
+
+        Ok(instances
+            .iter()
+            .filter(|instance| {
+                params
+                    .process_id
+                    .as_ref()
+                    .is_none_or(|id| &instance.process_id == id)
+            })
+            .cloned()
+            .collect())
     }
 
-    /// Deduplicate detections that overlap on the same line
-    ///
-    /// When multiple patterns match the same location (line + column range),
-    /// keep only the one with the highest severity. This handles cases where
-    /// TagScout has multiple patterns with the same regex but different templates
-    /// (e.g., HTTP success vs error patterns that both match any HTTP response).
-    ///
-    /// Future stages (signatures, scenarios) will see ALL matches before deduplication,
-    /// so they have full context for analysis.
-    fn deduplicate_detections(
-        detections: Vec<crate::pattern_engine::Detection>,
-    ) -> Vec<crate::pattern_engine::Detection> {
-        use std::collections::HashMap;
-
-        // Group detections by line and column range
-        let mut grouped: HashMap<(usize, (usize, usize)), Vec<crate::pattern_engine::Detection>> =
-            HashMap::new();
-
-        for detection in detections {
-            let key = (detection.line_number, detection.column_range);
-            grouped.entry(key).or_insert_with(Vec::new).push(detection);
-        }
-
-        // For each group, keep only the highest severity
-        let mut deduplicated = Vec::new();
-        for (_, mut group) in grouped {
-            if group.len() == 1 {
-                deduplicated.push(group.pop().unwrap());
-            } else {
-                // Sort by severity (Error > Warning > Info > Hint)
-                group.sort_by_key(|d| match d.final_severity {
-                    crate::pattern_engine::Severity::Error => 0,
-                    crate::pattern_engine::Severity::Warning => 1,
-                    crate::pattern_engine::Severity::Info => 2,
-                    crate::pattern_engine::Severity::Hint => 3,
-                });
+    /// Handle the `logScout/patternDetails` request
+    ///
+    /// Describes a loaded pattern and, given a document and line, its
+    /// detection there.
+    pub async fn pattern_details(&self, params: PatternDetailsParams) -> Result<PatternDetails> {
+        let pattern = {
+            let engine_guard = self.pattern_engine.read().await;
+            engine_guard
+                .as_ref()
+                .and_then(|engine| engine.get_pattern(&params.pattern_id))
+                .map(|compiled| compiled.pattern.clone())
+        };
+        let Some(pattern) = pattern else {
+            return Err(Error::invalid_params(format!(
+                "Unknown pattern: {}",
+                params.pattern_id
+            )));
+        };
+        let details = PatternDetails::new(&pattern);
+
+        let (Some(uri), Some(line)) = (params.uri, params.line) else {
+            return Ok(details);
+        };
+        let detections = self.document_detections(&uri).await.unwrap_or_default();
+        let Some(detection) = detections
+            .iter()
+            .find(|d| d.line_number == line as usize && d.pattern.id == params.pattern_id)
+        else {
+            return Err(Error::invalid_params(format!(
+                "No detection of '{}' on line {} of {}",
+                params.pattern_id,
+                line + 1,
+                uri
+            )));
+        };
+
+        let message = pattern_engine::substitute_template(
+            &detection.pattern.annotation,
+            &detection.field_values,
+        );
+        let mut details = details.with_detection(detection, message);
+        if let Some(detection) = details.detection.as_mut() {
+            // Columns in the negotiated encoding, as in the detection's diagnostic
+            let encoder = self.line_encoder(&uri);
+            let line = detection.line;
+            detection.column_range = encoder.column_range(line, detection.column_range);
+            for alternative in &mut detection.alternatives {
+                alternative.column_range = encoder.column_range(line, alternative.column_range);
+            }
+        }
+        Ok(details)
+    }
+
+    /// Ranked action plan of a document, analyzing it first if needed
+    async fn remediation_plan(&self, uri: &Url) -> Option<Arc<Vec<RemediationAction>>> {
+        self.document_detections(uri).await?;
+        self.remediations.get(uri).map(|plan| Arc::clone(&plan))
+    }
+
+    /// Jump to the next occurrence of a pattern (or of any detection)
+    async fn jump_to_next_occurrence(&self, uri: Url, pattern_id: Option<String>) {
+        let Some(detections) = self.document_detections(&uri).await else {
+            return;
+        };
+
+        let cursor = {
+            let mut entry = self
+                .occurrence_cursors
+                .entry((uri.clone(), pattern_id.clone()))
+                .or_insert(0);
+            let cursor = *entry;
+            *entry += 1;
+            cursor
+        };
+
+        let Some(detection) = code_lens::occurrence_at(&detections, pattern_id.as_deref(), cursor)
+        else {
+            return;
+        };
+
+        let (start_col, end_col) = detection.column_range;
+        let line = detection.line_number as u32;
+        let selection = LineEncoder::new(self.position_encoding())
+            .with_detections(std::slice::from_ref(detection))
+            .range(Range {
+                start: Position {
+                    line,
+                    character: start_col as u32,
+                },
+                end: Position {
+                    line,
+                    character: end_col as u32,
+                },
+            });
+        let params = ShowDocumentParams {
+            uri,
+            external: Some(false),
+            take_focus: Some(true),
+            selection: Some(selection),
+        };
+
+        if let Err(e) = self.client.show_document(params).await {
+            tracing::warn!("Failed to jump to occurrence: {}", e);
+        }
+    }
+
+    /// Start following a log file on disk, or stop if it is already followed
+    async fn toggle_follow(&self, uri: Url) {
+        if let Some((_, task)) = self.followers.remove(&uri) {
+            task.abort();
+            self.client
+                .show_message(
+                    MessageType::INFO,
+                    format!("Stopped following {}", uri.path()),
+                )
+                .await;
+            return;
+        }
+
+        let Ok(path) = uri.to_file_path() else {
+            self.client
+                .show_message(MessageType::ERROR, "Only files on disk can be followed")
+                .await;
+            return;
+        };
+
+        let settings = self.settings_for(&uri).await;
+        let follower = LogFollower::new(
+            path,
+            settings.multiline_context_window,
+            settings.max_file_size_mb as u64 * 1024 * 1024,
+        );
+        let server = self.clone();
+        let follow_uri = uri.clone();
+        let task = tokio::spawn(async move {
+            server.follow_file(follow_uri, follower).await;
+        });
+        self.followers.insert(uri.clone(), task);
+
+        self.client
+            .show_message(MessageType::INFO, format!("Following {}", uri.path()))
+            .await;
+    }
+
+    /// Poll a followed file and publish its detections at a throttled rate
+    ///
+    /// New lines go through the same stages, settings and rules as an
+    /// opened document, on a blocking thread without holding the engine
+    /// lock, and their results are appended to the document's.
+    async fn follow_file(&self, uri: Url, mut follower: LogFollower) {
+        let mut ticker = tokio::time::interval(follow::POLL_INTERVAL);
+        let mut last_publish: Option<std::time::Instant> = None;
+        let mut pending = false;
+
+        loop {
+            ticker.tick().await;
+
+            let Some(engine) = self.pattern_engine.read().await.clone() else {
+                continue;
+            };
+            let pipeline = Arc::clone(&*self.pipeline.read().await);
+            let settings = self.settings_for(&uri).await;
+            let suppressions = self.suppressions.read().await.clone();
+            let task = tokio::task::spawn_blocking(move || {
+                let update = follower.poll(&engine).map(|update| {
+                    let mut context = AnalysisContext::new("", &engine)
+                        .with_detections(update.detections)
+                        .with_settings(&settings)
+                        .with_suppressions(&suppressions, Some(follower.path()));
+                    // Correlation stages run over all detections when publishing
+                    pipeline
+                        .run_selected(&mut context, |stage| !CORRELATION_STAGES.contains(&stage));
+                    (update.reset, context.into_results())
+                });
+                (follower, update)
+            });
+            let update = match task.await {
+                Ok((returned, update)) => {
+                    follower = returned;
+                    update
+                }
+                Err(e) => {
+                    tracing::error!("Following {} failed: {}", uri, e);
+                    return;
+                }
+            };
+
+            match update {
+                Ok((reset, results)) => {
+                    if reset || !results.detections.is_empty() || !results.suppressed.is_empty() {
+                        self.append_results(&uri, results, reset);
+                        pending = true;
+                    }
+                }
+                // The file can briefly disappear while being rotated
+                Err(e) => tracing::debug!("Failed to read {}: {}", follower.path().display(), e),
+            }
+
+            let due = last_publish.is_none_or(|at| at.elapsed() >= follow::PUBLISH_INTERVAL);
+            if pending && due {
+                let settings = self.settings_for(&uri).await;
+                let detections = self.detections.get(&uri).map(|d| Arc::clone(&d));
+                let (diagnostics, linked) = match detections {
+                    Some(detections) => {
+                        self.correlate_followed(&uri, &detections, &settings).await;
+                        self.rank_remediations(&uri, &detections, &settings).await;
+                        // Followed logs are correlation peers of the open documents
+                        let linked = self
+                            .correlate_document(&uri, Arc::clone(&detections), &settings)
+                            .await;
+                        let correlations = self.correlations(&settings).await;
+                        let diagnostics = self.build_diagnostics(
+                            &uri,
+                            &detections,
+                            correlations.as_deref(),
+                            &settings,
+                        );
+                        (diagnostics, linked)
+                    }
+                    None => (Vec::new(), HashSet::new()),
+                };
+
+                self.client
+                    .publish_diagnostics(uri.clone(), diagnostics, None)
+                    .await;
+                self.republish_linked_documents(linked).await;
+                if let Err(e) = self.client.code_lens_refresh().await {
+                    tracing::debug!("Code lens refresh not supported: {}", e);
+                }
+
+                last_publish = Some(std::time::Instant::now());
+                pending = false;
+            }
+        }
+    }
+
+    /// Append the results of a followed log's new lines to its cached ones,
+    /// or replace them after a rotation
+    fn append_results(&self, uri: &Url, results: AnalysisResults, reset: bool) {
+        fn append<T: Clone>(
+            map: &DashMap<Url, Arc<Vec<T>>>,
+            uri: &Url,
+            items: Vec<T>,
+            reset: bool,
+        ) {
+            if !reset {
+                if let Some(mut cached) = map.get_mut(uri) {
+                    // Only copies when a request still holds the previous list
+                    Arc::make_mut(&mut cached).extend(items);
+                    return;
+                }
+            }
+            map.insert(uri.clone(), Arc::new(items));
+        }
+
+        // The correlation index shares the detections; it is rebuilt when
+        // they are published
+        self.correlation_indexes.remove(uri);
+        append(&self.detections, uri, results.detections, reset);
+        append(&self.suppressed, uri, results.suppressed, reset);
+    }
+
+    /// Run the correlation stages of a followed log over all its detections
+    ///
+    /// Signatures, flows and scenarios whose lines were read in different
+    /// polls are found, and replace the previous ones.
+    async fn correlate_followed(
+        &self,
+        uri: &Url,
+        detections: &Arc<Vec<Detection>>,
+        settings: &Settings,
+    ) {
+        let Some(engine) = self.pattern_engine.read().await.clone() else {
+            return;
+        };
+        let pipeline = Arc::clone(&*self.pipeline.read().await);
+        let detections = Arc::clone(detections);
+        let settings = settings.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            let mut context = AnalysisContext::new("", &engine)
+                .with_detections(detections.to_vec())
+                .with_settings(&settings);
+            pipeline.run_selected(&mut context, |stage| CORRELATION_STAGES.contains(&stage));
+            context.into_results()
+        });
+        let Ok(results) = task.await else {
+            tracing::error!("Correlating {} failed", uri);
+            return;
+        };
+
+        self.signatures
+            .insert(uri.clone(), Arc::new(results.signatures));
+        self.processes
+            .insert(uri.clone(), Arc::new(results.processes));
+        self.scenarios
+            .insert(uri.clone(), Arc::new(results.scenarios));
+    }
 
-                // Keep the highest severity (first after sorting)
-                if let Some(highest) = group.into_iter().next() {
-                    deduplicated.push(highest);
-                }
-            }
+    /// Rank the action plan of a followed log over all its results
+    async fn rank_remediations(&self, uri: &Url, detections: &[Detection], settings: &Settings) {
+        if !self
+            .pipeline
+            .read()
+            .await
+            .runs("remediation", Some(settings))
+        {
+            return;
         }
-
-        // Sort by line number to maintain document order
-        deduplicated.sort_by_key(|d| d.line_number);
-
-        deduplicated
+        let signatures = self
+            .signatures
+            .get(uri)
+            .map(|s| Arc::clone(&s))
+            .unwrap_or_default();
+        let scenarios = self
+            .scenarios
+            .get(uri)
+            .map(|s| Arc::clone(&s))
+            .unwrap_or_default();
+        self.remediations.insert(
+            uri.clone(),
+            Arc::new(remediation::recommend(detections, &signatures, &scenarios)),
+        );
     }
 
     /// Analyze document and publish diagnostics (push mode)
@@ -266,13 +1524,14 @@ impl LogScoutServer {
             .await;
 
         let total_lines = text.lines().count();
-        let diagnostics = self.analyze_text(text, uri.as_str(), total_lines).await;
+        let (diagnostics, linked) = self.analyze_text(text, uri, total_lines).await;
 
         // Publish diagnostics to client
         let count = diagnostics.len();
         self.client
             .publish_diagnostics(uri.clone(), diagnostics, None)
             .await;
+        self.republish_linked_documents(linked).await;
 
         self.client
             .log_message(
@@ -282,58 +1541,93 @@ impl LogScoutServer {
             .await;
     }
 
-    /// Replace template placeholders like {{ fieldName }} with actual values from field_values
-    /// Handles all spacing variations: {{CODE}}, {{ CODE }}, {{ CODE}}, {{CODE }}
-    fn substitute_template(
-        template: &str,
-        field_values: &std::collections::HashMap<String, String>,
-    ) -> String {
-        use regex::Regex;
-
-        tracing::info!("=== SUBSTITUTE_TEMPLATE ===");
-        tracing::info!("  Input template: '{}'", template);
-        tracing::info!("  Field values: {:?}", field_values);
-
-        let mut result = template.to_string();
-
-        // Replace each placeholder with the actual value
-        for (field_name, field_value) in field_values {
-            // Create regex to match {{optional_spaces field_name optional_spaces}}
-            // This handles: {{CODE}}, {{ CODE }}, {{ CODE}}, {{CODE }}, etc.
-            let pattern_str = format!(r"\{{\{{\s*{}\s*\}}\}}", regex::escape(field_name));
-            if let Ok(re) = Regex::new(&pattern_str) {
-                let before = result.clone();
-                result = re.replace_all(&result, field_value.as_str()).to_string();
-                if before != result {
-                    tracing::info!("  Replaced {{{{ {} }}}} with '{}'", field_name, field_value);
-                }
-            }
+    /// Unit of position columns (UTF-16 until negotiated)
+    fn position_encoding(&self) -> PositionEncoding {
+        self.position_encoding.get().copied().unwrap_or_default()
+    }
+
+    /// Encoder for byte columns of a document, from its detections and text
+    ///
+    /// Followed logs and archive members only have their detections.
+    fn line_encoder(&self, uri: &Url) -> LineEncoder {
+        let mut encoder = LineEncoder::new(self.position_encoding());
+        if encoder.encoding() == PositionEncoding::Utf8 {
+            return encoder;
         }
+        if let Some(detections) = self.detections.get(uri) {
+            encoder = encoder.with_detections(&detections);
+        }
+        if let Some(text) = self.documents.get(uri) {
+            encoder = encoder.with_text(&text);
+        }
+        encoder
+    }
+
+    /// Convert the byte columns of locations to the negotiated encoding
+    fn encode_locations<'a>(&self, locations: impl IntoIterator<Item = &'a mut Location>) {
+        if self.position_encoding() == PositionEncoding::Utf8 {
+            return;
+        }
+        let mut encoders: HashMap<Url, LineEncoder> = HashMap::new();
+        for location in locations {
+            let encoder = encoders
+                .entry(location.uri.clone())
+                .or_insert_with(|| self.line_encoder(&location.uri));
+            location.range = encoder.range(location.range);
+        }
+    }
 
-        // Keep unsubstituted placeholders as-is (shows {{ FIELD }} instead of [...])
-        // This makes it clear which fields weren't extracted
+    /// Convert the byte columns of a document's diagnostics, and of their
+    /// related locations, to the negotiated encoding
+    fn encode_diagnostics(&self, uri: &Url, diagnostics: &mut [Diagnostic]) {
+        if self.position_encoding() == PositionEncoding::Utf8 {
+            return;
+        }
+        let encoder = self.line_encoder(uri);
+        for diagnostic in diagnostics.iter_mut() {
+            diagnostic.range = encoder.range(diagnostic.range);
+        }
+        self.encode_locations(
+            diagnostics
+                .iter_mut()
+                .flat_map(|diagnostic| diagnostic.related_information.iter_mut().flatten())
+                .map(|related| &mut related.location),
+        );
+    }
 
-        tracing::info!("  Output result: '{}'", result);
-        tracing::info!("=== END SUBSTITUTE_TEMPLATE ===");
+    /// Byte position of a position sent by the client in an open document
+    fn byte_position(&self, uri: &Url, position: Position) -> Position {
+        let Some(text) = self.documents.get(uri) else {
+            return position;
+        };
+        match text.lines().nth(position.line as usize) {
+            Some(line) => Position {
+                line: position.line,
+                character: self
+                    .position_encoding()
+                    .byte_offset(line, position.character) as u32,
+            },
+            None => position,
+        }
+    }
 
-        result
+    /// Diagnostic of a suppressed detection, tagged as unnecessary
+    fn suppressed_to_diagnostic(&self, suppressed: &Suppressed) -> Diagnostic {
+        let mut diagnostic = self.detection_to_diagnostic(&suppressed.detection);
+        diagnostic.tags = Some(vec![DiagnosticTag::UNNECESSARY]);
+        if let Some(mut payload) = DetectionPayload::from_data(diagnostic.data.as_ref()) {
+            payload.suppressed_by = Some(suppressed.rule.clone());
+            diagnostic.data = Some(payload.to_value());
+        }
+        diagnostic
     }
 
     /// Convert a Detection to an LSP Diagnostic
     fn detection_to_diagnostic(&self, detection: &Detection) -> Diagnostic {
-        let severity = match detection.pattern.severity {
-            Severity::Error => DiagnosticSeverity::ERROR,
-            Severity::Warning => DiagnosticSeverity::WARNING,
-            Severity::Info => DiagnosticSeverity::INFORMATION,
-            Severity::Hint => DiagnosticSeverity::HINT,
-        };
+        let severity = DiagnosticSeverity::from(detection.final_severity);
 
         let (start_col, end_col) = detection.column_range;
 
-        // Substitute template placeholders in category
-        let category =
-            Self::substitute_template(&detection.pattern.category, &detection.field_values);
-
         // Get raw template from pattern (the message template from TagScout)
         let template = if detection.pattern.annotation.is_empty() {
             "(missing)".to_string()
@@ -341,10 +1635,10 @@ impl LogScoutServer {
             detection.pattern.annotation.clone()
         };
 
-        tracing::info!("=== COMPUTING MERGED_TEMPLATE ===");
-        tracing::info!("  Pattern name: {}", detection.pattern.name);
-        tracing::info!("  Template: '{}'", template);
-        tracing::info!("  Field values count: {}", detection.field_values.len());
+        tracing::trace!("=== COMPUTING MERGED_TEMPLATE ===");
+        tracing::trace!("  Pattern name: {}", detection.pattern.name);
+        tracing::trace!("  Template: '{}'", template);
+        tracing::trace!("  Field values count: {}", detection.field_values.len());
 
         // Create merged template - template with substituted values
         let merged_template = if template == "(missing)" {
@@ -355,115 +1649,17 @@ impl LogScoutServer {
             template.clone() // Diagnostic message will be "(missing)"
         } else {
             // Substitute field values into template
-            let substituted = Self::substitute_template(&template, &detection.field_values);
-            tracing::info!(
+            let substituted =
+                pattern_engine::substitute_template(&template, &detection.field_values);
+            tracing::trace!(
                 "  Successfully computed merged_template from template: '{}'",
                 substituted
             );
             substituted
         };
 
-        tracing::info!("  Final merged_template: '{}'", merged_template);
-        tracing::info!("=== END COMPUTING MERGED_TEMPLATE ===");
-
-        // Build diagnostic data with complete information
-        let mut data_map = serde_json::Map::new();
-
-        // Include ALL original TagScout annotation fields if available
-        if let Some(ref tagscout_metadata) = detection.pattern.tagscout_metadata {
-            if let serde_json::Value::Object(metadata_map) = tagscout_metadata {
-                // Copy all original TagScout fields
-                for (key, value) in metadata_map {
-                    data_map.insert(key.clone(), value.clone());
-                }
-            }
-        }
-
-        // Build extracted_parameters as list of key-value pairs
-        let extracted_params: Vec<serde_json::Value> = detection
-            .field_values
-            .iter()
-            .map(|(name, value)| {
-                let mut param = serde_json::Map::new();
-                param.insert("name".to_string(), serde_json::Value::String(name.clone()));
-                param.insert(
-                    "value".to_string(),
-                    serde_json::Value::String(value.clone()),
-                );
-                serde_json::Value::Object(param)
-            })
-            .collect();
-
-        // Add/override with detection-specific fields using TagScout naming conventions
-        tracing::info!("=== BUILDING DIAGNOSTIC DATA ===");
-        tracing::info!("  template: '{}'", template);
-        tracing::info!("  merged_template: '{}'", merged_template);
-        tracing::info!("  log_line (matched): '{}'", detection.matched_text);
-
-        // Core fields: template and merged result
-        data_map.insert(
-            "template".to_string(),
-            serde_json::Value::String(template.clone()),
-        );
-        data_map.insert(
-            "merged_template".to_string(),
-            serde_json::Value::String(merged_template.clone()),
-        );
-
-        // Source information
-        data_map.insert(
-            "log_line".to_string(),
-            serde_json::Value::String(detection.context.first().cloned().unwrap_or_default()),
-        );
-
-        // Extracted parameters as list of {name, value} objects
-        data_map.insert(
-            "extracted_parameters".to_string(),
-            serde_json::Value::Array(extracted_params),
-        );
-
-        // Pattern metadata
-        data_map.insert(
-            "pattern_id".to_string(),
-            serde_json::Value::String(detection.pattern.id.clone()),
-        );
-        data_map.insert(
-            "pattern_name".to_string(),
-            serde_json::Value::String(detection.pattern.name.clone()),
-        );
-        data_map.insert(
-            "category".to_string(),
-            serde_json::Value::String(category.clone()),
-        );
-
-        // Debugging information
-        data_map.insert(
-            "matched_text".to_string(),
-            serde_json::Value::String(detection.matched_text.clone()),
-        );
-        data_map.insert(
-            "pattern_regex".to_string(),
-            serde_json::Value::String(detection.pattern.pattern.clone()),
-        );
-
-        // Include timestamp if present
-        if let Some(ref timestamp) = detection.timestamp {
-            data_map.insert(
-                "timestamp".to_string(),
-                serde_json::Value::String(timestamp.clone()),
-            );
-        }
-
-        // Include log level if present
-        if let Some(ref log_level) = detection.log_level {
-            data_map.insert(
-                "log_level".to_string(),
-                serde_json::Value::String(format!("{:?}", log_level)),
-            );
-        }
-
-        tracing::info!("  Data map has {} keys", data_map.len());
-        tracing::info!("=== END BUILDING DIAGNOSTIC DATA ===");
+        tracing::trace!("  Final merged_template: '{}'", merged_template);
+        tracing::trace!("=== END COMPUTING MERGED_TEMPLATE ===");
 
         Diagnostic {
             range: Range {
@@ -483,32 +1679,95 @@ impl LogScoutServer {
             message: merged_template, // Main message is the merged template (substituted values)
             related_information: None,
             tags: None,
-            data: Some(serde_json::Value::Object(data_map)),
+            data: Some(DetectionPayload::new(detection).to_value()),
         }
     }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for LogScoutServer {
-    async fn initialize(&self, _params: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
         tracing::info!("Client initializing LSP server");
 
+        // Remember workspace folders for locating local pattern packs
+        #[allow(deprecated)]
+        let root_uris: Vec<Url> = match (params.workspace_folders, params.root_uri) {
+            (Some(folders), _) => folders.into_iter().map(|folder| folder.uri).collect(),
+            (None, Some(root_uri)) => vec![root_uri],
+            (None, None) => Vec::new(),
+        };
+        *self.workspace_roots.write().await = root_uris
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect();
+
+        // Client settings, with per-workspace-folder overrides
+        if let Some(options) = &params.initialization_options {
+            match ClientSettings::from_json(options) {
+                Ok(settings) => *self.settings.write().await = settings,
+                Err(e) => tracing::warn!("Ignoring initializationOptions: {}", e),
+            }
+        }
+
+        // Columns are converted from bytes to the negotiated encoding
+        let encoding = PositionEncoding::negotiate(
+            params
+                .capabilities
+                .general
+                .as_ref()
+                .and_then(|general| general.position_encodings.as_deref()),
+        );
+        let _ = self.position_encoding.set(encoding);
+        tracing::info!("Position encoding: {:?}", encoding);
+
+        let _ = self.configuration_support.set(
+            params
+                .capabilities
+                .workspace
+                .as_ref()
+                .and_then(|workspace| workspace.configuration)
+                .unwrap_or(false),
+        );
+
+        // Correlation makes diagnostics depend on other documents. This is
+        // only computed here: later settings changes do not update the
+        // capability.
+        let correlation_enabled = {
+            let settings = self.settings.read().await;
+            settings.global.correlation_enabled
+                || settings
+                    .folders
+                    .iter()
+                    .any(|(_, folder)| folder.correlation_enabled)
+        };
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
+                position_encoding: Some(encoding.kind()),
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::INCREMENTAL,
                 )),
                 diagnostic_provider: Some(DiagnosticServerCapabilities::Options(
                     DiagnosticOptions {
                         identifier: Some("log-scout".to_string()),
-                        inter_file_dependencies: false,
+                        inter_file_dependencies: correlation_enabled,
                         workspace_diagnostics: false,
                         work_done_progress_options: WorkDoneProgressOptions {
                             work_done_progress: Some(true),
                         },
                     },
                 )),
-                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: None,
+                        work_done_progress_options: WorkDoneProgressOptions::default(),
+                        resolve_provider: Some(true),
+                    },
+                )),
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(false),
+                }),
+                inlay_hint_provider: Some(OneOf::Left(true)),
                 execute_command_provider: Some(ExecuteCommandOptions {
                     commands: vec![
                         "logScout.analyze".to_string(),
@@ -516,12 +1775,25 @@ impl LanguageServer for LogScoutServer {
                         "logScout.exportResults".to_string(),
                         "logScout.refreshPatterns".to_string(),
                         "logScout.getPatterns".to_string(),
+                        code_lens::NEXT_OCCURRENCE_COMMAND.to_string(),
+                        inlay_hints::SET_TIME_ANCHOR_COMMAND.to_string(),
+                        follow::FOLLOW_COMMAND.to_string(),
+                        REMEDIATION_PLAN_COMMAND.to_string(),
+                        MUTE_COMMAND.to_string(),
+                        LIST_SUPPRESSIONS_COMMAND.to_string(),
+                        OPEN_DOCUMENTATION_COMMAND.to_string(),
+                        SHOW_OCCURRENCES_COMMAND.to_string(),
+                        TICKET_MARKDOWN_COMMAND.to_string(),
+                        SUGGEST_PATTERN_COMMAND.to_string(),
                     ],
                     work_done_progress_options: WorkDoneProgressOptions {
                         work_done_progress: Some(true),
                     },
                 }),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
                 document_symbol_provider: Some(OneOf::Left(true)),
                 ..Default::default()
             },
@@ -538,6 +1810,13 @@ impl LanguageServer for LogScoutServer {
             .log_message(MessageType::INFO, "Log Scout Analyzer ready!")
             .await;
 
+        self.reload_suppressions().await;
+
+        // Local pattern packs are available immediately, before TagScout loads
+        if let Err(e) = self.reload_local_patterns().await {
+            tracing::warn!("Failed to load local pattern packs: {}", e);
+        }
+
         // Initialize TagScout in background
         let client_clone = self.client.clone();
         let server_clone = self.clone();
@@ -586,12 +1865,18 @@ impl LanguageServer for LogScoutServer {
 
         tracing::debug!("Document changed: {}", uri);
 
-        // Apply incremental changes
+        // Apply incremental changes, whose ranges are in the negotiated encoding
+        let encoding = self.position_encoding();
         if let Some(mut doc_entry) = self.documents.get_mut(&uri) {
             for change in params.content_changes {
-                // For simplicity, just replace the whole document
-                // In production, you'd handle incremental edits properly
-                *doc_entry = change.text;
+                match change.range {
+                    Some(range) => {
+                        let start = encoding.text_offset(&doc_entry, range.start);
+                        let end = encoding.text_offset(&doc_entry, range.end).max(start);
+                        doc_entry.replace_range(start..end, &change.text);
+                    }
+                    None => *doc_entry = change.text,
+                }
             }
 
             let text = doc_entry.clone();
@@ -605,6 +1890,17 @@ impl LanguageServer for LogScoutServer {
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
         tracing::info!("Document saved: {}", params.text_document.uri);
 
+        let saved_ignore_file = params
+            .text_document
+            .uri
+            .to_file_path()
+            .is_ok_and(|path| path.file_name() == Some(suppression::IGNORE_FILE.as_ref()));
+        if saved_ignore_file {
+            self.reload_suppressions().await;
+            self.reanalyze_open_documents().await;
+            return;
+        }
+
         // Re-analyze on save if text is provided
         if let Some(text) = params.text {
             self.documents
@@ -614,12 +1910,41 @@ impl LanguageServer for LogScoutServer {
         }
     }
 
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        // Pull-model clients only signal that the configuration changed
+        let section = match ClientSettings::section(&params.settings) {
+            Some(section) => section.clone(),
+            None => match self.fetch_settings().await {
+                Some(section) => section,
+                None => {
+                    tracing::debug!(
+                        "No Log Scout settings in the change; keeping the current ones"
+                    );
+                    return;
+                }
+            },
+        };
+
+        match ClientSettings::from_json(&section) {
+            Ok(settings) => {
+                tracing::info!("Applying updated client settings");
+                self.apply_settings(settings).await;
+            }
+            Err(e) => {
+                self.client
+                    .show_message(MessageType::ERROR, format!("Log Scout settings: {}", e))
+                    .await;
+            }
+        }
+    }
+
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let uri = params.text_document.uri;
         tracing::info!("Document closed: {}", uri);
 
-        // Remove from cache
-        self.documents.remove(&uri);
+        // Remove from cache, with the logs of an archive
+        self.forget_document(&uri).await;
+        self.release_archive(&uri).await;
 
         // Clear diagnostics
         self.client.publish_diagnostics(uri, vec![], None).await;
@@ -639,7 +1964,7 @@ impl LanguageServer for LogScoutServer {
                         "**Log Line Analysis**\n\nLine {}: `{}`\n\nLength: {} characters",
                         position.line + 1,
                         line,
-                        line.len()
+                        line.chars().count()
                     ),
                 });
 
@@ -652,7 +1977,7 @@ impl LanguageServer for LogScoutServer {
                         },
                         end: Position {
                             line: position.line,
-                            character: line.len() as u32,
+                            character: self.position_encoding().column(line, line.len()),
                         },
                     }),
                 }));
@@ -665,43 +1990,218 @@ impl LanguageServer for LogScoutServer {
     async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
         let uri = &params.text_document.uri;
 
-        let mut actions = vec![];
-
-        // "Export results" action
-        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
-            title: "Export analysis results".to_string(),
-            kind: Some(CodeActionKind::REFACTOR),
-            diagnostics: Some(params.context.diagnostics.clone()),
-            edit: None,
-            command: Some(Command {
-                title: "Export Results".to_string(),
-                command: "logScout.exportResults".to_string(),
-                arguments: Some(vec![serde_json::to_value(uri).unwrap()]),
-            }),
-            is_preferred: Some(false),
-            disabled: None,
-            data: None,
-        }));
+        // Documentation, occurrences, muting and tickets for the detections in range
+        let mut actions: Vec<CodeActionOrCommand> = params
+            .context
+            .diagnostics
+            .iter()
+            .flat_map(|diagnostic| code_actions::detection_actions(uri, diagnostic))
+            .collect();
 
-        // "Show timeline" action
-        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
-            title: "Show timeline visualization".to_string(),
-            kind: Some(CodeActionKind::EMPTY),
-            diagnostics: None,
-            edit: None,
-            command: Some(Command {
-                title: "Show Timeline".to_string(),
-                command: "logScout.showTimeline".to_string(),
-                arguments: Some(vec![serde_json::to_value(uri).unwrap()]),
-            }),
-            is_preferred: Some(false),
-            disabled: None,
-            data: None,
-        }));
+        // Remediation steps suggested for the diagnostics in range
+        if let Some(plan) = self.remediation_plan(uri).await {
+            for diagnostic in &params.context.diagnostics {
+                let Some(NumberOrString::String(code)) = &diagnostic.code else {
+                    continue;
+                };
+                for step in plan.iter().filter(|step| step.suggested_by(code)) {
+                    actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                        title: format!("Remediation #{}: {}", step.rank, step.action),
+                        kind: Some(CodeActionKind::QUICKFIX),
+                        diagnostics: Some(vec![diagnostic.clone()]),
+                        edit: None,
+                        command: Some(Command {
+                            title: "Show Action Plan".to_string(),
+                            command: REMEDIATION_PLAN_COMMAND.to_string(),
+                            arguments: Some(vec![serde_json::to_value(uri).unwrap()]),
+                        }),
+                        is_preferred: Some(step.rank == 1),
+                        disabled: None,
+                        data: None,
+                    }));
+                }
+            }
+        }
+
+        // Lines of open logs can seed a new pattern
+        if self.documents.contains_key(uri) {
+            actions.push(code_actions::suggest_pattern_action(uri, params.range));
+        }
 
         Ok(Some(actions))
     }
 
+    /// Resolve a detection's code action with the pattern details in `data`
+    async fn code_action_resolve(&self, mut action: CodeAction) -> Result<CodeAction> {
+        let Some(params) = action
+            .data
+            .clone()
+            .and_then(|data| serde_json::from_value::<PatternDetailsParams>(data).ok())
+        else {
+            return Ok(action);
+        };
+
+        let details = self.pattern_details(params).await?;
+        action.data = Some(serde_json::to_value(details).unwrap_or_default());
+        Ok(action)
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let uri = &params.text_document_position_params.text_document.uri;
+        let position = self.byte_position(uri, params.text_document_position_params.position);
+
+        let Some(detections) = self.document_detections(uri).await else {
+            return Ok(None);
+        };
+        let Some(detection) = detections
+            .iter()
+            .find(|d| d.covers(position.line as usize, position.character as usize))
+        else {
+            return Ok(None);
+        };
+
+        // Local patterns jump to their pack file; TagScout patterns open a
+        // rendered read-only document
+        let location = match &detection.pattern.source {
+            Some(source) => Url::from_file_path(&source.path).ok().map(|uri| Location {
+                uri,
+                range: Range {
+                    start: Position {
+                        line: source.line as u32,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: source.line as u32,
+                        character: 0,
+                    },
+                },
+            }),
+            None => Some(Location {
+                uri: virtual_document::pattern_uri(&detection.pattern.id),
+                range: Range::default(),
+            }),
+        };
+
+        Ok(location.map(GotoDefinitionResponse::Scalar))
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let occurrences = self.value_occurrences(&params.text_document_position).await;
+        if occurrences.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            occurrences
+                .into_iter()
+                .map(|occurrence| occurrence.location)
+                .collect(),
+        ))
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        let merge = |d: &Detection| {
+            pattern_engine::substitute_template(&d.pattern.annotation, &d.field_values)
+        };
+        let mut symbols = Vec::new();
+
+        // Open logs first, using their current (possibly unsaved) content
+        let mut open_uris: Vec<Url> = self.documents.iter().map(|doc| doc.key().clone()).collect();
+        open_uris.sort();
+        for uri in &open_uris {
+            if let Some(detections) = self.document_detections(uri).await {
+                symbols.extend(workspace_symbols::detection_symbols(
+                    uri,
+                    &detections,
+                    &params.query,
+                    merge,
+                ));
+            }
+        }
+        self.encode_locations(symbols.iter_mut().map(|symbol| &mut symbol.location));
+
+        // Then logs on disk in the workspace folders, forgetting the cached
+        // detections of files that were deleted or renamed
+        let roots = self.workspace_roots.read().await.clone();
+        let log_files =
+            tokio::task::spawn_blocking(move || workspace_symbols::find_log_files(&roots))
+                .await
+                .unwrap_or_default();
+        self.workspace_detections
+            .retain(|path, _| log_files.contains(path));
+        for path in log_files {
+            if symbols.len() >= MAX_SYMBOLS {
+                break;
+            }
+            let Ok(uri) = Url::from_file_path(&path) else {
+                continue;
+            };
+            if self.documents.contains_key(&uri) {
+                continue;
+            }
+            if let Some(detections) = self.workspace_file_detections(&path).await {
+                let encoder =
+                    LineEncoder::new(self.position_encoding()).with_detections(&detections);
+                symbols.extend(
+                    workspace_symbols::detection_symbols(&uri, &detections, &params.query, merge)
+                        .into_iter()
+                        .map(|mut symbol| {
+                            symbol.location.range = encoder.range(symbol.location.range);
+                            symbol
+                        }),
+                );
+            }
+        }
+
+        symbols.truncate(MAX_SYMBOLS);
+        Ok(Some(symbols))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = &params.text_document.uri;
+
+        match self.document_detections(uri).await {
+            Some(detections) => Ok(Some(code_lens::build_code_lenses(uri, &detections))),
+            None => Ok(None),
+        }
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let uri = &params.text_document.uri;
+        let Some(text) = self.documents.get(uri).map(|doc| doc.clone()) else {
+            return Ok(None);
+        };
+
+        let anchor = self.time_anchors.get(uri).map(|anchor| *anchor);
+        let mut hints = inlay_hints::time_delta_hints(&text, params.range, anchor);
+
+        if let Some(detections) = self.document_detections(uri).await {
+            let engine_guard = self.pattern_engine.read().await;
+            if let Some(engine) = engine_guard.as_ref() {
+                let enums = self.enums.read().await;
+                hints.extend(inlay_hints::parameter_hints(
+                    &text,
+                    params.range,
+                    &detections,
+                    engine,
+                    &enums,
+                ));
+            }
+        }
+
+        let encoder = self.line_encoder(uri);
+        for hint in &mut hints {
+            hint.position = encoder.position(hint.position);
+        }
+        Ok(Some(hints))
+    }
+
     async fn execute_command(
         &self,
         params: ExecuteCommandParams,
@@ -791,7 +2291,8 @@ impl LanguageServer for LogScoutServer {
                                 "parameterExtractors": p.parameter_extractors.iter().map(|pe| {
                                     serde_json::json!({
                                         "name": pe.name,
-                                        "regex": pe.regex
+                                        "regex": pe.regex,
+                                        "enum": pe.enum_name
                                     })
                                 }).collect::<Vec<_>>(),
                             })
@@ -813,6 +2314,277 @@ impl LanguageServer for LogScoutServer {
                     })))
                 }
             }
+            code_lens::NEXT_OCCURRENCE_COMMAND => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok());
+                let pattern_id = params
+                    .arguments
+                    .get(1)
+                    .and_then(|arg| arg.as_str())
+                    .map(|id| id.to_string());
+
+                if let Some(uri) = uri {
+                    self.jump_to_next_occurrence(uri, pattern_id).await;
+                }
+                Ok(None)
+            }
+            inlay_hints::SET_TIME_ANCHOR_COMMAND => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok());
+                let line = params
+                    .arguments
+                    .get(1)
+                    .and_then(|arg| arg.as_u64())
+                    .map(|line| line as u32);
+
+                if let Some(uri) = uri {
+                    // Setting the current anchor again (or passing no line) clears it
+                    let current = self.time_anchors.get(&uri).map(|anchor| *anchor);
+                    match line {
+                        Some(line) if current != Some(line) => {
+                            self.time_anchors.insert(uri, line);
+                        }
+                        _ => {
+                            self.time_anchors.remove(&uri);
+                        }
+                    }
+
+                    if let Err(e) = self.client.inlay_hint_refresh().await {
+                        tracing::debug!("Inlay hint refresh not supported: {}", e);
+                    }
+                }
+                Ok(None)
+            }
+            follow::FOLLOW_COMMAND => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok());
+
+                if let Some(uri) = uri {
+                    self.toggle_follow(uri).await;
+                }
+                Ok(None)
+            }
+            REMEDIATION_PLAN_COMMAND => {
+                let Some(uri) = params
+                    .arguments
+                    .first()
+                    .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok())
+                else {
+                    return Err(Error::invalid_params("Expected a document URI"));
+                };
+                let Some(plan) = self.remediation_plan(&uri).await else {
+                    return Err(Error::invalid_params(format!("Document not open: {}", uri)));
+                };
+
+                let message = if plan.is_empty() {
+                    "No remediation actions for this log".to_string()
+                } else {
+                    format!("Action plan:\n{}", remediation::format_plan(&plan))
+                };
+                self.client.show_message(MessageType::INFO, message).await;
+
+                Ok(Some(serde_json::to_value(plan.as_slice()).unwrap()))
+            }
+            MUTE_COMMAND => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok());
+                let pattern_id = params.arguments.get(1).and_then(|arg| arg.as_str());
+                let (Some(uri), Some(pattern_id)) = (uri, pattern_id) else {
+                    return Err(Error::invalid_params(
+                        "Expected a document URI and a pattern ID",
+                    ));
+                };
+                let everywhere =
+                    params.arguments.get(2).and_then(|arg| arg.as_str()) == Some("everywhere");
+
+                match self.mute_pattern(&uri, pattern_id, everywhere).await {
+                    Ok(ignore_file) => {
+                        self.client
+                            .show_message(
+                                MessageType::INFO,
+                                format!("Muted '{}' in {}", pattern_id, ignore_file.display()),
+                            )
+                            .await;
+                    }
+                    Err(e) => {
+                        self.client
+                            .show_message(
+                                MessageType::ERROR,
+                                format!("Failed to mute '{}': {}", pattern_id, e),
+                            )
+                            .await;
+                    }
+                }
+                Ok(None)
+            }
+            OPEN_DOCUMENTATION_COMMAND => {
+                let Some(pattern_id) = params.arguments.first().and_then(|arg| arg.as_str()) else {
+                    return Err(Error::invalid_params("Expected a pattern ID"));
+                };
+
+                match self.open_documentation(pattern_id).await {
+                    Ok(uri) => Ok(Some(serde_json::to_value(uri).unwrap())),
+                    Err(e) => {
+                        self.client
+                            .show_message(
+                                MessageType::ERROR,
+                                format!("Failed to open documentation: {}", e),
+                            )
+                            .await;
+                        Ok(None)
+                    }
+                }
+            }
+            SHOW_OCCURRENCES_COMMAND => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok());
+                let pattern_id = params.arguments.get(1).and_then(|arg| arg.as_str());
+                let (Some(uri), Some(pattern_id)) = (uri, pattern_id) else {
+                    return Err(Error::invalid_params(
+                        "Expected a document URI and a pattern ID",
+                    ));
+                };
+
+                let locations = self.pattern_occurrences(&uri, pattern_id);
+                let documents: std::collections::HashSet<&Url> =
+                    locations.iter().map(|location| &location.uri).collect();
+                self.client
+                    .show_message(
+                        MessageType::INFO,
+                        format!(
+                            "{} occurrence(s) of '{}' in {} log(s)",
+                            locations.len(),
+                            pattern_id,
+                            documents.len()
+                        ),
+                    )
+                    .await;
+
+                Ok(Some(serde_json::to_value(locations).unwrap()))
+            }
+            TICKET_MARKDOWN_COMMAND => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok());
+                let line = params.arguments.get(1).and_then(|arg| arg.as_u64());
+                let pattern_id = params.arguments.get(2).and_then(|arg| arg.as_str());
+                let (Some(uri), Some(line), Some(pattern_id)) = (uri, line, pattern_id) else {
+                    return Err(Error::invalid_params(
+                        "Expected a document URI, a line and a pattern ID",
+                    ));
+                };
+
+                let Some(markdown) = self.detection_ticket(&uri, line as usize, pattern_id).await
+                else {
+                    return Err(Error::invalid_params(format!(
+                        "No detection of '{}' on line {}",
+                        pattern_id,
+                        line + 1
+                    )));
+                };
+
+                Ok(Some(serde_json::json!({ "markdown": markdown })))
+            }
+            SUGGEST_PATTERN_COMMAND => {
+                let uri = params
+                    .arguments
+                    .first()
+                    .and_then(|arg| serde_json::from_value::<Url>(arg.clone()).ok());
+                // Either a line or a single-line selection
+                let target = params.arguments.get(1).and_then(|arg| match arg.as_u64() {
+                    Some(line) => Some((line as usize, None)),
+                    None => serde_json::from_value::<Range>(arg.clone())
+                        .ok()
+                        .filter(|range| range.start.line == range.end.line)
+                        .map(|range| {
+                            (
+                                range.start.line as usize,
+                                Some((
+                                    range.start.character as usize,
+                                    range.end.character as usize,
+                                )),
+                            )
+                        }),
+                });
+                let (Some(uri), Some((line, columns))) = (uri, target) else {
+                    return Err(Error::invalid_params(
+                        "Expected a document URI and a line or single-line range",
+                    ));
+                };
+
+                match self.suggest_pattern(&uri, line, columns).await {
+                    Ok((draft, pack, entry_line)) => {
+                        // Open the draft so it can be refined
+                        let selection = Range {
+                            start: Position {
+                                line: entry_line as u32,
+                                character: 0,
+                            },
+                            end: Position {
+                                line: entry_line as u32,
+                                character: 0,
+                            },
+                        };
+                        if let Ok(pack_uri) = Url::from_file_path(&pack) {
+                            let params = ShowDocumentParams {
+                                uri: pack_uri,
+                                external: Some(false),
+                                take_focus: Some(true),
+                                selection: Some(selection),
+                            };
+                            if let Err(e) = self.client.show_document(params).await {
+                                tracing::debug!("Failed to open {}: {}", pack.display(), e);
+                            }
+                        }
+
+                        Ok(Some(serde_json::json!({
+                            "pattern": draft,
+                            "path": pack,
+                            "line": entry_line,
+                        })))
+                    }
+                    Err(e) => {
+                        self.client
+                            .show_message(
+                                MessageType::ERROR,
+                                format!("Failed to suggest pattern: {}", e),
+                            )
+                            .await;
+                        Ok(None)
+                    }
+                }
+            }
+            LIST_SUPPRESSIONS_COMMAND => {
+                let list = self.list_suppressions().await;
+                let rules = list.rules.len();
+                let muted: usize = self
+                    .suppressed
+                    .iter()
+                    .map(|entry| entry.value().len())
+                    .sum();
+                self.client
+                    .show_message(
+                        MessageType::INFO,
+                        format!(
+                            "{} suppression rule(s) muting {} detection(s) in open logs",
+                            rules, muted
+                        ),
+                    )
+                    .await;
+
+                Ok(Some(serde_json::to_value(list).unwrap()))
+            }
             _ => {
                 tracing::warn!("Unknown command: {}", params.command);
                 Ok(None)
@@ -837,6 +2609,7 @@ impl LanguageServer for LogScoutServer {
                     || line.contains("INFO")
                     || line.contains("FATAL")
                 {
+                    let end = self.position_encoding().column(line, line.len());
                     #[allow(deprecated)]
                     let symbol = DocumentSymbol {
                         name: line.chars().take(50).collect::<String>(),
@@ -851,7 +2624,7 @@ impl LanguageServer for LogScoutServer {
                             },
                             end: Position {
                                 line: line_num as u32,
-                                character: line.len() as u32,
+                                character: end,
                             },
                         },
                         selection_range: Range {
@@ -861,7 +2634,7 @@ impl LanguageServer for LogScoutServer {
                             },
                             end: Position {
                                 line: line_num as u32,
-                                character: line.len() as u32,
+                                character: end,
                             },
                         },
                         children: None,
@@ -899,7 +2672,7 @@ impl LanguageServer for LogScoutServer {
 
             // Analyze the document
             let total_lines = text.lines().count();
-            let diagnostics = self.analyze_text(&text, uri.as_str(), total_lines).await;
+            let (diagnostics, _) = self.analyze_text(&text, &uri, total_lines).await;
 
             tracing::info!(
                 "Returning {} diagnostics for pull request",
@@ -940,3 +2713,170 @@ impl LanguageServer for LogScoutServer {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+    use tower_lsp::LspService;
+
+    #[tokio::test]
+    async fn test_close_forgets_suppressed_detections() {
+        let (service, mut socket) = LspService::new(LogScoutServer::new);
+        tokio::spawn(async move { while socket.next().await.is_some() {} });
+        let server = service.inner();
+        let engine = PatternEngine::new(vec![Pattern::test("boom", "BOOM")], 0.85, 10).unwrap();
+        *server.pattern_engine.write().await = Some(Arc::new(engine));
+        *server.suppressions.write().await =
+            Suppressions::new(vec![SuppressionRule::mute("boom", None)]);
+
+        let uri = Url::parse("file:///var/log/app.log").unwrap();
+        server
+            .did_open(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(
+                    uri.clone(),
+                    "log".to_string(),
+                    1,
+                    "BOOM\n".to_string(),
+                ),
+            })
+            .await;
+        let list = serde_json::to_value(server.list_suppressions().await).unwrap();
+        let occurrence = &list["rules"][0]["occurrences"][0];
+        assert_eq!(occurrence["line"], 0);
+        assert_eq!(occurrence["patternId"], "boom");
+        assert_eq!(occurrence["version"], crate::payload::SCHEMA_VERSION);
+
+        server
+            .did_close(DidCloseTextDocumentParams {
+                text_document: TextDocumentIdentifier::new(uri),
+            })
+            .await;
+        assert!(server.list_suppressions().await.rules[0]
+            .occurrences
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_correlation_skips_disabled_folders() {
+        let (service, mut socket) = LspService::new(LogScoutServer::new);
+        tokio::spawn(async move { while socket.next().await.is_some() {} });
+        let server = service.inner();
+        let pattern = Pattern {
+            parameter_extractors: vec![pattern_engine::ParameterExtractor {
+                name: "reqId".to_string(),
+                regex: r"req=(\S+)".to_string(),
+                enum_name: None,
+            }],
+            ..Pattern::test("failed", "failed")
+        };
+        let engine = PatternEngine::new(vec![pattern], 0.85, 10).unwrap();
+        *server.pattern_engine.write().await = Some(Arc::new(engine));
+        let open = |path: &str| {
+            let uri = Url::parse(path).unwrap();
+            let params = DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(
+                    uri.clone(),
+                    "log".to_string(),
+                    1,
+                    "call failed req=ab12\n".to_string(),
+                ),
+            };
+            (uri, params)
+        };
+        let (_, params) = open("file:///work/legacy/b.log");
+        server.did_open(params).await;
+
+        // Disabled after the legacy log was analyzed
+        let legacy = Settings {
+            correlation_enabled: false,
+            ..Settings::default()
+        };
+        server.settings.write().await.folders = vec![(PathBuf::from("/work/legacy"), legacy)];
+        let (app, params) = open("file:///work/app/a.log");
+        server.did_open(params).await;
+        assert!(server.correlations.read().await.is_empty());
+
+        let (other, params) = open("file:///work/app/c.log");
+        server.did_open(params).await;
+        assert_eq!(
+            server.correlations.read().await.linked_documents(&other),
+            HashSet::from([app])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_workspace_files_honor_size_limit() {
+        let (service, mut socket) = LspService::new(LogScoutServer::new);
+        tokio::spawn(async move { while socket.next().await.is_some() {} });
+        let server = service.inner();
+        let engine = PatternEngine::new(vec![Pattern::test("boom", "BOOM")], 0.85, 10).unwrap();
+        *server.pattern_engine.write().await = Some(Arc::new(engine));
+
+        let path =
+            std::env::temp_dir().join(format!("logscout-symbols-{}.log", std::process::id()));
+        std::fs::write(&path, "BOOM\n").unwrap();
+        server.settings.write().await.global.max_file_size_mb = 0;
+        assert!(server.workspace_file_detections(&path).await.is_none());
+
+        server.workspace_detections.clear();
+        server.settings.write().await.global.max_file_size_mb = 1;
+        assert_eq!(
+            server
+                .workspace_file_detections(&path)
+                .await
+                .map(|d| d.len()),
+            Some(1)
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_followed_signature_across_polls() {
+        let (service, mut socket) = LspService::new(LogScoutServer::new);
+        tokio::spawn(async move { while socket.next().await.is_some() {} });
+        let server = service.inner();
+        let engine = PatternEngine::new(
+            vec![
+                Pattern::test_with("vpn-reconnect", "VPN reconnect", Severity::Info, "vpn"),
+                Pattern::test_with("sip-401", "SIP/2.0 401", Severity::Warning, "vpn"),
+            ],
+            0.85,
+            10,
+        )
+        .unwrap();
+        let engine = Arc::new(engine);
+        *server.pattern_engine.write().await = Some(Arc::clone(&engine));
+        *server.pipeline.write().await = Arc::new(AnalysisPipeline::standard(
+            SignatureEngine::new(vec![crate::signature::SignatureDefinition {
+                id: "vpn-registration-loop".to_string(),
+                name: "VPN-induced registration loop".to_string(),
+                description: String::new(),
+                category: "vpn".to_string(),
+                patterns: vec!["vpn-reconnect".to_string(), "sip-401".to_string()],
+                window_ms: 10_000,
+                window_lines: 200,
+                severity: None,
+                action: None,
+            }]),
+            ProcessEngine::default(),
+            ScenarioEngine::default(),
+        ));
+
+        // The members are read in two polls
+        let uri = Url::parse("file:///var/log/jabber.log").unwrap();
+        for (line, text) in ["VPN reconnect", "SIP/2.0 401"].into_iter().enumerate() {
+            let results = AnalysisResults {
+                detections: engine.process_line(text, line),
+                ..AnalysisResults::default()
+            };
+            server.append_results(&uri, results, false);
+        }
+        let detections = server.detections.get(&uri).map(|d| Arc::clone(&d)).unwrap();
+        server
+            .correlate_followed(&uri, &detections, &Settings::default())
+            .await;
+
+        assert_eq!(server.signatures.get(&uri).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,198 @@
+//! Severity Policy
+//!
+//! User-level rules that remap the evaluated severity of detections per
+//! pattern ID, product or category, e.g. "SIP 401s are only warnings for
+//! Jabber". The most specific matching rule wins and the detection records
+//! which rule changed it.
+
+use crate::pattern_engine::{Detection, Severity, SeveritySource};
+use serde::{Deserialize, Serialize};
+
+/// One remapping rule; every field that is set must match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeverityRule {
+    /// Pattern ID the rule applies to
+    #[serde(default)]
+    pub pattern_id: Option<String>,
+
+    /// Pattern category the rule applies to
+    #[serde(default)]
+    pub category: Option<String>,
+
+    /// Product (the pattern's service) the rule applies to, ignoring case
+    #[serde(default)]
+    pub product: Option<String>,
+
+    /// Only remap detections evaluated at this severity
+    #[serde(default)]
+    pub from: Option<Severity>,
+
+    /// Severity to use instead
+    pub severity: Severity,
+}
+
+impl SeverityRule {
+    /// Whether the rule applies to a detection
+    pub fn matches(&self, detection: &Detection) -> bool {
+        let pattern = &detection.pattern;
+        self.pattern_id.as_ref().is_none_or(|id| *id == pattern.id)
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| *category == pattern.category)
+            && self.product.as_ref().is_none_or(|product| {
+                pattern
+                    .service
+                    .as_ref()
+                    .is_some_and(|service| service.eq_ignore_ascii_case(product))
+            })
+            && self
+                .from
+                .is_none_or(|from| from == detection.final_severity)
+    }
+
+    /// Rank used to pick among matching rules: pattern ID beats product,
+    /// which beats category
+    pub fn specificity(&self) -> u8 {
+        let mut specificity = 0;
+        if self.pattern_id.is_some() {
+            specificity += 4;
+        }
+        if self.product.is_some() {
+            specificity += 2;
+        }
+        if self.category.is_some() {
+            specificity += 1;
+        }
+        specificity
+    }
+
+    /// Short description of what the rule matches, e.g. `product=jabber`
+    pub fn scope(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(id) = &self.pattern_id {
+            parts.push(format!("pattern_id={}", id));
+        }
+        if let Some(product) = &self.product {
+            parts.push(format!("product={}", product));
+        }
+        if let Some(category) = &self.category {
+            parts.push(format!("category={}", category));
+        }
+        if let Some(from) = self.from {
+            parts.push(format!("from={}", from.as_str()));
+        }
+        if parts.is_empty() {
+            "all".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Most specific rule matching a detection, with its index in `rules`
+///
+/// Ties go to the rule listed first.
+pub fn matching_rule<'a>(
+    rules: &'a [SeverityRule],
+    detection: &Detection,
+) -> Option<(usize, &'a SeverityRule)> {
+    rules
+        .iter()
+        .enumerate()
+        .filter(|(_, rule)| rule.matches(detection))
+        .fold(None, |best, (index, rule)| match best {
+            Some((_, current)) if current.specificity() >= rule.specificity() => best,
+            _ => Some((index, rule)),
+        })
+}
+
+/// Remap the final severity of detections matched by a rule
+pub fn apply_policy(rules: &[SeverityRule], detections: &mut [Detection]) {
+    if rules.is_empty() {
+        return;
+    }
+
+    for detection in detections {
+        if let Some((index, rule)) = matching_rule(rules, detection) {
+            detection.severity_source = SeveritySource::Policy {
+                rule: index,
+                scope: rule.scope(),
+                original: detection.final_severity,
+            };
+            detection.final_severity = rule.severity;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+
+    fn detections() -> Vec<Detection> {
+        let pattern = |id: &str, regex: &str, service: Option<&str>| Pattern {
+            category: "registration".to_string(),
+            service: service.map(str::to_string),
+            ..Pattern::test(id, regex)
+        };
+        detect_lines(
+            vec![
+                pattern("sip-401", "SIP/2.0 401", Some("Jabber")),
+                pattern("sip-403", "SIP/2.0 403", None),
+            ],
+            "SIP/2.0 401\nSIP/2.0 403",
+        )
+    }
+
+    #[test]
+    fn test_most_specific_rule_wins() {
+        let rules = vec![
+            SeverityRule {
+                pattern_id: None,
+                category: Some("registration".to_string()),
+                product: None,
+                from: None,
+                severity: Severity::Info,
+            },
+            SeverityRule {
+                pattern_id: None,
+                category: None,
+                product: Some("jabber".to_string()),
+                from: Some(Severity::Error),
+                severity: Severity::Warning,
+            },
+        ];
+        let mut detections = detections();
+        apply_policy(&rules, &mut detections);
+
+        assert_eq!(detections[0].final_severity, Severity::Warning);
+        assert_eq!(
+            detections[0].severity_source,
+            SeveritySource::Policy {
+                rule: 1,
+                scope: "product=jabber, from=error".to_string(),
+                original: Severity::Error,
+            }
+        );
+        assert_eq!(detections[1].final_severity, Severity::Info);
+    }
+
+    #[test]
+    fn test_unmatched_detections_keep_their_severity() {
+        let rules = vec![SeverityRule {
+            pattern_id: Some("sip-401".to_string()),
+            category: None,
+            product: None,
+            from: Some(Severity::Warning),
+            severity: Severity::Hint,
+        }];
+        let mut detections = detections();
+        apply_policy(&rules, &mut detections);
+
+        assert!(detections
+            .iter()
+            .all(|d| d.final_severity == Severity::Error
+                && d.severity_source == SeveritySource::Default));
+    }
+}
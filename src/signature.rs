@@ -0,0 +1,315 @@
+//! Signature Detection
+//!
+//! A signature is a named set of patterns from one category that must all
+//! match within a time (or line) window, e.g. a "VPN-induced registration
+//! loop" made of a VPN reconnect, a SIP 401 and a re-register. Matching
+//! signatures become higher-level diagnostics that point back at the
+//! detections they were built from.
+
+use crate::pattern_engine::{line_range, Detection, Severity};
+use crate::payload::{DiagnosticPayload, SignaturePayload};
+use crate::timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Location, NumberOrString, Url,
+};
+
+/// Definition of a signature, as written in a pattern pack's `signatures:` list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignatureDefinition {
+    /// Unique identifier
+    pub id: String,
+
+    /// Human-readable name
+    pub name: String,
+
+    /// What the signature means
+    #[serde(default)]
+    pub description: String,
+
+    /// Category shared by all member patterns
+    pub category: String,
+
+    /// IDs of the patterns that must all match
+    pub patterns: Vec<String>,
+
+    /// Maximum time between the first and last member (in milliseconds)
+    #[serde(default = "default_window_ms")]
+    pub window_ms: i64,
+
+    /// Maximum lines between the first and last member, used when the
+    /// detections carry no timestamps
+    #[serde(default = "default_window_lines")]
+    pub window_lines: usize,
+
+    /// Severity of the signature diagnostic (defaults to the most severe member)
+    #[serde(default)]
+    pub severity: Option<Severity>,
+
+    /// Suggested action or remediation
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+fn default_window_ms() -> i64 {
+    60_000
+}
+
+fn default_window_lines() -> usize {
+    200
+}
+
+/// One occurrence of a signature in a document
+#[derive(Debug, Clone)]
+pub struct SignatureMatch {
+    pub signature: Arc<SignatureDefinition>,
+
+    /// The first detection of each member pattern, in line order
+    pub detections: Vec<Detection>,
+}
+
+impl SignatureMatch {
+    /// Line and column range of the earliest member detection
+    pub fn anchor(&self) -> (usize, (usize, usize)) {
+        let first = &self.detections[0];
+        (first.line_number, first.column_range)
+    }
+
+    /// Severity of the signature diagnostic
+    pub fn severity(&self) -> Severity {
+        self.signature.severity.unwrap_or_else(|| {
+            self.detections
+                .iter()
+                .map(|d| d.final_severity)
+                .max_by_key(Severity::rank)
+                .unwrap_or(Severity::Warning)
+        })
+    }
+
+    /// Build the diagnostic for this signature in `uri`
+    ///
+    /// The member detections are listed as related information.
+    pub fn to_diagnostic(&self, uri: &Url) -> Diagnostic {
+        let (line, (start, end)) = self.anchor();
+        let severity = DiagnosticSeverity::from(self.severity());
+
+        let message = if self.signature.description.is_empty() {
+            self.signature.name.clone()
+        } else {
+            format!("{}: {}", self.signature.name, self.signature.description)
+        };
+
+        let related = self
+            .detections
+            .iter()
+            .map(|detection| DiagnosticRelatedInformation {
+                location: Location {
+                    uri: uri.clone(),
+                    range: line_range(detection.line_number, detection.column_range),
+                },
+                message: format!(
+                    "{} (line {})",
+                    detection.pattern.name,
+                    detection.line_number + 1
+                ),
+            })
+            .collect();
+
+        Diagnostic {
+            range: line_range(line, (start, end)),
+            severity: Some(severity),
+            code: Some(NumberOrString::String(self.signature.id.clone())),
+            code_description: None,
+            source: Some("log-scout".to_string()),
+            message,
+            related_information: Some(related),
+            tags: None,
+            data: Some(DiagnosticPayload::Signature(SignaturePayload::new(self)).to_value()),
+        }
+    }
+}
+
+/// Finds signature occurrences among a document's detections
+#[derive(Debug, Default)]
+pub struct SignatureEngine {
+    signatures: Vec<Arc<SignatureDefinition>>,
+}
+
+impl SignatureEngine {
+    /// Create an engine; signatures without member patterns are ignored
+    pub fn new(signatures: Vec<SignatureDefinition>) -> Self {
+        Self {
+            signatures: signatures
+                .into_iter()
+                .filter(|signature| !signature.patterns.is_empty())
+                .map(Arc::new)
+                .collect(),
+        }
+    }
+
+    /// Number of loaded signatures
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Whether no signatures are loaded
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Find all signature occurrences in a document's detections
+    pub fn detect(&self, detections: &[Detection]) -> Vec<SignatureMatch> {
+        let mut matches = Vec::new();
+        for signature in &self.signatures {
+            matches.extend(detect_signature(signature, detections));
+        }
+
+        matches.sort_by_key(|m| m.anchor());
+        matches
+    }
+}
+
+/// Non-overlapping occurrences of one signature
+///
+/// Starting from each unused member detection, the window is scanned for
+/// the first detection of every member pattern. Detections used by one
+/// occurrence are not reused by the next.
+fn detect_signature(
+    signature: &Arc<SignatureDefinition>,
+    detections: &[Detection],
+) -> Vec<SignatureMatch> {
+    let mut members: Vec<&Detection> = detections
+        .iter()
+        .filter(|d| {
+            d.pattern.category == signature.category && signature.patterns.contains(&d.pattern.id)
+        })
+        .collect();
+    members.sort_by_key(|d| (d.line_number, d.column_range.0));
+
+    let times: Vec<Option<i64>> = members
+        .iter()
+        .map(|d| {
+            d.timestamp
+                .as_deref()
+                .and_then(timestamp::extract_timestamp)
+                .map(|ts| ts.datetime.and_utc().timestamp_millis())
+        })
+        .collect();
+
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start < members.len() {
+        let mut found: HashMap<&str, usize> = HashMap::new();
+        let mut end = start;
+
+        for index in start..members.len() {
+            let within = match (times[start], times[index]) {
+                (Some(first), Some(current)) => current - first <= signature.window_ms,
+                _ => {
+                    members[index].line_number - members[start].line_number
+                        <= signature.window_lines
+                }
+            };
+            if !within {
+                break;
+            }
+
+            found
+                .entry(members[index].pattern.id.as_str())
+                .or_insert(index);
+            end = index;
+            if found.len() == signature.patterns.len() {
+                break;
+            }
+        }
+
+        if found.len() == signature.patterns.len() {
+            let mut indices: Vec<usize> = found.into_values().collect();
+            indices.sort_unstable();
+            matches.push(SignatureMatch {
+                signature: Arc::clone(signature),
+                detections: indices.iter().map(|&i| members[i].clone()).collect(),
+            });
+            start = end + 1;
+        } else {
+            start += 1;
+        }
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+
+    fn engine() -> SignatureEngine {
+        SignatureEngine::new(vec![SignatureDefinition {
+            id: "vpn-registration-loop".to_string(),
+            name: "VPN-induced registration loop".to_string(),
+            description: String::new(),
+            category: "registration".to_string(),
+            patterns: vec!["vpn-reconnect".to_string(), "sip-401".to_string()],
+            window_ms: 10_000,
+            window_lines: 200,
+            severity: None,
+            action: None,
+        }])
+    }
+
+    #[test]
+    fn test_signature_within_time_window() {
+        let detections = detect_lines(
+            vec![
+                Pattern::test_with(
+                    "vpn-reconnect",
+                    "VPN reconnect",
+                    Severity::Info,
+                    "registration",
+                ),
+                Pattern::test_with("sip-401", "SIP/2.0 401", Severity::Warning, "registration"),
+            ],
+            "2024-01-15 10:00:00,000 VPN reconnect\n\
+             2024-01-15 10:00:02,000 SIP/2.0 401 Unauthorized\n\
+             2024-01-15 11:00:00,000 VPN reconnect\n\
+             2024-01-15 11:05:00,000 SIP/2.0 401 Unauthorized",
+        );
+        let matches = engine().detect(&detections);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].anchor().0, 0);
+        assert_eq!(matches[0].severity(), Severity::Warning);
+
+        let uri = Url::parse("file:///logs/jabber.log").unwrap();
+        let diagnostic = matches[0].to_diagnostic(&uri);
+        assert_eq!(diagnostic.message, "VPN-induced registration loop");
+        let related = diagnostic.related_information.unwrap();
+        assert_eq!(related.len(), 2);
+        assert_eq!(related[1].message, "sip-401 (line 2)");
+    }
+
+    #[test]
+    fn test_signature_line_window_without_timestamps() {
+        let patterns = vec![
+            Pattern::test_with(
+                "vpn-reconnect",
+                "VPN reconnect",
+                Severity::Info,
+                "registration",
+            ),
+            Pattern::test_with("sip-401", "SIP/2.0 401", Severity::Warning, "registration"),
+        ];
+        let detections = detect_lines(
+            patterns.clone(),
+            "VPN reconnect\nSIP/2.0 401\nVPN reconnect\nSIP/2.0 401",
+        );
+        assert_eq!(engine().detect(&detections).len(), 2);
+
+        // A missing member means no signature
+        let detections = detect_lines(patterns, "VPN reconnect\nVPN reconnect");
+        assert!(engine().detect(&detections).is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+//! Suppression of Known Noise
+//!
+//! Rules read from `.logscout-ignore` files in the workspace folders mute
+//! detections that are known to be harmless. Each non-empty line is one
+//! rule made of whitespace-separated conditions, all of which must match:
+//!
+//! ```text
+//! # Mute a pattern everywhere
+//! sip-401
+//! # Mute a category in one log, until the end of the year
+//! category:registration file:logs/jabber.log expires:2026-12-31
+//! # Mute a pattern for one extracted parameter value
+//! pattern:http-error status=404
+//! ```
+//!
+//! A bare word (or `pattern:`) is a pattern ID, `file:` paths are relative
+//! to the ignore file, `name=value` compares an extracted parameter, and
+//! rules past their `expires:` date no longer apply.
+
+use crate::pattern_engine::Detection;
+use crate::payload::DetectionPayload;
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tower_lsp::lsp_types::Url;
+
+/// Name of the ignore file in a workspace folder
+pub const IGNORE_FILE: &str = ".logscout-ignore";
+
+/// Command adding a rule muting a pattern (in one file or everywhere)
+pub const MUTE_COMMAND: &str = "logScout.mute";
+
+/// Command listing the suppression rules and what they suppress
+pub const LIST_SUPPRESSIONS_COMMAND: &str = "logScout.listSuppressions";
+
+/// Errors in an ignore file
+#[derive(Debug, Error)]
+pub enum SuppressionError {
+    #[error("Line {line}: {message}")]
+    InvalidRule { line: usize, message: String },
+
+    #[error("Cannot write '{0}' to an ignore file: it contains whitespace")]
+    Unwritable(String),
+}
+
+/// How suppressed detections are shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionMode {
+    /// Drop them from the diagnostics
+    #[default]
+    Hide,
+
+    /// Keep them, tagged as unnecessary (rendered faded by most editors)
+    Dim,
+}
+
+/// One rule of an ignore file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SuppressionRule {
+    pub pattern_id: Option<String>,
+    pub category: Option<String>,
+
+    /// Log file the rule is limited to
+    pub file: Option<PathBuf>,
+
+    /// Extracted parameters that must have these values
+    pub parameters: Vec<(String, String)>,
+
+    /// Last day the rule applies
+    pub expires: Option<NaiveDate>,
+
+    /// Ignore file and 1-based line the rule was read from
+    pub source: Option<PathBuf>,
+    pub line: usize,
+}
+
+impl SuppressionRule {
+    /// Rule muting a pattern, optionally only in one log file
+    pub fn mute(pattern_id: &str, file: Option<PathBuf>) -> Self {
+        Self {
+            pattern_id: Some(pattern_id.to_string()),
+            category: None,
+            file,
+            parameters: Vec::new(),
+            expires: None,
+            source: None,
+            line: 0,
+        }
+    }
+
+    /// Parse one line of an ignore file; `base` resolves `file:` paths
+    ///
+    /// Returns `None` for blank lines and comments.
+    pub fn parse(text: &str, base: &Path) -> Result<Option<Self>, String> {
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            return Ok(None);
+        }
+
+        let mut rule = Self {
+            pattern_id: None,
+            category: None,
+            file: None,
+            parameters: Vec::new(),
+            expires: None,
+            source: None,
+            line: 0,
+        };
+
+        for token in text.split_whitespace() {
+            if let Some(id) = token.strip_prefix("pattern:") {
+                rule.pattern_id = Some(id.to_string());
+            } else if let Some(category) = token.strip_prefix("category:") {
+                rule.category = Some(category.to_string());
+            } else if let Some(file) = token.strip_prefix("file:") {
+                rule.file = Some(base.join(file));
+            } else if let Some(date) = token.strip_prefix("expires:") {
+                let expires = NaiveDate::parse_from_str(date, "%Y-%m-%d");
+                rule.expires = Some(expires.map_err(|_| {
+                    format!("Invalid expiry date '{}' (expected YYYY-MM-DD)", date)
+                })?);
+            } else if let Some((name, value)) = token.split_once('=') {
+                rule.parameters.push((name.to_string(), value.to_string()));
+            } else if token.contains(':') {
+                return Err(format!("Unknown condition '{}'", token));
+            } else {
+                rule.pattern_id = Some(token.to_string());
+            }
+        }
+
+        if rule.pattern_id.is_none() && rule.category.is_none() && rule.parameters.is_empty() {
+            return Err("A rule needs a pattern ID, a category or a parameter".to_string());
+        }
+        Ok(Some(rule))
+    }
+
+    /// Whether the rule no longer applies on `today`
+    pub fn is_expired(&self, today: NaiveDate) -> bool {
+        self.expires.is_some_and(|expires| today > expires)
+    }
+
+    /// Whether the rule mutes a detection in the log at `path`
+    pub fn matches(&self, detection: &Detection, path: Option<&Path>, today: NaiveDate) -> bool {
+        !self.is_expired(today)
+            && self
+                .pattern_id
+                .as_ref()
+                .is_none_or(|id| *id == detection.pattern.id)
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| *category == detection.pattern.category)
+            && self.file.as_ref().is_none_or(|file| path == Some(file))
+            && self.parameters.iter().all(|(name, value)| {
+                detection
+                    .field_values
+                    .get(name)
+                    .is_some_and(|actual| actual == value)
+            })
+    }
+
+    /// The rule as a line of an ignore file in `base`
+    ///
+    /// Fails if a condition contains whitespace, which would split it when
+    /// the line is read back.
+    pub fn to_line(&self, base: &Path) -> Result<String, SuppressionError> {
+        let mut tokens = Vec::new();
+        if let Some(id) = &self.pattern_id {
+            tokens.push(format!("pattern:{}", id));
+        }
+        if let Some(category) = &self.category {
+            tokens.push(format!("category:{}", category));
+        }
+        if let Some(file) = &self.file {
+            let file = file.strip_prefix(base).unwrap_or(file);
+            tokens.push(format!("file:{}", file.display()));
+        }
+        for (name, value) in &self.parameters {
+            tokens.push(format!("{}={}", name, value));
+        }
+        if let Some(expires) = self.expires {
+            tokens.push(format!("expires:{}", expires.format("%Y-%m-%d")));
+        }
+        if let Some(token) = tokens
+            .iter()
+            .find(|token| token.contains(char::is_whitespace))
+        {
+            return Err(SuppressionError::Unwritable(token.clone()));
+        }
+        Ok(tokens.join(" "))
+    }
+}
+
+/// Parse the content of an ignore file located in `base`
+///
+/// Returns the valid rules and an error for each invalid line.
+pub fn parse_ignore_file(
+    content: &str,
+    base: &Path,
+) -> (Vec<SuppressionRule>, Vec<SuppressionError>) {
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+    for (index, text) in content.lines().enumerate() {
+        match SuppressionRule::parse(text, base) {
+            Ok(Some(mut rule)) => {
+                rule.source = Some(base.join(IGNORE_FILE));
+                rule.line = index + 1;
+                rules.push(rule);
+            }
+            Ok(None) => {}
+            Err(message) => errors.push(SuppressionError::InvalidRule {
+                line: index + 1,
+                message,
+            }),
+        }
+    }
+    (rules, errors)
+}
+
+/// A detection muted by a rule
+#[derive(Debug, Clone)]
+pub struct Suppressed {
+    pub detection: Detection,
+    pub rule: SuppressionRule,
+
+    /// Whether it is still published (tagged as unnecessary)
+    pub visible: bool,
+}
+
+/// Result of `logScout.listSuppressions`
+#[derive(Debug, Clone, Serialize)]
+pub struct SuppressionList {
+    pub rules: Vec<ListedRule>,
+}
+
+/// A rule with the detections it mutes in open documents
+#[derive(Debug, Clone, Serialize)]
+pub struct ListedRule {
+    #[serde(flatten)]
+    pub rule: SuppressionRule,
+    pub expired: bool,
+    pub occurrences: Vec<SuppressedOccurrence>,
+}
+
+/// A muted detection, described by the payload of its diagnostic
+#[derive(Debug, Clone, Serialize)]
+pub struct SuppressedOccurrence {
+    pub uri: Url,
+    pub line: usize,
+
+    #[serde(flatten)]
+    pub payload: DetectionPayload,
+}
+
+/// Rules from all ignore files of the workspace
+#[derive(Debug, Clone, Default)]
+pub struct Suppressions {
+    rules: Vec<SuppressionRule>,
+}
+
+impl Suppressions {
+    pub fn new(rules: Vec<SuppressionRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Read the ignore file of each workspace folder
+    ///
+    /// Invalid rules are skipped with a warning.
+    pub fn load(roots: &[PathBuf]) -> Self {
+        let mut rules = Vec::new();
+        for root in roots {
+            let path = root.join(IGNORE_FILE);
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let (parsed, errors) = parse_ignore_file(&content, root);
+            for e in errors {
+                tracing::warn!("Skipping a rule of {}: {}", path.display(), e);
+            }
+            rules.extend(parsed);
+        }
+        Self { rules }
+    }
+
+    /// All rules, including expired ones
+    pub fn rules(&self) -> &[SuppressionRule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// First rule muting a detection
+    pub fn find(
+        &self,
+        detection: &Detection,
+        path: Option<&Path>,
+        today: NaiveDate,
+    ) -> Option<&SuppressionRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(detection, path, today))
+    }
+
+    /// Move the muted detections out of `detections`
+    pub fn apply(
+        &self,
+        detections: &mut Vec<Detection>,
+        path: Option<&Path>,
+        mode: SuppressionMode,
+        today: NaiveDate,
+    ) -> Vec<Suppressed> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+
+        let mut suppressed = Vec::new();
+        detections.retain(|detection| match self.find(detection, path, today) {
+            Some(rule) => {
+                suppressed.push(Suppressed {
+                    detection: detection.clone(),
+                    rule: rule.clone(),
+                    visible: mode == SuppressionMode::Dim,
+                });
+                false
+            }
+            None => true,
+        });
+        suppressed
+    }
+}
+
+/// Append a rule to the ignore file of `root`, returning the file's path
+pub fn append_rule(root: &Path, rule: &SuppressionRule) -> io::Result<PathBuf> {
+    let line = rule
+        .to_line(root)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let path = root.join(IGNORE_FILE);
+    let needs_newline = fs::read_to_string(&path)
+        .map(|content| !content.is_empty() && !content.ends_with('\n'))
+        .unwrap_or(false);
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    writeln!(file, "{}", line)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, Pattern};
+
+    fn http_detections() -> Vec<Detection> {
+        detect_lines(
+            vec![Pattern {
+                name: "HTTP error".to_string(),
+                category: "network".to_string(),
+                capture_fields: vec!["status".to_string()],
+                ..Pattern::test("http-error", r"HTTP (?P<status>\d+)")
+            }],
+            "HTTP 404\nHTTP 500",
+        )
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_ignore_file() {
+        let base = Path::new("/work");
+        let (rules, errors) = parse_ignore_file(
+            "# noise\n\nsip-401\ncategory:network file:logs/a.log expires:2026-12-31\npattern:http-error status=404\n",
+            base,
+        );
+
+        assert!(errors.is_empty());
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0].pattern_id.as_deref(), Some("sip-401"));
+        assert_eq!(rules[0].line, 3);
+        assert_eq!(rules[1].file, Some(PathBuf::from("/work/logs/a.log")));
+        assert_eq!(
+            rules[1].to_line(base).unwrap(),
+            "category:network file:logs/a.log expires:2026-12-31"
+        );
+        assert_eq!(
+            rules[2].to_line(base).unwrap(),
+            "pattern:http-error status=404"
+        );
+        assert_eq!(
+            rules[2].parameters,
+            vec![("status".to_string(), "404".to_string())]
+        );
+
+        // Only the invalid line is skipped
+        let (rules, errors) = parse_ignore_file("sip-401\nexpires:soon\nsip-403", base);
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].line, 3);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().starts_with("Line 2:"));
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let base = Path::new("/work");
+        let log = PathBuf::from("/work/logs/a.log");
+        let suppressions = Suppressions::new(
+            parse_ignore_file(
+                "http-error status=404\ncategory:network file:logs/b.log\nhttp-error expires:2026-01-01",
+                base,
+            )
+            .0,
+        );
+
+        let mut detections = http_detections();
+        let suppressed = suppressions.apply(
+            &mut detections,
+            Some(&log),
+            SuppressionMode::Dim,
+            date("2026-10-18"),
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].field_values["status"], "500");
+        assert_eq!(suppressed.len(), 1);
+        assert_eq!(suppressed[0].rule.line, 1);
+        assert!(suppressed[0].visible);
+
+        // Before the expiry date the last rule mutes everything
+        let mut detections = http_detections();
+        suppressions.apply(
+            &mut detections,
+            Some(&log),
+            SuppressionMode::Hide,
+            date("2025-12-31"),
+        );
+        assert!(detections.is_empty());
+    }
+
+    #[test]
+    fn test_append_mute_rule() {
+        let root = std::env::temp_dir().join(format!("logscout-ignore-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(IGNORE_FILE), "sip-401").unwrap();
+
+        let rule = SuppressionRule::mute("http-error", Some(root.join("logs/a.log")));
+        append_rule(&root, &rule).unwrap();
+
+        let suppressions = Suppressions::load(std::slice::from_ref(&root));
+        assert_eq!(suppressions.rules().len(), 2);
+        assert_eq!(suppressions.rules()[1].file, Some(root.join("logs/a.log")));
+
+        // A path with whitespace would not read back as one condition
+        let rule = SuppressionRule::mute("http-error", Some(root.join("my logs/a.log")));
+        let error = append_rule(&root, &rule).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            Suppressions::load(std::slice::from_ref(&root))
+                .rules()
+                .len(),
+            2
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -34,6 +34,9 @@ pub enum CacheError {
     InvalidFormat(String),
 }
 
+/// TagScout enum tables by name, each mapping raw values to their meaning
+pub type EnumTables = HashMap<String, HashMap<String, String>>;
+
 /// Cache metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheMetadata {
@@ -99,6 +102,10 @@ pub struct PatternCache {
 
     /// Cached patterns by ID
     pub patterns: HashMap<String, CachedPattern>,
+
+    /// TagScout enum tables by name
+    #[serde(default)]
+    pub enums: EnumTables,
 }
 
 impl PatternCache {
@@ -117,6 +124,7 @@ impl PatternCache {
                 categories: Vec::new(),
             },
             patterns: HashMap::new(),
+            enums: HashMap::new(),
         }
     }
 
@@ -203,6 +211,11 @@ impl PatternCache {
             .collect()
     }
 
+    /// Replace the cached enum tables
+    pub fn set_enums(&mut self, enums: EnumTables) {
+        self.enums = enums;
+    }
+
     /// Check if cache is expired
     pub fn is_expired(&self) -> bool {
         let elapsed = Utc::now()
@@ -232,8 +245,8 @@ impl PatternCache {
             .patterns
             .values()
             .flat_map(|cp| cp.annotation.category.iter())
-            .map(|c| c.clone())
             .filter(|c| !c.is_empty())
+            .cloned()
             .collect();
         categories.sort();
         categories.dedup();
@@ -394,20 +407,7 @@ impl CacheManager {
         &mut self,
         patterns: Vec<(TagScoutAnnotation, Pattern)>,
     ) -> Result<(), CacheError> {
-        {
-            let cache = self.cache.get_or_insert_with(|| {
-                PatternCache::new(
-                    self.ttl_seconds,
-                    CacheSource {
-                        connection_info: "unknown".to_string(),
-                        database: "unknown".to_string(),
-                        collection: "unknown".to_string(),
-                    },
-                )
-            });
-
-            cache.add_patterns(patterns);
-        }
+        self.cache_mut().add_patterns(patterns);
 
         if self.auto_save {
             if let Some(cache) = &self.cache {
@@ -418,6 +418,27 @@ impl CacheManager {
         Ok(())
     }
 
+    /// Replace the enum tables of the current cache (saved with the next update)
+    pub fn set_enums(&mut self, enums: EnumTables) {
+        self.cache_mut().set_enums(enums);
+    }
+
+    /// The current cache, created empty (with an unknown source) if none
+    /// was loaded
+    fn cache_mut(&mut self) -> &mut PatternCache {
+        let ttl_seconds = self.ttl_seconds;
+        self.cache.get_or_insert_with(|| {
+            PatternCache::new(
+                ttl_seconds,
+                CacheSource {
+                    connection_info: "unknown".to_string(),
+                    database: "unknown".to_string(),
+                    collection: "unknown".to_string(),
+                },
+            )
+        })
+    }
+
     /// Get current cache
     pub fn get_cache(&self) -> Option<&PatternCache> {
         self.cache.as_ref()
@@ -505,28 +526,28 @@ pub struct CacheStats {
 mod tests {
     use super::*;
     use crate::pattern_engine::{PatternMode, Severity};
+    use crate::tagscout::client::TagScoutParameter;
 
     fn create_test_annotation() -> TagScoutAnnotation {
         TagScoutAnnotation {
             id: bson::oid::ObjectId::new(),
-            name: "Test Error".to_string(),
-            description: "Test description".to_string(),
-            pattern: r"ERROR:\s+(.+)".to_string(),
+            raw_data: "2024-01-15 10:30:45 ERROR: Test Error Pattern".to_string(),
+            regexes: vec![r"ERROR:\s+(.+)".to_string()],
             severity: "error".to_string(),
-            category: "errors".to_string(),
-            product: "test-product".to_string(),
-            component: "test-component".to_string(),
-            tags: vec!["test".to_string()],
-            action: "Check logs".to_string(),
-            kb_id: "KB123".to_string(),
-            bug_id: "BUG456".to_string(),
-            version_introduced: "1.0".to_string(),
-            version_fixed: "1.1".to_string(),
-            active: true,
-            last_updated: Some(bson::DateTime::now()),
-            created_at: Some(bson::DateTime::now()),
-            author: "test-author".to_string(),
-            metadata: None,
+            category: vec!["errors".to_string(), "test".to_string()],
+            template: "Test error: {{ MESSAGE }}".to_string(),
+            production: true,
+            content: false,
+            documentation: "Check logs for details".to_string(),
+            internal_notes: String::new(),
+            multiline: None,
+            external: false,
+            borg: false,
+            parameters: vec![TagScoutParameter {
+                name: "MESSAGE".to_string(),
+                regex: r"ERROR:\s+(.+)".to_string(),
+                r#enum: "None".to_string(),
+            }],
         }
     }
 
@@ -548,7 +569,9 @@ mod tests {
             condition_triggers: Vec::new(),
             capture_fields: Vec::new(),
             parameter_extractors: Vec::new(),
+            priority: 0,
             tagscout_metadata: None,
+            source: None,
         }
     }
 
@@ -241,7 +241,7 @@ impl TagScoutClient {
             .database("admin")
             .run_command(doc! { "ping": 1 }, None)
             .await
-            .map_err(|e| TagScoutError::ConnectionError(e))?;
+            .map_err(TagScoutError::ConnectionError)?;
 
         tracing::info!("Successfully connected to TagScout MongoDB");
         Ok(())
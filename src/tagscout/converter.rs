@@ -170,6 +170,7 @@ impl PatternConverter {
             .map(|p| crate::pattern_engine::ParameterExtractor {
                 name: p.name.clone(),
                 regex: p.regex.clone(),
+                enum_name: Self::enum_name(&p.r#enum),
             })
             .collect();
 
@@ -193,7 +194,9 @@ impl PatternConverter {
             condition_triggers: Vec::new(),
             capture_fields,
             parameter_extractors,
+            priority: 0,
             tagscout_metadata,
+            source: None,
         })
     }
 
@@ -281,7 +284,7 @@ impl PatternConverter {
                 let name_part = parts[3..]
                     .iter()
                     .take(5)
-                    .map(|s| *s)
+                    .copied()
                     .collect::<Vec<&str>>()
                     .join(" ");
                 if name_part.len() > 50 {
@@ -303,6 +306,16 @@ impl PatternConverter {
         }
     }
 
+    /// Normalize a TagScout parameter enum reference ("None" means no enum)
+    fn enum_name(enum_ref: &str) -> Option<String> {
+        let enum_ref = enum_ref.trim();
+        if enum_ref.is_empty() || enum_ref.eq_ignore_ascii_case("none") {
+            None
+        } else {
+            Some(enum_ref.to_string())
+        }
+    }
+
     /// Convert severity string to Severity enum
     fn convert_severity(&self, severity_str: &str) -> Result<Severity, ConversionError> {
         // Check custom mapping first
@@ -460,28 +473,28 @@ pub fn convert_with_result(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tagscout::client::TagScoutParameter;
 
     fn create_test_annotation() -> TagScoutAnnotation {
         TagScoutAnnotation {
             id: bson::oid::ObjectId::new(),
-            name: "Test Error Pattern".to_string(),
-            description: "Detects test errors".to_string(),
-            pattern: r"ERROR:\s+(.+)".to_string(),
+            raw_data: "2024-01-15 10:30:45 ERROR: Test Error Pattern".to_string(),
+            regexes: vec![r"ERROR:\s+(.+)".to_string()],
             severity: "error".to_string(),
-            category: "errors".to_string(),
-            product: "test-product".to_string(),
-            component: "test-component".to_string(),
-            tags: vec!["test".to_string()],
-            action: "Check logs for details".to_string(),
-            kb_id: "KB12345".to_string(),
-            bug_id: "BUG67890".to_string(),
-            version_introduced: "1.0.0".to_string(),
-            version_fixed: "1.1.0".to_string(),
-            active: true,
-            last_updated: Some(bson::DateTime::now()),
-            created_at: Some(bson::DateTime::now()),
-            author: "test-author".to_string(),
-            metadata: None,
+            category: vec!["errors".to_string(), "test".to_string()],
+            template: "Test error: {{ MESSAGE }}".to_string(),
+            production: true,
+            content: false,
+            documentation: "Check logs for details".to_string(),
+            internal_notes: String::new(),
+            multiline: None,
+            external: false,
+            borg: false,
+            parameters: vec![TagScoutParameter {
+                name: "MESSAGE".to_string(),
+                regex: r"ERROR:\s+(.+)".to_string(),
+                r#enum: "None".to_string(),
+            }],
         }
     }
 
@@ -490,13 +503,32 @@ mod tests {
         let converter = PatternConverter::new();
         let annotation = create_test_annotation();
 
-        let result = converter.convert(&annotation);
+        let result = converter.convert(&annotation, Some("test-product"));
         assert!(result.is_ok());
 
         let pattern = result.unwrap();
         assert_eq!(pattern.name, "Test Error Pattern");
         assert_eq!(pattern.severity, Severity::Error);
         assert_eq!(pattern.category, "errors");
+        assert_eq!(pattern.service.as_deref(), Some("test-product"));
+    }
+
+    #[test]
+    fn test_parameter_enum_reference() {
+        let converter = PatternConverter::new();
+        let mut annotation = create_test_annotation();
+        annotation.parameters.push(TagScoutParameter {
+            name: "STATE".to_string(),
+            regex: r"state=(\d+)".to_string(),
+            r#enum: "RegistrationState".to_string(),
+        });
+
+        let pattern = converter.convert(&annotation, None).unwrap();
+        assert_eq!(pattern.parameter_extractors[0].enum_name, None);
+        assert_eq!(
+            pattern.parameter_extractors[1].enum_name.as_deref(),
+            Some("RegistrationState")
+        );
     }
 
     #[test]
@@ -540,28 +572,22 @@ mod tests {
     }
 
     #[test]
-    fn test_build_action() {
+    fn test_action_from_documentation() {
         let converter = PatternConverter::new();
         let annotation = create_test_annotation();
 
-        let action = converter.build_action(&annotation);
-        assert!(action.is_some());
-
-        let action_text = action.unwrap();
-        assert!(action_text.contains("KB12345"));
-        assert!(action_text.contains("BUG67890"));
-        assert!(action_text.contains("1.1.0"));
+        let pattern = converter.convert(&annotation, None).unwrap();
+        assert_eq!(pattern.action.as_deref(), Some("Check logs for details"));
     }
 
     #[test]
-    fn test_build_tags() {
+    fn test_tags_from_categories() {
         let converter = PatternConverter::new();
         let annotation = create_test_annotation();
 
-        let tags = converter.build_tags(&annotation);
-        assert!(tags.contains(&"test".to_string()));
-        assert!(tags.contains(&"test-product".to_string()));
-        assert!(tags.contains(&"test-component".to_string()));
+        let pattern = converter.convert(&annotation, None).unwrap();
+        assert!(pattern.tags.contains(&"errors".to_string()));
+        assert!(pattern.tags.contains(&"test".to_string()));
     }
 
     #[test]
@@ -584,9 +610,9 @@ mod tests {
     fn test_inactive_pattern_filtering() {
         let converter = PatternConverter::new();
         let mut annotation = create_test_annotation();
-        annotation.active = false;
+        annotation.production = false;
 
-        let result = converter.convert(&annotation);
+        let result = converter.convert(&annotation, None);
         assert!(result.is_err());
     }
 
@@ -7,7 +7,7 @@ pub mod cache;
 pub mod client;
 pub mod converter;
 
-pub use cache::{CacheManager, CacheStats, PatternCache};
+pub use cache::{CacheManager, CacheStats, EnumTables, PatternCache};
 pub use client::{TagScoutAnnotation, TagScoutClient, TagScoutConfig, TagScoutError};
 pub use converter::{ConversionError, ConverterConfig, PatternConverter};
 
@@ -39,7 +39,8 @@ pub enum IntegrationError {
 }
 
 /// Sync mode configuration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SyncMode {
     /// Only use cached patterns, no network access
     OfflineOnly,
@@ -248,10 +249,23 @@ impl SyncService {
         let patterns_count = patterns.len();
 
         // Update cache
+        // Enum tables are optional; patterns still work without decoded values
+        let enums = match client.fetch_all_enums().await {
+            Ok(product_enums) => Some(merge_enums(product_enums)),
+            Err(e) => {
+                tracing::warn!("Failed to fetch TagScout enums: {}", e);
+                None
+            }
+        };
+
         let mut cache_manager = self.cache_manager.write().await;
+        if let Some(enums) = enums {
+            cache_manager.set_enums(enums);
+        }
+
         let pattern_tuples: Vec<_> = annotations_with_products
             .into_iter()
-            .zip(patterns.clone().into_iter())
+            .zip(patterns.clone())
             .map(|((_, annotation), pattern)| (annotation, pattern))
             .collect();
 
@@ -306,6 +320,25 @@ impl SyncService {
         Ok(cache.get_all_patterns().into_iter().cloned().collect())
     }
 
+    /// Get the cached TagScout annotation a pattern was converted from
+    pub async fn get_annotation(&self, pattern_id: &str) -> Option<TagScoutAnnotation> {
+        let cache_manager = self.cache_manager.read().await;
+        cache_manager
+            .get_cache()?
+            .get_pattern(pattern_id)
+            .map(|cached| cached.annotation.clone())
+    }
+
+    /// Get TagScout enum tables by name
+    pub async fn get_enums(&self) -> Result<EnumTables, IntegrationError> {
+        let cache_manager = self.cache_manager.read().await;
+        let cache = cache_manager
+            .get_cache()
+            .ok_or(IntegrationError::NotInitialized)?;
+
+        Ok(cache.enums.clone())
+    }
+
     /* Product-based filtering no longer supported -  collections are product-specific
     /// Get patterns by product
     pub async fn get_patterns_by_product(
@@ -391,14 +424,77 @@ impl SyncService {
         let cache_manager = self.cache_manager.read().await;
         cache_manager
             .get_cache()
-            .map_or(false, |c| c.metadata.pattern_count > 0)
+            .is_some_and(|c| c.metadata.pattern_count > 0)
+    }
+}
+
+/// Flatten the enum tables of all products into one table per name
+///
+/// Patterns reference enums by name only, so the first product defining a
+/// name keeps it; a different table under the same name is dropped with a
+/// warning.
+fn merge_enums(product_enums: Vec<(String, Vec<client::TagScoutEnum>)>) -> EnumTables {
+    let mut tables = EnumTables::new();
+    let mut owners: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+
+    for (product, enums) in product_enums {
+        for e in enums {
+            match tables.get(&e.name) {
+                None => {
+                    owners.insert(e.name.clone(), product.clone());
+                    tables.insert(e.name, e.r#enum);
+                }
+                Some(existing) if *existing == e.r#enum => {}
+                Some(_) => tracing::warn!(
+                    "Enum {} of {} conflicts with the one of {}, which is kept",
+                    e.name,
+                    product,
+                    owners[&e.name]
+                ),
+            }
+        }
     }
+
+    tables
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn tag_scout_enum(name: &str, values: &[(&str, &str)]) -> client::TagScoutEnum {
+        client::TagScoutEnum {
+            id: bson::oid::ObjectId::new(),
+            name: name.to_string(),
+            production: true,
+            r#enum: values
+                .iter()
+                .map(|(value, meaning)| (value.to_string(), meaning.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_merge_enums_keeps_first_product_on_conflict() {
+        let enums = merge_enums(vec![
+            (
+                "jabber".to_string(),
+                vec![tag_scout_enum("State", &[("1", "Registered")])],
+            ),
+            (
+                "webex".to_string(),
+                vec![
+                    tag_scout_enum("State", &[("1", "Connected")]),
+                    tag_scout_enum("Reason", &[("2", "Timeout")]),
+                ],
+            ),
+        ]);
+
+        assert_eq!(enums.len(), 2);
+        assert_eq!(enums["State"]["1"], "Registered");
+        assert_eq!(enums["Reason"]["2"], "Timeout");
+    }
+
     #[tokio::test]
     async fn test_sync_service_creation() {
         let config = SyncServiceConfig::default();
@@ -408,8 +504,10 @@ mod tests {
 
     #[tokio::test]
     async fn test_cache_first_mode() {
-        let mut config = SyncServiceConfig::default();
-        config.sync_mode = SyncMode::OfflineOnly;
+        let config = SyncServiceConfig {
+            sync_mode: SyncMode::OfflineOnly,
+            ..Default::default()
+        };
 
         let service = SyncService::new(config).await;
         assert!(service.is_ok());
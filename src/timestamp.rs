@@ -0,0 +1,204 @@
+//! Timestamp parsing for log lines
+//!
+//! Recognizes the timestamp formats commonly found in Jabber, Webex and
+//! system logs so detections and lines can be placed on a timeline.
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// ISO-style date and time: `2024-01-15 10:30:45,123`, `2024-01-15T10:30:45.123Z`
+    static ref ISO_DATETIME: Regex = Regex::new(
+        r"(\d{4})[-/](\d{2})[-/](\d{2})[T ](\d{2}):(\d{2}):(\d{2})(?:[.,](\d{1,9}))?"
+    )
+    .unwrap();
+
+    /// Syslog-style date and time: `Jan 15 10:30:45`
+    static ref SYSLOG_DATETIME: Regex = Regex::new(
+        r"\b(Jan|Feb|Mar|Apr|May|Jun|Jul|Aug|Sep|Oct|Nov|Dec)\s+(\d{1,2})\s+(\d{2}):(\d{2}):(\d{2})(?:[.,](\d{1,9}))?"
+    )
+    .unwrap();
+
+    /// Time of day only: `10:30:45.123`
+    static ref TIME_ONLY: Regex =
+        Regex::new(r"\b(\d{2}):(\d{2}):(\d{2})(?:[.,](\d{1,9}))?").unwrap();
+}
+
+/// A timestamp found in a log line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogTimestamp {
+    /// The timestamp text as it appears in the line
+    pub raw: String,
+
+    /// Parsed date and time (date defaults to 1970-01-01 when the log omits it)
+    pub datetime: NaiveDateTime,
+
+    /// Byte offset of the timestamp within the line
+    pub start: usize,
+
+    /// Whether the log line carried a date, not just a time of day
+    pub has_date: bool,
+}
+
+impl LogTimestamp {
+    /// Time of day formatted as `HH:MM:SS`
+    pub fn time_of_day(&self) -> String {
+        self.datetime.format("%H:%M:%S").to_string()
+    }
+}
+
+/// Find and parse the first timestamp in a log line
+///
+/// Formats are tried from most to least specific; a match that does not
+/// parse (e.g. month 13) is skipped rather than ending the search.
+pub fn extract_timestamp(line: &str) -> Option<LogTimestamp> {
+    iso_timestamp(line)
+        .or_else(|| syslog_timestamp(line))
+        .or_else(|| time_only_timestamp(line))
+}
+
+fn iso_timestamp(line: &str) -> Option<LogTimestamp> {
+    ISO_DATETIME.captures_iter(line).find_map(|cap| {
+        let date = NaiveDate::from_ymd_opt(
+            cap[1].parse().ok()?,
+            cap[2].parse().ok()?,
+            cap[3].parse().ok()?,
+        )?;
+        let time = parse_time(&cap[4], &cap[5], &cap[6], cap.get(7).map(|m| m.as_str()))?;
+        let whole = cap.get(0)?;
+        Some(LogTimestamp {
+            raw: whole.as_str().to_string(),
+            datetime: date.and_time(time),
+            start: whole.start(),
+            has_date: true,
+        })
+    })
+}
+
+fn syslog_timestamp(line: &str) -> Option<LogTimestamp> {
+    SYSLOG_DATETIME.captures_iter(line).find_map(|cap| {
+        let month = month_number(&cap[1])?;
+        // Syslog omits the year; use a fixed one so ordering within a file still works
+        let date = NaiveDate::from_ymd_opt(1970, month, cap[2].parse().ok()?)?;
+        let time = parse_time(&cap[3], &cap[4], &cap[5], cap.get(6).map(|m| m.as_str()))?;
+        let whole = cap.get(0)?;
+        Some(LogTimestamp {
+            raw: whole.as_str().to_string(),
+            datetime: date.and_time(time),
+            start: whole.start(),
+            has_date: true,
+        })
+    })
+}
+
+fn time_only_timestamp(line: &str) -> Option<LogTimestamp> {
+    TIME_ONLY.captures_iter(line).find_map(|cap| {
+        let time = parse_time(&cap[1], &cap[2], &cap[3], cap.get(4).map(|m| m.as_str()))?;
+        let whole = cap.get(0)?;
+        Some(LogTimestamp {
+            raw: whole.as_str().to_string(),
+            datetime: NaiveDate::default().and_time(time),
+            start: whole.start(),
+            has_date: false,
+        })
+    })
+}
+
+/// Parse a timestamp string previously captured from a log line
+pub fn parse_timestamp(text: &str) -> Option<NaiveDateTime> {
+    extract_timestamp(text).map(|ts| ts.datetime)
+}
+
+/// Format a duration in milliseconds for display (e.g. `+250ms`, `+1.5s`, `+2m 03s`)
+pub fn format_delta(millis: i64) -> String {
+    let sign = if millis < 0 { "-" } else { "+" };
+    let millis = millis.unsigned_abs();
+
+    if millis < 1_000 {
+        format!("{}{}ms", sign, millis)
+    } else if millis < 60_000 {
+        format!("{}{:.1}s", sign, millis as f64 / 1_000.0)
+    } else if millis < 3_600_000 {
+        format!(
+            "{}{}m {:02}s",
+            sign,
+            millis / 60_000,
+            (millis % 60_000) / 1_000
+        )
+    } else {
+        format!(
+            "{}{}h {:02}m",
+            sign,
+            millis / 3_600_000,
+            (millis % 3_600_000) / 60_000
+        )
+    }
+}
+
+fn parse_time(hour: &str, minute: &str, second: &str, fraction: Option<&str>) -> Option<NaiveTime> {
+    let nanos = match fraction {
+        Some(f) => {
+            let digits: String = f.chars().chain(std::iter::repeat('0')).take(9).collect();
+            digits.parse().ok()?
+        }
+        None => 0,
+    };
+
+    NaiveTime::from_hms_nano_opt(
+        hour.parse().ok()?,
+        minute.parse().ok()?,
+        second.parse().ok()?,
+        nanos,
+    )
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    let month = NaiveDate::parse_from_str(&format!("{} 1 2000", name), "%b %d %Y").ok()?;
+    Some(month.month())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso_timestamp() {
+        let ts = extract_timestamp("2024-01-15 10:30:45,123 ERROR [main] failed").unwrap();
+        assert_eq!(ts.raw, "2024-01-15 10:30:45,123");
+        assert_eq!(ts.time_of_day(), "10:30:45");
+        assert_eq!(ts.datetime.and_utc().timestamp_subsec_millis(), 123);
+        assert!(ts.has_date);
+    }
+
+    #[test]
+    fn test_syslog_and_time_only() {
+        let syslog = extract_timestamp("Jan  5 08:01:02 host sshd[42]: ok").unwrap();
+        assert_eq!(syslog.datetime.month(), 1);
+        assert_eq!(syslog.time_of_day(), "08:01:02");
+
+        let time_only = extract_timestamp("[12:00:01.5] INFO started").unwrap();
+        assert!(!time_only.has_date);
+        assert_eq!(time_only.datetime.and_utc().timestamp_subsec_millis(), 500);
+
+        assert!(extract_timestamp("no timestamp here").is_none());
+    }
+
+    #[test]
+    fn test_invalid_date_falls_through() {
+        let later = extract_timestamp("2024-13-45 10:30:45 retry at 2024-01-16 09:00:00").unwrap();
+        assert_eq!(later.raw, "2024-01-16 09:00:00");
+
+        let time_only = extract_timestamp("2024-13-45 10:30:45 INFO started").unwrap();
+        assert!(!time_only.has_date);
+        assert_eq!(time_only.time_of_day(), "10:30:45");
+    }
+
+    #[test]
+    fn test_format_delta() {
+        assert_eq!(format_delta(250), "+250ms");
+        assert_eq!(format_delta(1_500), "+1.5s");
+        assert_eq!(format_delta(123_000), "+2m 03s");
+        assert_eq!(format_delta(-30_000), "-30.0s");
+    }
+}
@@ -0,0 +1,221 @@
+//! Virtual Documents
+//!
+//! Read-only documents rendered by the server for URIs that do not exist on
+//! disk (e.g. TagScout pattern definitions). Clients fetch their content
+//! through the `logScout/virtualDocument` request.
+
+use crate::pattern_engine::Pattern;
+use crate::tagscout::TagScoutAnnotation;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use tower_lsp::lsp_types::Url;
+
+/// Custom request returning the content of a virtual document
+pub const VIRTUAL_DOCUMENT_METHOD: &str = "logScout/virtualDocument";
+
+/// URI scheme for rendered pattern definitions
+pub const PATTERN_SCHEME: &str = "logscout-pattern";
+
+/// Parameters of the `logScout/virtualDocument` request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualDocumentParams {
+    pub uri: Url,
+}
+
+/// Content of a virtual document
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualDocument {
+    pub uri: Url,
+    pub language_id: String,
+    pub content: String,
+}
+
+/// URI of the rendered definition of a pattern
+pub fn pattern_uri(id: &str) -> Url {
+    let mut uri = Url::parse(&format!("{}:/", PATTERN_SCHEME)).unwrap();
+    uri.set_path(&format!("/{}.md", id));
+    uri
+}
+
+/// Pattern ID referenced by a `logscout-pattern:` URI
+pub fn pattern_id_from_uri(uri: &Url) -> Option<String> {
+    if uri.scheme() != PATTERN_SCHEME {
+        return None;
+    }
+
+    let name = uri.path().trim_start_matches('/');
+    let id = name.strip_suffix(".md").unwrap_or(name);
+    percent_decode(id)
+}
+
+/// Render a pattern definition as Markdown
+///
+/// Uses the cached TagScout annotation when available so the document shows
+/// the annotation exactly as authored (all regexes, documentation, notes).
+pub fn render_pattern(pattern: &Pattern, annotation: Option<&TagScoutAnnotation>) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# {}\n", pattern.name);
+    let _ = writeln!(out, "| Field | Value |");
+    let _ = writeln!(out, "|---|---|");
+    let _ = writeln!(out, "| ID | `{}` |", pattern.id);
+    if let Some(service) = &pattern.service {
+        let _ = writeln!(out, "| Product | {} |", service);
+    }
+    let _ = writeln!(out, "| Category | {} |", pattern.category);
+    let _ = writeln!(out, "| Severity | {} |", pattern.severity.as_str());
+    if let Some(source) = &pattern.source {
+        let _ = writeln!(
+            out,
+            "| Source | {}:{} |",
+            source.path.display(),
+            source.line + 1
+        );
+    }
+
+    let regexes = match annotation {
+        Some(annotation) => annotation.regexes.clone(),
+        None => vec![pattern.pattern.clone()],
+    };
+    let _ = writeln!(out, "\n## Regexes\n");
+    for regex in regexes {
+        let _ = writeln!(out, "```regex\n{}\n```", regex);
+    }
+
+    let template = annotation
+        .map(|a| a.template.as_str())
+        .unwrap_or(&pattern.annotation);
+    let _ = writeln!(out, "\n## Template\n\n{}", template);
+
+    if !pattern.parameter_extractors.is_empty() {
+        let _ = writeln!(out, "\n## Parameters\n");
+        for extractor in &pattern.parameter_extractors {
+            let _ = write!(out, "- `{}`: `{}`", extractor.name, extractor.regex);
+            if let Some(enum_name) = &extractor.enum_name {
+                let _ = write!(out, " (enum `{}`)", enum_name);
+            }
+            out.push('\n');
+        }
+    }
+
+    if let Some(annotation) = annotation {
+        if !annotation.raw_data.is_empty() {
+            let _ = writeln!(out, "\n## Example\n\n```log\n{}\n```", annotation.raw_data);
+        }
+        if !annotation.documentation.is_empty() {
+            let _ = writeln!(out, "\n## Documentation\n\n{}", annotation.documentation);
+        }
+        if !annotation.internal_notes.is_empty() {
+            let _ = writeln!(out, "\n## Internal Notes\n\n{}", annotation.internal_notes);
+        }
+    } else if let Some(action) = &pattern.action {
+        let _ = writeln!(out, "\n## Action\n\n{}", action);
+    }
+
+    out
+}
+
+/// Decode `%XX` escapes in a URI path
+pub(crate) fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::ParameterExtractor;
+
+    fn pattern(id: &str) -> Pattern {
+        Pattern {
+            name: "Registration failed".to_string(),
+            annotation: "Registration failed with {{ CODE }}".to_string(),
+            category: "sip".to_string(),
+            service: Some("jabber_prt".to_string()),
+            parameter_extractors: vec![ParameterExtractor {
+                name: "CODE".to_string(),
+                regex: r"failed (\d+)".to_string(),
+                enum_name: Some("SipCode".to_string()),
+            }],
+            ..Pattern::test(id, r"REGISTER failed (\d+)")
+        }
+    }
+
+    #[test]
+    fn test_pattern_uri_round_trip() {
+        let uri = pattern_uri("65a1b2c3d4e5f6a7b8c9d0e1");
+        assert_eq!(
+            uri.as_str(),
+            "logscout-pattern:/65a1b2c3d4e5f6a7b8c9d0e1.md"
+        );
+        assert_eq!(
+            pattern_id_from_uri(&uri).as_deref(),
+            Some("65a1b2c3d4e5f6a7b8c9d0e1")
+        );
+
+        let spaced = pattern_uri("my pattern");
+        assert_eq!(pattern_id_from_uri(&spaced).as_deref(), Some("my pattern"));
+
+        let file = Url::parse("file:///tmp/a.md").unwrap();
+        assert_eq!(pattern_id_from_uri(&file), None);
+    }
+
+    #[test]
+    fn test_render_pattern_without_annotation() {
+        let content = render_pattern(&pattern("sip-register"), None);
+        assert!(content.starts_with("# Registration failed"));
+        assert!(content.contains("| Product | jabber_prt |"));
+        assert!(content.contains("REGISTER failed (\\d+)"));
+        assert!(content.contains("- `CODE`: `failed (\\d+)` (enum `SipCode`)"));
+    }
+
+    #[test]
+    fn test_render_pattern_with_annotation() {
+        let annotation = TagScoutAnnotation {
+            id: bson::oid::ObjectId::new(),
+            raw_data: "REGISTER failed 403".to_string(),
+            regexes: vec![
+                r"REGISTER failed (\d+)".to_string(),
+                r"Registration rejected (\d+)".to_string(),
+            ],
+            severity: "error".to_string(),
+            category: vec!["sip".to_string()],
+            template: "SIP registration failed: {{ CODE }}".to_string(),
+            production: true,
+            content: false,
+            documentation: "Check the SIP credentials".to_string(),
+            internal_notes: "Seen after password rotation".to_string(),
+            multiline: None,
+            external: false,
+            borg: false,
+            parameters: Vec::new(),
+        };
+
+        let content = render_pattern(&pattern("sip-register"), Some(&annotation));
+        assert!(content.contains(
+            "## Regexes\n\n```regex\nREGISTER failed (\\d+)\n```\n```regex\nRegistration rejected (\\d+)\n```"
+        ));
+        assert!(content.contains("## Template\n\nSIP registration failed: {{ CODE }}"));
+        assert!(content.contains("## Parameters\n\n- `CODE`: `failed (\\d+)` (enum `SipCode`)"));
+        assert!(content.contains("## Example\n\n```log\nREGISTER failed 403\n```"));
+        assert!(content.contains("## Documentation\n\nCheck the SIP credentials"));
+        assert!(content.contains("## Internal Notes\n\nSeen after password rotation"));
+        assert!(!content.contains("Registration failed with {{ CODE }}"));
+    }
+}
@@ -0,0 +1,214 @@
+//! Workspace Symbol Provider
+//!
+//! Searches detections across open and workspace logs by pattern name,
+//! category, merged template text or extracted parameter value.
+
+use crate::pattern_engine::Detection;
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{Location, Position, Range, SymbolInformation, SymbolKind, Url};
+
+/// Extensions of files treated as logs when scanning workspace folders
+pub const LOG_EXTENSIONS: [&str; 3] = ["log", "txt", "out"];
+
+/// Upper bound on symbols returned for one query
+pub const MAX_SYMBOLS: usize = 1_000;
+
+/// Directories never scanned for logs
+const SKIPPED_DIRS: [&str; 3] = ["node_modules", "target", "build"];
+
+/// Find log files under the workspace folders
+///
+/// Hidden directories and common build output directories are skipped.
+pub fn find_log_files(roots: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for root in roots {
+        collect_log_files(root, &mut files);
+    }
+
+    files.sort();
+    files.dedup();
+    files
+}
+
+fn collect_log_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                collect_log_files(&path, files);
+            }
+        } else if file_type.is_file()
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| LOG_EXTENSIONS.contains(&ext))
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Check whether a detection matches a workspace symbol query
+///
+/// Every whitespace-separated term must appear (case-insensitively) in the
+/// pattern name, category, product, merged template or a parameter value.
+/// An empty query matches everything. The merged template is only asked
+/// for when the other fields leave a term unmatched.
+pub fn matches_query<'a>(
+    detection: &Detection,
+    merged_template: impl FnOnce() -> &'a str,
+    query: &str,
+) -> bool {
+    let pattern = &detection.pattern;
+    let mut fields = vec![pattern.name.to_lowercase(), pattern.category.to_lowercase()];
+    if let Some(service) = &pattern.service {
+        fields.push(service.to_lowercase());
+    }
+    fields.extend(detection.field_values.values().map(|v| v.to_lowercase()));
+
+    let query = query.to_lowercase();
+    let unmatched: Vec<&str> = query
+        .split_whitespace()
+        .filter(|term| !fields.iter().any(|field| field.contains(term)))
+        .collect();
+    if unmatched.is_empty() {
+        return true;
+    }
+
+    let merged_template = merged_template().to_lowercase();
+    unmatched.iter().all(|term| merged_template.contains(term))
+}
+
+/// Build the symbols for the detections of one log matching `query`
+pub fn detection_symbols(
+    uri: &Url,
+    detections: &[Detection],
+    query: &str,
+    merge: impl Fn(&Detection) -> String,
+) -> Vec<SymbolInformation> {
+    detections
+        .iter()
+        .filter_map(|detection| {
+            let merged = OnceCell::new();
+            let merged_text = || merged.get_or_init(|| merge(detection)).as_str();
+            if !matches_query(detection, merged_text, query) {
+                return None;
+            }
+
+            let merged_template = merged.into_inner().unwrap_or_else(|| merge(detection));
+            let name = if merged_template.is_empty() {
+                detection.pattern.name.clone()
+            } else {
+                merged_template
+            };
+            let line = detection.line_number as u32;
+            let (start, end) = detection.column_range;
+
+            #[allow(deprecated)]
+            Some(SymbolInformation {
+                name,
+                kind: SymbolKind::EVENT,
+                tags: None,
+                deprecated: None,
+                location: Location {
+                    uri: uri.clone(),
+                    range: Range {
+                        start: Position {
+                            line,
+                            character: start as u32,
+                        },
+                        end: Position {
+                            line,
+                            character: end as u32,
+                        },
+                    },
+                },
+                container_name: Some(format!(
+                    "{} · {}",
+                    detection.pattern.name, detection.pattern.category
+                )),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_engine::{detect_lines, ParameterExtractor, Pattern, Severity};
+
+    fn detect(text: &str) -> Vec<Detection> {
+        detect_lines(
+            vec![Pattern {
+                name: "HTTP response".to_string(),
+                annotation: "HTTP {{ STATUS }}".to_string(),
+                severity: Severity::Warning,
+                category: "http".to_string(),
+                service: Some("auth".to_string()),
+                parameter_extractors: vec![ParameterExtractor {
+                    name: "STATUS".to_string(),
+                    regex: r"HTTP/1\.1 (\d{3})".to_string(),
+                    enum_name: None,
+                }],
+                ..Pattern::test("http-status", r"HTTP/1\.1 \d{3}")
+            }],
+            text,
+        )
+    }
+
+    #[test]
+    fn test_query_matches_parameters_and_product() {
+        let detections = detect("GET /token HTTP/1.1 200\nGET /token HTTP/1.1 403");
+        let uri = Url::parse("file:///auth.log").unwrap();
+        let merge = |d: &Detection| format!("HTTP {}", d.field_values["STATUS"]);
+
+        let symbols = detection_symbols(&uri, &detections, "403 auth", merge);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "HTTP 403");
+        assert_eq!(symbols[0].location.range.start.line, 1);
+        assert_eq!(
+            symbols[0].container_name.as_deref(),
+            Some("HTTP response · http")
+        );
+
+        assert_eq!(detection_symbols(&uri, &detections, "", merge).len(), 2);
+        assert!(detection_symbols(&uri, &detections, "404", merge).is_empty());
+    }
+
+    #[test]
+    fn test_query_merges_template_only_when_needed() {
+        let detections = detect("GET /token HTTP/1.1 403");
+
+        assert!(matches_query(&detections[0], || unreachable!(), "403 auth"));
+        assert!(matches_query(
+            &detections[0],
+            || "http 403 forbidden",
+            "forbidden"
+        ));
+    }
+
+    #[test]
+    fn test_find_log_files_skips_hidden_dirs() {
+        let root = std::env::temp_dir().join(format!("logscout-symbols-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("logs")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("logs/jabber.log"), "").unwrap();
+        std::fs::write(root.join(".git/ignored.log"), "").unwrap();
+        std::fs::write(root.join("notes.md"), "").unwrap();
+
+        let files = find_log_files(std::slice::from_ref(&root));
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(files, vec![root.join("logs/jabber.log")]);
+    }
+}